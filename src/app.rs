@@ -1,21 +1,59 @@
-use crate::server::ServerOptions;
+use crate::server::{FsyncPolicy, ServerOptions};
+use std::time::Duration;
 
 #[derive(Clone, Copy)]
 enum ArgState {
     Normal,
     Port,
     ReplicaOf,
+    Dir,
+    DbFilename,
+    AppendOnly,
+    AppendFilename,
+    AppendFsync,
+    AutoAofRewritePercentage,
+    AutoAofRewriteMinSize,
+    ReplBacklogSize,
+    ReplicaReadOnly,
+    MinReplicasToWrite,
+    MinReplicasMaxLag,
 }
 
 pub fn parse_options() -> ServerOptions {
     let mut state = ArgState::Normal;
     let mut port: Option<u16> = None;
     let mut replica_of: Option<String> = None;
+    let mut dir: Option<String> = None;
+    let mut dbfilename: Option<String> = None;
+    let mut appendonly = false;
+    let mut appendfilename: Option<String> = None;
+    let mut appendfsync = FsyncPolicy::EverySec;
+    let mut auto_aof_rewrite_percentage = DEFAULT_AUTO_AOF_REWRITE_PERCENTAGE;
+    let mut auto_aof_rewrite_min_size = DEFAULT_AUTO_AOF_REWRITE_MIN_SIZE;
+    let mut repl_backlog_size = DEFAULT_REPL_BACKLOG_SIZE;
+    let mut replica_read_only = true;
+    let mut min_replicas_to_write = 0;
+    let mut min_replicas_max_lag = DEFAULT_MIN_REPLICAS_MAX_LAG;
 
     for arg in std::env::args().skip(1) {
         match (state, arg.as_str()) {
             (ArgState::Normal, "--port") => state = ArgState::Port,
             (ArgState::Normal, "--replicaof") => state = ArgState::ReplicaOf,
+            (ArgState::Normal, "--dir") => state = ArgState::Dir,
+            (ArgState::Normal, "--dbfilename") => state = ArgState::DbFilename,
+            (ArgState::Normal, "--appendonly") => state = ArgState::AppendOnly,
+            (ArgState::Normal, "--appendfilename") => state = ArgState::AppendFilename,
+            (ArgState::Normal, "--appendfsync") => state = ArgState::AppendFsync,
+            (ArgState::Normal, "--auto-aof-rewrite-percentage") => {
+                state = ArgState::AutoAofRewritePercentage
+            }
+            (ArgState::Normal, "--auto-aof-rewrite-min-size") => {
+                state = ArgState::AutoAofRewriteMinSize
+            }
+            (ArgState::Normal, "--repl-backlog-size") => state = ArgState::ReplBacklogSize,
+            (ArgState::Normal, "--replica-read-only") => state = ArgState::ReplicaReadOnly,
+            (ArgState::Normal, "--min-replicas-to-write") => state = ArgState::MinReplicasToWrite,
+            (ArgState::Normal, "--min-replicas-max-lag") => state = ArgState::MinReplicasMaxLag,
             (ArgState::Port, value) => {
                 port = value.parse().ok();
                 state = ArgState::Normal;
@@ -24,6 +62,74 @@ pub fn parse_options() -> ServerOptions {
                 replica_of = Some(value.replace(' ', ":"));
                 state = ArgState::Normal;
             }
+            (ArgState::Dir, value) => {
+                dir = Some(value.to_string());
+                state = ArgState::Normal;
+            }
+            (ArgState::DbFilename, value) => {
+                dbfilename = Some(value.to_string());
+                state = ArgState::Normal;
+            }
+            (ArgState::AppendOnly, value) => {
+                match parse_yes_no(value) {
+                    Some(value) => appendonly = value,
+                    None => eprintln!("ignoring invalid appendonly value: {}", value),
+                }
+                state = ArgState::Normal;
+            }
+            (ArgState::AppendFilename, value) => {
+                appendfilename = Some(value.to_string());
+                state = ArgState::Normal;
+            }
+            (ArgState::AppendFsync, value) => {
+                match value.parse() {
+                    Ok(policy) => appendfsync = policy,
+                    Err(err) => eprintln!("ignoring {}", err),
+                }
+                state = ArgState::Normal;
+            }
+            (ArgState::AutoAofRewritePercentage, value) => {
+                match value.parse() {
+                    Ok(value) => auto_aof_rewrite_percentage = value,
+                    Err(_) => eprintln!("ignoring invalid auto-aof-rewrite-percentage: {}", value),
+                }
+                state = ArgState::Normal;
+            }
+            (ArgState::AutoAofRewriteMinSize, value) => {
+                match parse_memory(value) {
+                    Some(value) => auto_aof_rewrite_min_size = value,
+                    None => eprintln!("ignoring invalid auto-aof-rewrite-min-size: {}", value),
+                }
+                state = ArgState::Normal;
+            }
+            (ArgState::ReplBacklogSize, value) => {
+                match parse_memory(value).and_then(|value| usize::try_from(value).ok()) {
+                    Some(value) => repl_backlog_size = value,
+                    None => eprintln!("ignoring invalid repl-backlog-size: {}", value),
+                }
+                state = ArgState::Normal;
+            }
+            (ArgState::ReplicaReadOnly, value) => {
+                match parse_yes_no(value) {
+                    Some(value) => replica_read_only = value,
+                    None => eprintln!("ignoring invalid replica-read-only value: {}", value),
+                }
+                state = ArgState::Normal;
+            }
+            (ArgState::MinReplicasToWrite, value) => {
+                match value.parse() {
+                    Ok(value) => min_replicas_to_write = value,
+                    Err(_) => eprintln!("ignoring invalid min-replicas-to-write: {}", value),
+                }
+                state = ArgState::Normal;
+            }
+            (ArgState::MinReplicasMaxLag, value) => {
+                match value.parse() {
+                    Ok(value) => min_replicas_max_lag = Duration::from_secs(value),
+                    Err(_) => eprintln!("ignoring invalid min-replicas-max-lag: {}", value),
+                }
+                state = ArgState::Normal;
+            }
             (_, value) => {
                 eprintln!("ignoring invalid argument: {}", value)
             }
@@ -33,7 +139,73 @@ pub fn parse_options() -> ServerOptions {
     ServerOptions {
         port: port.unwrap_or(DEFAULT_PORT),
         replica_of,
+        dir: dir.unwrap_or_else(|| DEFAULT_DIR.to_string()),
+        dbfilename: dbfilename.unwrap_or_else(|| DEFAULT_DBFILENAME.to_string()),
+        appendonly,
+        appendfilename: appendfilename.unwrap_or_else(|| DEFAULT_APPENDFILENAME.to_string()),
+        appendfsync,
+        auto_aof_rewrite_percentage,
+        auto_aof_rewrite_min_size,
+        repl_backlog_size,
+        replica_read_only,
+        min_replicas_to_write,
+        min_replicas_max_lag,
+    }
+}
+
+/// Parses a size in bytes, optionally suffixed by a unit such as `kb`, `mb` or `gb`.
+fn parse_memory(value: &str) -> Option<u64> {
+    let value = value.to_lowercase();
+    let digits = value.trim_end_matches(|ch: char| ch.is_ascii_alphabetic());
+
+    let multiplier = match &value[digits.len()..] {
+        "" | "b" => 1,
+        "k" => 1000,
+        "kb" => 1024,
+        "m" => 1000 * 1000,
+        "mb" => 1024 * 1024,
+        "g" => 1000 * 1000 * 1000,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+
+    digits.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+fn parse_yes_no(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
     }
 }
 
 static DEFAULT_PORT: u16 = 6379;
+static DEFAULT_DIR: &str = ".";
+static DEFAULT_DBFILENAME: &str = "dump.rdb";
+static DEFAULT_APPENDFILENAME: &str = "appendonly.aof";
+static DEFAULT_AUTO_AOF_REWRITE_PERCENTAGE: u64 = 100;
+static DEFAULT_AUTO_AOF_REWRITE_MIN_SIZE: u64 = 64 * 1024 * 1024;
+static DEFAULT_REPL_BACKLOG_SIZE: usize = 1024 * 1024;
+static DEFAULT_MIN_REPLICAS_MAX_LAG: Duration = Duration::from_secs(10);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_memory_units() {
+        assert_eq!(parse_memory("100"), Some(100));
+        assert_eq!(parse_memory("1k"), Some(1000));
+        assert_eq!(parse_memory("1kb"), Some(1024));
+        assert_eq!(parse_memory("64mb"), Some(64 * 1024 * 1024));
+        assert_eq!(parse_memory("1GB"), Some(1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn parse_memory_invalid() {
+        assert_eq!(parse_memory("mb"), None);
+        assert_eq!(parse_memory("10tb"), None);
+        assert_eq!(parse_memory("-1"), None);
+    }
+}
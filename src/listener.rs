@@ -44,17 +44,21 @@ async fn handle_client(server: ServerHandle, socket: TcpStream) {
     let (socket_reader, socket_writer) = socket.into_split();
     let (values_sender, values_receiver) = mpsc::unbounded_channel::<Vec<Value>>();
 
-    let reader_handle = tokio::spawn(async move {
+    let mut reader_handle = tokio::spawn(async move {
         handle_client_reader(server, address, socket_reader, values_sender).await
     });
 
-    let writer_handle =
+    let mut writer_handle =
         tokio::spawn(async move { handle_client_writer(socket_writer, values_receiver).await });
 
     tokio::select! {
-        _ = reader_handle => {}
-        _ = writer_handle => {}
+        _ = &mut reader_handle => {}
+        _ = &mut writer_handle => {}
     }
+
+    // the server notices the connection is gone once the writer drops its receiver
+    reader_handle.abort();
+    writer_handle.abort();
 }
 
 async fn handle_client_reader<R>(
@@ -67,24 +71,53 @@ async fn handle_client_reader<R>(
 {
     let mut reader = CommandReader::new(ValueReader::new(BufReader::new(socket_reader)));
 
-    loop {
+    // announced by replicas with REPLCONF listening-port before they PSYNC
+    let mut listening_port = address.port();
+
+    let synced = loop {
         match reader.read().await {
             Ok(command) => {
-                if let Err(err) = server.send(command.clone(), values_sender.clone()) {
-                    println!("failed to send command to server: {:?}", err);
-                    break;
-                }
-
-                if let Command::Psync { .. } = command {
-                    if let Err(err) = server.add_replica(address, values_sender.clone()) {
-                        println!("failed to add replica: {:?}", err);
+                let result = match command {
+                    Command::Psync {
+                        master_replid,
+                        master_repl_offset,
+                        ..
+                    } => {
+                        let result = server.sync_replica(
+                            address,
+                            listening_port,
+                            master_replid,
+                            master_repl_offset,
+                            values_sender,
+                        );
+
+                        if let Err(err) = &result {
+                            println!("failed to send PSYNC to server: {:?}", err);
+                        }
+
+                        break result.is_ok();
                     }
+                    Command::Replconf {
+                        ref key, ref value, ..
+                    } if key.eq_ignore_ascii_case("listening-port".as_bytes()) => {
+                        if let Ok(port) = String::from_utf8_lossy(value).parse() {
+                            listening_port = port;
+                        }
+
+                        server.send(command, values_sender.clone())
+                    }
+                    command => server.send(command, values_sender.clone()),
+                };
+
+                if let Err(err) = result {
+                    println!("failed to send command to server: {:?}", err);
+                    break false;
                 }
             }
             Err(CommandReadError::Invalid(values)) => {
                 if let Err(err) = values_sender.send(values) {
                     println!("failed to send invalid response to client: {:?}", err);
-                    break;
+                    break false;
                 }
             }
             Err(CommandReadError::Stop(cause)) => {
@@ -92,6 +125,53 @@ async fn handle_client_reader<R>(
                     println!("fatal error reading command: {}", cause);
                 }
 
+                break false;
+            }
+        }
+    };
+
+    // after PSYNC the server holds the only sender for this connection, so it closes
+    // whenever the server drops the replica
+    if synced {
+        handle_replica_reader(server, address, reader).await;
+    }
+}
+
+/// Reads from a connection that completed PSYNC: replicas only send REPLCONF ACK from then on.
+async fn handle_replica_reader<R>(
+    server: ServerHandle,
+    address: SocketAddr,
+    mut reader: CommandReader<BufReader<R>>,
+) where
+    R: AsyncRead + Unpin,
+{
+    loop {
+        match reader.read().await {
+            Ok(Command::Replconf { key, value, .. })
+                if key.eq_ignore_ascii_case("ACK".as_bytes()) =>
+            {
+                let Ok(offset) = String::from_utf8_lossy(&value).parse() else {
+                    println!("ignoring invalid REPLCONF ACK offset from {}", address);
+                    continue;
+                };
+
+                if let Err(err) = server.replica_ack(address, offset) {
+                    println!("failed to send ACK to server: {:?}", err);
+                    break;
+                }
+            }
+            Ok(command) => println!("ignoring command from replica {}: {:?}", address, command),
+            Err(CommandReadError::Invalid(values)) => {
+                println!(
+                    "ignoring invalid command from replica {}: {:?}",
+                    address, values
+                );
+            }
+            Err(CommandReadError::Stop(cause)) => {
+                if let Some(cause) = cause {
+                    println!("fatal error reading from replica: {}", cause);
+                }
+
                 break;
             }
         }
@@ -3,12 +3,10 @@ mod listener;
 mod protocol;
 mod server;
 
-use std::io;
-
 #[tokio::main]
-async fn main() -> Result<(), io::Error> {
+async fn main() -> anyhow::Result<()> {
     let options = app::parse_options();
-    let server = server::start(options.clone());
+    let server = server::start(options.clone()).await?;
     listener::start(options, server).await??;
     Ok(())
 }
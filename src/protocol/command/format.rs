@@ -1,4 +1,8 @@
-use crate::protocol::{Command, Value};
+use crate::protocol::{
+    Aggregate, Command, GetexExpiry, LexBound, ListEnd, ScoreBound, SetCondition, SetExpiry,
+    StreamId, StreamTrim, Value, XaddId, XreadId, XreadgroupId, ZrangeBy,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 pub fn to_value(command: &Command) -> Value {
     match command {
@@ -11,19 +15,106 @@ pub fn to_value(command: &Command) -> Value {
         Command::Set {
             key,
             value,
-            expiry: None,
+            condition,
+            expiry,
+            get,
             ..
-        } => Value::command("SET", &[key, value]),
-        Command::Set {
+        } => {
+            let mut args = vec![key.clone(), value.clone()];
+
+            match condition {
+                Some(SetCondition::Nx) => args.push("NX".as_bytes().to_vec()),
+                Some(SetCondition::Xx) => args.push("XX".as_bytes().to_vec()),
+                None => {}
+            }
+
+            match expiry {
+                Some(SetExpiry::At(deadline)) => {
+                    args.push("PXAT".as_bytes().to_vec());
+                    args.push(unix_millis(*deadline).to_string().into_bytes());
+                }
+                Some(SetExpiry::KeepTtl) => args.push("KEEPTTL".as_bytes().to_vec()),
+                None => {}
+            }
+
+            if *get {
+                args.push("GET".as_bytes().to_vec());
+            }
+
+            Value::command("SET", &args.iter().collect::<Vec<_>>())
+        }
+        Command::Incr { key, .. } => Value::command("INCR", &[key]),
+        Command::Decr { key, .. } => Value::command("DECR", &[key]),
+        Command::Incrby { key, increment, .. } => {
+            let increment = increment.to_string().into_bytes();
+            Value::command("INCRBY", &[key, &increment])
+        }
+        Command::Decrby { key, decrement, .. } => {
+            let decrement = decrement.to_string().into_bytes();
+            Value::command("DECRBY", &[key, &decrement])
+        }
+        Command::Incrbyfloat { key, increment, .. } => {
+            let increment = increment.to_string().into_bytes();
+            Value::command("INCRBYFLOAT", &[key, &increment])
+        }
+        Command::Append { key, value, .. } => Value::command("APPEND", &[key, value]),
+        Command::Strlen { key, .. } => Value::command("STRLEN", &[key]),
+        Command::Getrange {
+            key, start, end, ..
+        } => {
+            let start = start.to_string().into_bytes();
+            let end = end.to_string().into_bytes();
+            Value::command("GETRANGE", &[key, &start, &end])
+        }
+        Command::Setrange {
+            key, offset, value, ..
+        } => {
+            let offset = offset.to_string().into_bytes();
+            Value::command("SETRANGE", &[key, &offset, value])
+        }
+        Command::Getdel { key, .. } => Value::command("GETDEL", &[key]),
+        Command::Getex { key, expiry, .. } => match expiry {
+            Some(GetexExpiry::At(deadline)) => {
+                let pxat = "PXAT".as_bytes().to_vec();
+                let deadline = unix_millis(*deadline).to_string().into_bytes();
+                Value::command("GETEX", &[key, &pxat, &deadline])
+            }
+            Some(GetexExpiry::Persist) => {
+                Value::command("GETEX", &[key, &"PERSIST".as_bytes().to_vec()])
+            }
+            None => Value::command("GETEX", &[key]),
+        },
+        Command::Getset { key, value, .. } => Value::command("GETSET", &[key, value]),
+        Command::Mget { keys, .. } => Value::command("MGET", &keys.iter().collect::<Vec<_>>()),
+        Command::Mset { pairs, .. } => pairs_command("MSET", pairs),
+        Command::Msetnx { pairs, .. } => pairs_command("MSETNX", pairs),
+        Command::Setnx { key, value, .. } => Value::command("SETNX", &[key, value]),
+        Command::Expire {
             key,
-            value,
-            expiry: Some(expiry),
+            deadline,
+            flags,
             ..
         } => {
-            let px = "PX".as_bytes().to_vec();
-            let expiry = expiry.as_millis().to_string().into_bytes();
-            Value::command("SET", &[key, value, &px, &expiry])
+            let mut args = vec![key.clone(), unix_millis(*deadline).to_string().into_bytes()];
+
+            for (name, set) in [
+                ("NX", flags.nx),
+                ("XX", flags.xx),
+                ("GT", flags.gt),
+                ("LT", flags.lt),
+            ] {
+                if set {
+                    args.push(name.as_bytes().to_vec());
+                }
+            }
+
+            Value::command("PEXPIREAT", &args.iter().collect::<Vec<_>>())
         }
+        Command::Ttl { key, .. } => Value::command("TTL", &[key]),
+        Command::Pttl { key, .. } => Value::command("PTTL", &[key]),
+        Command::Expiretime { key, .. } => Value::command("EXPIRETIME", &[key]),
+        Command::Pexpiretime { key, .. } => Value::command("PEXPIRETIME", &[key]),
+        Command::Persist { key, .. } => Value::command("PERSIST", &[key]),
         Command::Info { sections, .. } => {
             Value::command("INFO", &sections.iter().collect::<Vec<_>>())
         }
@@ -54,13 +145,746 @@ pub fn to_value(command: &Command) -> Value {
             let timeout = timeout.to_string().into_bytes();
             Value::command("WAIT", &[&replicas, &timeout])
         }
+        Command::Save { .. } => Value::command("SAVE", &[]),
+        Command::Bgsave { .. } => Value::command("BGSAVE", &[]),
+        Command::Lastsave { .. } => Value::command("LASTSAVE", &[]),
+        Command::Bgrewriteaof { .. } => Value::command("BGREWRITEAOF", &[]),
+        Command::Role { .. } => Value::command("ROLE", &[]),
+        Command::Replicaof { master: None, .. } => Value::command_str("REPLICAOF", &["NO", "ONE"]),
+        Command::Replicaof {
+            master: Some((host, port)),
+            ..
+        } => Value::command_str("REPLICAOF", &[host, &port.to_string()]),
+        Command::Type { key, .. } => Value::command("TYPE", &[key]),
+        Command::Lpush { key, elements, .. } => list_command("LPUSH", key, elements),
+        Command::Rpush { key, elements, .. } => list_command("RPUSH", key, elements),
+        Command::Lpop { key, count, .. } => pop_command("LPOP", key, *count),
+        Command::Rpop { key, count, .. } => pop_command("RPOP", key, *count),
+        Command::Lrange {
+            key, start, stop, ..
+        } => {
+            let start = start.to_string().into_bytes();
+            let stop = stop.to_string().into_bytes();
+            Value::command("LRANGE", &[key, &start, &stop])
+        }
+        Command::Llen { key, .. } => Value::command("LLEN", &[key]),
+        Command::Lindex { key, index, .. } => {
+            let index = index.to_string().into_bytes();
+            Value::command("LINDEX", &[key, &index])
+        }
+        Command::Lset {
+            key,
+            index,
+            element,
+            ..
+        } => {
+            let index = index.to_string().into_bytes();
+            Value::command("LSET", &[key, &index, element])
+        }
+        Command::Lrem {
+            key,
+            count,
+            element,
+            ..
+        } => {
+            let count = count.to_string().into_bytes();
+            Value::command("LREM", &[key, &count, element])
+        }
+        Command::Ltrim {
+            key, start, stop, ..
+        } => {
+            let start = start.to_string().into_bytes();
+            let stop = stop.to_string().into_bytes();
+            Value::command("LTRIM", &[key, &start, &stop])
+        }
+        Command::Linsert {
+            key,
+            before,
+            pivot,
+            element,
+            ..
+        } => {
+            let position = if *before { "BEFORE" } else { "AFTER" };
+            let position = position.as_bytes().to_vec();
+            Value::command("LINSERT", &[key, &position, pivot, element])
+        }
+        Command::Lmove {
+            source,
+            destination,
+            from,
+            to,
+            ..
+        } => {
+            let from = list_end(*from).as_bytes().to_vec();
+            let to = list_end(*to).as_bytes().to_vec();
+            Value::command("LMOVE", &[source, destination, &from, &to])
+        }
+        Command::Blpop { keys, timeout, .. } => blocking_pop_command("BLPOP", keys, timeout),
+        Command::Brpop { keys, timeout, .. } => blocking_pop_command("BRPOP", keys, timeout),
+        Command::Blmove {
+            source,
+            destination,
+            from,
+            to,
+            timeout,
+            ..
+        } => {
+            let from = list_end(*from).as_bytes().to_vec();
+            let to = list_end(*to).as_bytes().to_vec();
+            let timeout = timeout.as_secs_f64().to_string().into_bytes();
+            Value::command("BLMOVE", &[source, destination, &from, &to, &timeout])
+        }
+        Command::Hset { key, pairs, .. } => {
+            let mut args = vec![key];
+            pairs
+                .iter()
+                .for_each(|(field, value)| args.extend([field, value]));
+            Value::command("HSET", &args)
+        }
+        Command::Hget { key, field, .. } => Value::command("HGET", &[key, field]),
+        Command::Hmget { key, fields, .. } => list_command("HMGET", key, fields),
+        Command::Hdel { key, fields, .. } => list_command("HDEL", key, fields),
+        Command::Hgetall { key, .. } => Value::command("HGETALL", &[key]),
+        Command::Hincrby {
+            key,
+            field,
+            increment,
+            ..
+        } => {
+            let increment = increment.to_string().into_bytes();
+            Value::command("HINCRBY", &[key, field, &increment])
+        }
+        Command::Hincrbyfloat {
+            key,
+            field,
+            increment,
+            ..
+        } => {
+            let increment = increment.to_string().into_bytes();
+            Value::command("HINCRBYFLOAT", &[key, field, &increment])
+        }
+        Command::Hexists { key, field, .. } => Value::command("HEXISTS", &[key, field]),
+        Command::Hlen { key, .. } => Value::command("HLEN", &[key]),
+        Command::Hkeys { key, .. } => Value::command("HKEYS", &[key]),
+        Command::Hvals { key, .. } => Value::command("HVALS", &[key]),
+        Command::Hscan {
+            key,
+            cursor,
+            pattern,
+            count,
+            novalues,
+            ..
+        } => {
+            let mut args = vec![key.clone(), cursor.to_string().into_bytes()];
+
+            if let Some(pattern) = pattern {
+                args.extend(["MATCH".as_bytes().to_vec(), pattern.clone()]);
+            }
+
+            if let Some(count) = count {
+                args.extend(["COUNT".as_bytes().to_vec(), count.to_string().into_bytes()]);
+            }
+
+            if *novalues {
+                args.push("NOVALUES".as_bytes().to_vec());
+            }
+
+            Value::command("HSCAN", &args.iter().collect::<Vec<_>>())
+        }
+        Command::Sadd { key, members, .. } => list_command("SADD", key, members),
+        Command::Srem { key, members, .. } => list_command("SREM", key, members),
+        Command::Smembers { key, .. } => Value::command("SMEMBERS", &[key]),
+        Command::Sismember { key, member, .. } => Value::command("SISMEMBER", &[key, member]),
+        Command::Smismember { key, members, .. } => list_command("SMISMEMBER", key, members),
+        Command::Scard { key, .. } => Value::command("SCARD", &[key]),
+        Command::Spop { key, count, .. } => pop_command("SPOP", key, *count),
+        Command::Srandmember { key, count, .. } => match count {
+            Some(count) => Value::command("SRANDMEMBER", &[key, &count.to_string().into_bytes()]),
+            None => Value::command("SRANDMEMBER", &[key]),
+        },
+        Command::Sinter { keys, .. } => Value::command("SINTER", &keys.iter().collect::<Vec<_>>()),
+        Command::Sunion { keys, .. } => Value::command("SUNION", &keys.iter().collect::<Vec<_>>()),
+        Command::Sdiff { keys, .. } => Value::command("SDIFF", &keys.iter().collect::<Vec<_>>()),
+        Command::Sinterstore {
+            destination, keys, ..
+        } => list_command("SINTERSTORE", destination, keys),
+        Command::Sunionstore {
+            destination, keys, ..
+        } => list_command("SUNIONSTORE", destination, keys),
+        Command::Sdiffstore {
+            destination, keys, ..
+        } => list_command("SDIFFSTORE", destination, keys),
+        Command::Zadd {
+            key,
+            flags,
+            elements,
+            ..
+        } => {
+            let mut args = vec![key.clone()];
+
+            for (name, set) in [
+                ("NX", flags.nx),
+                ("XX", flags.xx),
+                ("GT", flags.gt),
+                ("LT", flags.lt),
+                ("CH", flags.ch),
+                ("INCR", flags.incr),
+            ] {
+                if set {
+                    args.push(name.as_bytes().to_vec());
+                }
+            }
+
+            for (score, member) in elements {
+                args.extend([score.to_string().into_bytes(), member.clone()]);
+            }
+
+            Value::command("ZADD", &args.iter().collect::<Vec<_>>())
+        }
+        Command::Zrange {
+            key,
+            by,
+            rev,
+            limit,
+            withscores,
+            ..
+        } => {
+            let (low, high, by) = match by {
+                ZrangeBy::Rank { start, stop } => {
+                    let (start, stop) = (start.to_string(), stop.to_string());
+                    let (low, high) = if *rev { (stop, start) } else { (start, stop) };
+                    (low.into_bytes(), high.into_bytes(), None)
+                }
+                ZrangeBy::Score { min, max } => (
+                    score_bound(min).into_bytes(),
+                    score_bound(max).into_bytes(),
+                    Some("BYSCORE"),
+                ),
+                ZrangeBy::Lex { min, max } => (lex_bound(min), lex_bound(max), Some("BYLEX")),
+            };
+
+            // with REV, the range is given from the highest end
+            let (start, stop) = if *rev { (high, low) } else { (low, high) };
+            let mut args = vec![key.clone(), start, stop];
+
+            if let Some(by) = by {
+                args.push(by.as_bytes().to_vec());
+            }
+
+            if *rev {
+                args.push("REV".as_bytes().to_vec());
+            }
+
+            if let Some((offset, count)) = limit {
+                args.extend([
+                    "LIMIT".as_bytes().to_vec(),
+                    offset.to_string().into_bytes(),
+                    count.to_string().into_bytes(),
+                ]);
+            }
+
+            if *withscores {
+                args.push("WITHSCORES".as_bytes().to_vec());
+            }
+
+            Value::command("ZRANGE", &args.iter().collect::<Vec<_>>())
+        }
+        Command::Zrank { key, member, .. } => Value::command("ZRANK", &[key, member]),
+        Command::Zscore { key, member, .. } => Value::command("ZSCORE", &[key, member]),
+        Command::Zcard { key, .. } => Value::command("ZCARD", &[key]),
+        Command::Zincrby {
+            key,
+            increment,
+            member,
+            ..
+        } => {
+            let increment = increment.to_string().into_bytes();
+            Value::command("ZINCRBY", &[key, &increment, member])
+        }
+        Command::Zrem { key, members, .. } => list_command("ZREM", key, members),
+        Command::Zcount { key, min, max, .. } => {
+            let min = score_bound(min).into_bytes();
+            let max = score_bound(max).into_bytes();
+            Value::command("ZCOUNT", &[key, &min, &max])
+        }
+        Command::Zpopmin { key, count, .. } => pop_command("ZPOPMIN", key, *count),
+        Command::Zunionstore {
+            destination,
+            keys,
+            weights,
+            aggregate,
+            ..
+        } => zstore_command("ZUNIONSTORE", destination, keys, weights, *aggregate),
+        Command::Zinterstore {
+            destination,
+            keys,
+            weights,
+            aggregate,
+            ..
+        } => zstore_command("ZINTERSTORE", destination, keys, weights, *aggregate),
+        Command::Xadd {
+            key,
+            nomkstream,
+            trim,
+            id,
+            fields,
+            ..
+        } => {
+            let mut args = vec![key.clone()];
+
+            if *nomkstream {
+                args.push("NOMKSTREAM".as_bytes().to_vec());
+            }
+
+            if let Some(trim) = trim {
+                args.extend(stream_trim(trim));
+            }
+
+            let id = match id {
+                XaddId::Auto => "*".to_string(),
+                XaddId::AutoSequence(ms) => format!("{}-*", ms),
+                XaddId::Explicit(id) => id.to_string(),
+            };
+            args.push(id.into_bytes());
+
+            for (field, value) in fields {
+                args.extend([field.clone(), value.clone()]);
+            }
+
+            Value::command("XADD", &args.iter().collect::<Vec<_>>())
+        }
+        Command::Xrange {
+            key,
+            start,
+            end,
+            count,
+            ..
+        } => xrange_command("XRANGE", key, *start, *end, *count),
+        Command::Xrevrange {
+            key,
+            end,
+            start,
+            count,
+            ..
+        } => xrange_command("XREVRANGE", key, *end, *start, *count),
+        Command::Xlen { key, .. } => Value::command("XLEN", &[key]),
+        Command::Xdel { key, ids, .. } => {
+            let ids: Vec<_> = ids.iter().map(|id| id.to_string().into_bytes()).collect();
+            list_command("XDEL", key, &ids)
+        }
+        Command::Xtrim { key, trim, .. } => {
+            let args: Vec<_> = std::iter::once(key.clone())
+                .chain(stream_trim(trim))
+                .collect();
+            Value::command("XTRIM", &args.iter().collect::<Vec<_>>())
+        }
+        Command::Xsetid {
+            key,
+            last_id,
+            entries_added,
+            max_deleted_id,
+            ..
+        } => {
+            let mut args = vec![key.clone(), last_id.to_string().into_bytes()];
+
+            if let Some(entries_added) = entries_added {
+                args.extend([
+                    "ENTRIESADDED".as_bytes().to_vec(),
+                    entries_added.to_string().into_bytes(),
+                ]);
+            }
+
+            if let Some(max_deleted_id) = max_deleted_id {
+                args.extend([
+                    "MAXDELETEDID".as_bytes().to_vec(),
+                    max_deleted_id.to_string().into_bytes(),
+                ]);
+            }
+
+            Value::command("XSETID", &args.iter().collect::<Vec<_>>())
+        }
+        Command::Xread {
+            count,
+            block,
+            streams,
+            ..
+        } => {
+            let mut args = vec![];
+
+            if let Some(count) = count {
+                args.extend(["COUNT".as_bytes().to_vec(), count.to_string().into_bytes()]);
+            }
+
+            if let Some(block) = block {
+                args.extend([
+                    "BLOCK".as_bytes().to_vec(),
+                    block.as_millis().to_string().into_bytes(),
+                ]);
+            }
+
+            args.push("STREAMS".as_bytes().to_vec());
+            args.extend(streams.iter().map(|(key, _)| key.clone()));
+            args.extend(streams.iter().map(|(_, id)| xread_id(id)));
+
+            Value::command("XREAD", &args.iter().collect::<Vec<_>>())
+        }
+        Command::XgroupCreate {
+            key,
+            group,
+            id,
+            mkstream,
+            entries_read,
+            ..
+        } => {
+            let mut args = vec![
+                "CREATE".as_bytes().to_vec(),
+                key.clone(),
+                group.clone(),
+                xread_id(id),
+            ];
+
+            if *mkstream {
+                args.push("MKSTREAM".as_bytes().to_vec());
+            }
+
+            args.extend(entries_read_args(entries_read));
+            Value::command("XGROUP", &args.iter().collect::<Vec<_>>())
+        }
+        Command::XgroupSetid {
+            key,
+            group,
+            id,
+            entries_read,
+            ..
+        } => {
+            let mut args = vec![
+                "SETID".as_bytes().to_vec(),
+                key.clone(),
+                group.clone(),
+                xread_id(id),
+            ];
+
+            args.extend(entries_read_args(entries_read));
+            Value::command("XGROUP", &args.iter().collect::<Vec<_>>())
+        }
+        Command::XgroupDestroy { key, group, .. } => {
+            let destroy = "DESTROY".as_bytes().to_vec();
+            Value::command("XGROUP", &[&destroy, key, group])
+        }
+        Command::XgroupCreateconsumer {
+            key,
+            group,
+            consumer,
+            ..
+        } => {
+            let create = "CREATECONSUMER".as_bytes().to_vec();
+            Value::command("XGROUP", &[&create, key, group, consumer])
+        }
+        Command::XgroupDelconsumer {
+            key,
+            group,
+            consumer,
+            ..
+        } => {
+            let delete = "DELCONSUMER".as_bytes().to_vec();
+            Value::command("XGROUP", &[&delete, key, group, consumer])
+        }
+        Command::Xreadgroup {
+            group,
+            consumer,
+            count,
+            block,
+            noack,
+            streams,
+            ..
+        } => {
+            let mut args = vec!["GROUP".as_bytes().to_vec(), group.clone(), consumer.clone()];
+
+            if let Some(count) = count {
+                args.extend(["COUNT".as_bytes().to_vec(), count.to_string().into_bytes()]);
+            }
+
+            if let Some(block) = block {
+                args.extend([
+                    "BLOCK".as_bytes().to_vec(),
+                    block.as_millis().to_string().into_bytes(),
+                ]);
+            }
+
+            if *noack {
+                args.push("NOACK".as_bytes().to_vec());
+            }
+
+            args.push("STREAMS".as_bytes().to_vec());
+            args.extend(streams.iter().map(|(key, _)| key.clone()));
+            args.extend(streams.iter().map(|(_, id)| match id {
+                XreadgroupId::Undelivered => ">".as_bytes().to_vec(),
+                XreadgroupId::Pending(id) => id.to_string().into_bytes(),
+            }));
+
+            Value::command("XREADGROUP", &args.iter().collect::<Vec<_>>())
+        }
+        Command::Xack {
+            key, group, ids, ..
+        } => {
+            let mut args = vec![key.clone(), group.clone()];
+            args.extend(ids.iter().map(|id| id.to_string().into_bytes()));
+            Value::command("XACK", &args.iter().collect::<Vec<_>>())
+        }
+        Command::Xpending {
+            key, group, range, ..
+        } => {
+            let mut args = vec![key.clone(), group.clone()];
+
+            if let Some(range) = range {
+                if let Some(min_idle_time) = range.min_idle_time {
+                    args.extend([
+                        "IDLE".as_bytes().to_vec(),
+                        min_idle_time.to_string().into_bytes(),
+                    ]);
+                }
+
+                args.extend([
+                    range_id(range.start),
+                    range_id(range.end),
+                    range.count.to_string().into_bytes(),
+                ]);
+                args.extend(range.consumer.clone());
+            }
+
+            Value::command("XPENDING", &args.iter().collect::<Vec<_>>())
+        }
+        Command::Xclaim {
+            key,
+            group,
+            consumer,
+            min_idle_time,
+            ids,
+            options,
+            ..
+        } => {
+            let mut args = vec![
+                key.clone(),
+                group.clone(),
+                consumer.clone(),
+                min_idle_time.to_string().into_bytes(),
+            ];
+            args.extend(ids.iter().map(|id| id.to_string().into_bytes()));
+
+            let numbers = [
+                ("IDLE", options.idle),
+                ("TIME", options.time),
+                ("RETRYCOUNT", options.retry_count),
+            ];
+
+            for (name, value) in numbers {
+                if let Some(value) = value {
+                    args.extend([name.as_bytes().to_vec(), value.to_string().into_bytes()]);
+                }
+            }
+
+            if options.force {
+                args.push("FORCE".as_bytes().to_vec());
+            }
+
+            if options.justid {
+                args.push("JUSTID".as_bytes().to_vec());
+            }
+
+            if let Some(last_id) = options.last_id {
+                args.extend([
+                    "LASTID".as_bytes().to_vec(),
+                    last_id.to_string().into_bytes(),
+                ]);
+            }
+
+            Value::command("XCLAIM", &args.iter().collect::<Vec<_>>())
+        }
+        Command::Xautoclaim {
+            key,
+            group,
+            consumer,
+            min_idle_time,
+            start,
+            count,
+            justid,
+            ..
+        } => {
+            let mut args = vec![
+                key.clone(),
+                group.clone(),
+                consumer.clone(),
+                min_idle_time.to_string().into_bytes(),
+                range_id(*start),
+                "COUNT".as_bytes().to_vec(),
+                count.to_string().into_bytes(),
+            ];
+
+            if *justid {
+                args.push("JUSTID".as_bytes().to_vec());
+            }
+
+            Value::command("XAUTOCLAIM", &args.iter().collect::<Vec<_>>())
+        }
+        Command::XinfoStream { key, .. } => {
+            let stream = "STREAM".as_bytes().to_vec();
+            Value::command("XINFO", &[&stream, key])
+        }
+        Command::XinfoGroups { key, .. } => {
+            let groups = "GROUPS".as_bytes().to_vec();
+            Value::command("XINFO", &[&groups, key])
+        }
+        Command::XinfoConsumers { key, group, .. } => {
+            let consumers = "CONSUMERS".as_bytes().to_vec();
+            Value::command("XINFO", &[&consumers, key, group])
+        }
+    }
+}
+
+/// Milliseconds since the Unix epoch, or 0 for times before it.
+fn unix_millis(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+fn pairs_command(command: &str, pairs: &[(Vec<u8>, Vec<u8>)]) -> Value {
+    let args: Vec<_> = pairs.iter().flat_map(|(key, value)| [key, value]).collect();
+    Value::command(command, &args)
+}
+
+fn xrange_command(
+    command: &str,
+    key: &Vec<u8>,
+    first: StreamId,
+    second: StreamId,
+    count: Option<usize>,
+) -> Value {
+    let first = range_id(first);
+    let second = range_id(second);
+
+    match count {
+        Some(count) => {
+            let count_arg = "COUNT".as_bytes().to_vec();
+            let count = count.to_string().into_bytes();
+            Value::command(command, &[key, &first, &second, &count_arg, &count])
+        }
+        None => Value::command(command, &[key, &first, &second]),
+    }
+}
+
+/// Formats a range endpoint, with `-` and `+` for the smallest and largest IDs.
+fn range_id(id: StreamId) -> Vec<u8> {
+    match id {
+        StreamId::MIN => "-".as_bytes().to_vec(),
+        StreamId::MAX => "+".as_bytes().to_vec(),
+        id => id.to_string().into_bytes(),
+    }
+}
+
+fn xread_id(id: &XreadId) -> Vec<u8> {
+    match id {
+        XreadId::After(id) => id.to_string().into_bytes(),
+        XreadId::Last => "$".as_bytes().to_vec(),
+    }
+}
+
+fn entries_read_args(entries_read: &Option<u64>) -> Vec<Vec<u8>> {
+    match entries_read {
+        Some(entries_read) => vec![
+            "ENTRIESREAD".as_bytes().to_vec(),
+            entries_read.to_string().into_bytes(),
+        ],
+        None => vec![],
+    }
+}
+
+fn stream_trim(trim: &StreamTrim) -> [Vec<u8>; 2] {
+    match trim {
+        StreamTrim::MaxLen(len) => ["MAXLEN".as_bytes().to_vec(), len.to_string().into_bytes()],
+        StreamTrim::MinId(id) => ["MINID".as_bytes().to_vec(), id.to_string().into_bytes()],
+    }
+}
+
+fn zstore_command(
+    command: &str,
+    destination: &[u8],
+    keys: &[Vec<u8>],
+    weights: &Option<Vec<f64>>,
+    aggregate: Aggregate,
+) -> Value {
+    let mut args = vec![destination.to_vec(), keys.len().to_string().into_bytes()];
+    args.extend(keys.iter().cloned());
+
+    if let Some(weights) = weights {
+        args.push("WEIGHTS".as_bytes().to_vec());
+        args.extend(weights.iter().map(|weight| weight.to_string().into_bytes()));
+    }
+
+    let aggregate = match aggregate {
+        Aggregate::Sum => None,
+        Aggregate::Min => Some("MIN"),
+        Aggregate::Max => Some("MAX"),
+    };
+
+    if let Some(aggregate) = aggregate {
+        args.extend([
+            "AGGREGATE".as_bytes().to_vec(),
+            aggregate.as_bytes().to_vec(),
+        ]);
+    }
+
+    Value::command(command, &args.iter().collect::<Vec<_>>())
+}
+
+fn score_bound(bound: &ScoreBound) -> String {
+    match bound.exclusive {
+        true => format!("({}", bound.value),
+        false => bound.value.to_string(),
+    }
+}
+
+fn lex_bound(bound: &LexBound) -> Vec<u8> {
+    match bound {
+        LexBound::Lowest => b"-".to_vec(),
+        LexBound::Highest => b"+".to_vec(),
+        LexBound::Inclusive(member) => [b"[", member.as_slice()].concat(),
+        LexBound::Exclusive(member) => [b"(", member.as_slice()].concat(),
+    }
+}
+
+fn blocking_pop_command(command: &str, keys: &[Vec<u8>], timeout: &Duration) -> Value {
+    let timeout = timeout.as_secs_f64().to_string().into_bytes();
+    let args: Vec<_> = keys.iter().chain(std::iter::once(&timeout)).collect();
+    Value::command(command, &args)
+}
+
+fn list_command(command: &str, key: &Vec<u8>, elements: &[Vec<u8>]) -> Value {
+    let args: Vec<_> = std::iter::once(key).chain(elements).collect();
+    Value::command(command, &args)
+}
+
+fn pop_command(command: &str, key: &Vec<u8>, count: Option<usize>) -> Value {
+    match count {
+        Some(count) => Value::command(command, &[key, &count.to_string().into_bytes()]),
+        None => Value::command(command, &[key]),
+    }
+}
+
+fn list_end(end: ListEnd) -> &'static str {
+    match end {
+        ListEnd::Left => "LEFT",
+        ListEnd::Right => "RIGHT",
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::protocol::{Command, Value};
-    use std::time::Duration;
+    use crate::protocol::{
+        Aggregate, Command, ExpireFlags, GetexExpiry, ScoreBound, SetCondition, SetExpiry,
+        StreamId, StreamTrim, Value, ZrangeBy,
+    };
+    use std::time::{Duration, UNIX_EPOCH};
 
     #[test]
     fn ping_no_message() {
@@ -104,20 +928,63 @@ mod tests {
             size: 0,
             key: "key".as_bytes().to_vec(),
             value: "value".as_bytes().to_vec(),
+            condition: None,
             expiry: None,
+            get: false,
         };
         assert_to_value(command, &["SET", "key", "value"]);
     }
 
     #[test]
-    fn set_with_expiry() {
+    fn set_with_options() {
         let command = Command::Set {
             size: 0,
             key: "key".as_bytes().to_vec(),
             value: "value".as_bytes().to_vec(),
-            expiry: Some(Duration::from_millis(1000)),
+            condition: Some(SetCondition::Nx),
+            expiry: Some(SetExpiry::At(UNIX_EPOCH + Duration::from_millis(1000))),
+            get: true,
+        };
+        assert_to_value(
+            command,
+            &["SET", "key", "value", "NX", "PXAT", "1000", "GET"],
+        );
+    }
+
+    #[test]
+    fn getex() {
+        let command = Command::Getex {
+            size: 0,
+            key: "key".as_bytes().to_vec(),
+            expiry: Some(GetexExpiry::At(UNIX_EPOCH + Duration::from_secs(1))),
         };
-        assert_to_value(command, &["SET", "key", "value", "PX", "1000"]);
+        assert_to_value(command, &["GETEX", "key", "PXAT", "1000"]);
+    }
+
+    #[test]
+    fn msetnx() {
+        let command = Command::Msetnx {
+            size: 0,
+            pairs: vec![
+                ("a".as_bytes().to_vec(), "1".as_bytes().to_vec()),
+                ("b".as_bytes().to_vec(), "2".as_bytes().to_vec()),
+            ],
+        };
+        assert_to_value(command, &["MSETNX", "a", "1", "b", "2"]);
+    }
+
+    #[test]
+    fn expire() {
+        let command = Command::Expire {
+            size: 0,
+            key: "key".as_bytes().to_vec(),
+            deadline: UNIX_EPOCH + Duration::from_secs(2),
+            flags: ExpireFlags {
+                nx: true,
+                ..ExpireFlags::default()
+            },
+        };
+        assert_to_value(command, &["PEXPIREAT", "key", "2000", "NX"]);
     }
 
     #[test]
@@ -161,6 +1028,186 @@ mod tests {
         assert_to_value(command, &["PSYNC", "id", "0"]);
     }
 
+    #[test]
+    fn save() {
+        assert_to_value(Command::Save { size: 0 }, &["SAVE"]);
+        assert_to_value(Command::Bgsave { size: 0 }, &["BGSAVE"]);
+        assert_to_value(Command::Lastsave { size: 0 }, &["LASTSAVE"]);
+        assert_to_value(Command::Bgrewriteaof { size: 0 }, &["BGREWRITEAOF"]);
+    }
+
+    #[test]
+    fn role() {
+        assert_to_value(Command::Role { size: 0 }, &["ROLE"]);
+    }
+
+    #[test]
+    fn replicaof() {
+        let command = Command::Replicaof {
+            size: 0,
+            master: Some(("localhost".to_string(), 6379)),
+        };
+        assert_to_value(command, &["REPLICAOF", "localhost", "6379"]);
+
+        let command = Command::Replicaof {
+            size: 0,
+            master: None,
+        };
+        assert_to_value(command, &["REPLICAOF", "NO", "ONE"]);
+    }
+
+    #[test]
+    fn lists() {
+        let key = || "key".as_bytes().to_vec();
+
+        let command = Command::Rpush {
+            size: 0,
+            key: key(),
+            elements: vec!["a".as_bytes().to_vec(), "b".as_bytes().to_vec()],
+        };
+        assert_to_value(command, &["RPUSH", "key", "a", "b"]);
+
+        let command = Command::Lpop {
+            size: 0,
+            key: key(),
+            count: Some(2),
+        };
+        assert_to_value(command, &["LPOP", "key", "2"]);
+
+        let command = Command::Linsert {
+            size: 0,
+            key: key(),
+            before: false,
+            pivot: "a".as_bytes().to_vec(),
+            element: "b".as_bytes().to_vec(),
+        };
+        assert_to_value(command, &["LINSERT", "key", "AFTER", "a", "b"]);
+
+        let command = Command::Ltrim {
+            size: 0,
+            key: key(),
+            start: 1,
+            stop: -1,
+        };
+        assert_to_value(command, &["LTRIM", "key", "1", "-1"]);
+    }
+
+    #[test]
+    fn blpop() {
+        let command = Command::Blpop {
+            size: 0,
+            keys: vec!["a".as_bytes().to_vec(), "b".as_bytes().to_vec()],
+            timeout: Duration::ZERO,
+        };
+        assert_to_value(command, &["BLPOP", "a", "b", "0"]);
+    }
+
+    #[test]
+    fn hashes() {
+        let key = || "key".as_bytes().to_vec();
+        let field = || "field".as_bytes().to_vec();
+
+        let command = Command::Hincrbyfloat {
+            size: 0,
+            key: key(),
+            field: field(),
+            increment: 0.5,
+        };
+        assert_to_value(command, &["HINCRBYFLOAT", "key", "field", "0.5"]);
+
+        let command = Command::Hdel {
+            size: 0,
+            key: key(),
+            fields: vec![field()],
+        };
+        assert_to_value(command, &["HDEL", "key", "field"]);
+    }
+
+    #[test]
+    fn sets() {
+        let command = Command::Spop {
+            size: 0,
+            key: "key".as_bytes().to_vec(),
+            count: None,
+        };
+        assert_to_value(command, &["SPOP", "key"]);
+
+        let command = Command::Sdiff {
+            size: 0,
+            keys: vec!["a".as_bytes().to_vec(), "b".as_bytes().to_vec()],
+        };
+        assert_to_value(command, &["SDIFF", "a", "b"]);
+    }
+
+    #[test]
+    fn sorted_sets() {
+        let command = Command::Zcount {
+            size: 0,
+            key: "key".as_bytes().to_vec(),
+            min: ScoreBound {
+                value: 1.0,
+                exclusive: true,
+            },
+            max: ScoreBound {
+                value: f64::INFINITY,
+                exclusive: false,
+            },
+        };
+        assert_to_value(command, &["ZCOUNT", "key", "(1", "inf"]);
+
+        let command = Command::Zunionstore {
+            size: 0,
+            destination: "dest".as_bytes().to_vec(),
+            keys: vec!["a".as_bytes().to_vec(), "b".as_bytes().to_vec()],
+            weights: Some(vec![1.0, 2.5]),
+            aggregate: Aggregate::Min,
+        };
+        assert_to_value(
+            command,
+            &[
+                "ZUNIONSTORE",
+                "dest",
+                "2",
+                "a",
+                "b",
+                "WEIGHTS",
+                "1",
+                "2.5",
+                "AGGREGATE",
+                "MIN",
+            ],
+        );
+
+        let command = Command::Zrange {
+            size: 0,
+            key: "key".as_bytes().to_vec(),
+            by: ZrangeBy::Rank { start: 0, stop: -1 },
+            rev: true,
+            limit: None,
+            withscores: false,
+        };
+        assert_to_value(command, &["ZRANGE", "key", "0", "-1", "REV"]);
+    }
+
+    #[test]
+    fn streams() {
+        let command = Command::Xrevrange {
+            size: 0,
+            key: "key".as_bytes().to_vec(),
+            end: StreamId::MAX,
+            start: StreamId { ms: 5, seq: 1 },
+            count: Some(3),
+        };
+        assert_to_value(command, &["XREVRANGE", "key", "+", "5-1", "COUNT", "3"]);
+
+        let command = Command::Xtrim {
+            size: 0,
+            key: "key".as_bytes().to_vec(),
+            trim: StreamTrim::MinId(StreamId { ms: 10, seq: 0 }),
+        };
+        assert_to_value(command, &["XTRIM", "key", "MINID", "10-0"]);
+    }
+
     fn assert_to_value(command: Command, expected: &[&str]) {
         let value = command.to_value();
 
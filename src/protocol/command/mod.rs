@@ -3,11 +3,195 @@ mod parse;
 mod reader;
 
 use crate::protocol::Value;
-use std::time::Duration;
+use std::{
+    fmt,
+    time::{Duration, SystemTime},
+};
 
 pub use parse::FromValueError;
 pub use reader::{CommandReadError, CommandReader};
 
+/// The end of a list a command pops from or pushes to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ListEnd {
+    Left,
+    Right,
+}
+
+/// The condition SET puts on the key: NX only creates it, XX only replaces it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SetCondition {
+    Nx,
+    Xx,
+}
+
+/// When a key written by SET expires. Relative times from EX and PX are turned into deadlines
+/// when parsed, so the command means the same thing when replayed on a replica or from the AOF.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SetExpiry {
+    At(SystemTime),
+    KeepTtl,
+}
+
+/// What GETEX does to the key's time to live: expire it at a deadline, like SET does, or remove
+/// it with PERSIST.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GetexExpiry {
+    At(SystemTime),
+    Persist,
+}
+
+/// The EXPIRE options. NX only sets an expiry on keys without one, XX only changes an existing
+/// one, and GT and LT only move it later or sooner, where a key without one never expires.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ExpireFlags {
+    pub nx: bool,
+    pub xx: bool,
+    pub gt: bool,
+    pub lt: bool,
+}
+
+/// The ZADD options. NX and XX pick which members may be touched, GT and LT only let updates
+/// move scores one way, CH counts updated members in the reply and INCR adds to the score.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ZaddFlags {
+    pub nx: bool,
+    pub xx: bool,
+    pub gt: bool,
+    pub lt: bool,
+    pub ch: bool,
+    pub incr: bool,
+}
+
+/// What the start and stop of a ZRANGE stand for, always in ascending order.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ZrangeBy {
+    Rank { start: i64, stop: i64 },
+    Score { min: ScoreBound, max: ScoreBound },
+    Lex { min: LexBound, max: LexBound },
+}
+
+/// A score range endpoint, like `1.5`, `(1.5` or `-inf`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScoreBound {
+    pub value: f64,
+    pub exclusive: bool,
+}
+
+/// A member range endpoint: `-`, `+`, `[member` or `(member`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LexBound {
+    Lowest,
+    Highest,
+    Inclusive(Vec<u8>),
+    Exclusive(Vec<u8>),
+}
+
+/// How ZUNIONSTORE and ZINTERSTORE combine the scores of a member found in several sets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aggregate {
+    Sum,
+    Min,
+    Max,
+}
+
+/// The ID of a stream entry: the time it was added in milliseconds, and a sequence number for
+/// entries added in the same millisecond.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    /// The smallest ID after this one.
+    pub fn next(self) -> Option<StreamId> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(StreamId { ms: self.ms, seq }),
+            None => Some(StreamId {
+                ms: self.ms.checked_add(1)?,
+                seq: 0,
+            }),
+        }
+    }
+
+    /// The largest ID before this one.
+    pub fn prev(self) -> Option<StreamId> {
+        match self.seq.checked_sub(1) {
+            Some(seq) => Some(StreamId { ms: self.ms, seq }),
+            None => Some(StreamId {
+                ms: self.ms.checked_sub(1)?,
+                seq: u64::MAX,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+/// The ID requested for a new stream entry: `*`, `ms-*` or a full ID.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum XaddId {
+    Auto,
+    AutoSequence(u64),
+    Explicit(StreamId),
+}
+
+/// Where XREAD starts reading a stream, or where XGROUP puts a group: after a given ID, or
+/// after the last ID the stream had when the command ran (`$`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum XreadId {
+    After(StreamId),
+    Last,
+}
+
+/// What XREADGROUP reads from a stream: entries never delivered to the group (`>`), or the
+/// consumer's own pending entries after an ID.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum XreadgroupId {
+    Undelivered,
+    Pending(StreamId),
+}
+
+/// The optional part of XPENDING, which lists pending entries instead of summing them up.
+#[derive(Clone, Debug, PartialEq)]
+pub struct XpendingRange {
+    /// Only entries idle for at least this many milliseconds.
+    pub min_idle_time: Option<u64>,
+    pub start: StreamId,
+    pub end: StreamId,
+    pub count: usize,
+    pub consumer: Option<Vec<u8>>,
+}
+
+/// The options of XCLAIM, with times in milliseconds.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct XclaimOptions {
+    pub idle: Option<u64>,
+    pub time: Option<u64>,
+    pub retry_count: Option<u64>,
+    pub force: bool,
+    pub justid: bool,
+    pub last_id: Option<StreamId>,
+}
+
+/// How XADD and XTRIM trim a stream: down to a length, or up to an ID.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StreamTrim {
+    MaxLen(usize),
+    MinId(StreamId),
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Command {
     Ping {
@@ -25,11 +209,139 @@ pub enum Command {
         key: Vec<u8>,
     },
 
+    /// With `get`, the reply is the string the key held before, if any.
     Set {
         size: usize,
         key: Vec<u8>,
         value: Vec<u8>,
-        expiry: Option<Duration>,
+        condition: Option<SetCondition>,
+        expiry: Option<SetExpiry>,
+        get: bool,
+    },
+
+    Incr {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Decr {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Incrby {
+        size: usize,
+        key: Vec<u8>,
+        increment: i64,
+    },
+
+    Decrby {
+        size: usize,
+        key: Vec<u8>,
+        decrement: i64,
+    },
+
+    Incrbyfloat {
+        size: usize,
+        key: Vec<u8>,
+        increment: f64,
+    },
+
+    Append {
+        size: usize,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+
+    Strlen {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Getrange {
+        size: usize,
+        key: Vec<u8>,
+        start: i64,
+        end: i64,
+    },
+
+    Setrange {
+        size: usize,
+        key: Vec<u8>,
+        offset: usize,
+        value: Vec<u8>,
+    },
+
+    Getdel {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Getex {
+        size: usize,
+        key: Vec<u8>,
+        expiry: Option<GetexExpiry>,
+    },
+
+    Getset {
+        size: usize,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+
+    Mget {
+        size: usize,
+        keys: Vec<Vec<u8>>,
+    },
+
+    Mset {
+        size: usize,
+        pairs: Vec<(Vec<u8>, Vec<u8>)>,
+    },
+
+    /// Sets every key only if none of them exists.
+    Msetnx {
+        size: usize,
+        pairs: Vec<(Vec<u8>, Vec<u8>)>,
+    },
+
+    Setnx {
+        size: usize,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+
+    /// EXPIRE, PEXPIRE, EXPIREAT and PEXPIREAT, with the deadline they all come down to.
+    Expire {
+        size: usize,
+        key: Vec<u8>,
+        deadline: SystemTime,
+        flags: ExpireFlags,
+    },
+
+    Ttl {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Pttl {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Expiretime {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Pexpiretime {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Persist {
+        size: usize,
+        key: Vec<u8>,
     },
 
     Info {
@@ -46,7 +358,7 @@ pub enum Command {
     Psync {
         size: usize,
         master_replid: Option<Vec<u8>>,
-        master_repl_offset: Option<u32>,
+        master_repl_offset: Option<u64>,
     },
 
     Wait {
@@ -54,11 +366,598 @@ pub enum Command {
         replicas: u32,
         timeout: u64,
     },
+
+    Save {
+        size: usize,
+    },
+
+    Bgsave {
+        size: usize,
+    },
+
+    Lastsave {
+        size: usize,
+    },
+
+    Bgrewriteaof {
+        size: usize,
+    },
+
+    Role {
+        size: usize,
+    },
+
+    Type {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Lpush {
+        size: usize,
+        key: Vec<u8>,
+        elements: Vec<Vec<u8>>,
+    },
+
+    Rpush {
+        size: usize,
+        key: Vec<u8>,
+        elements: Vec<Vec<u8>>,
+    },
+
+    Lpop {
+        size: usize,
+        key: Vec<u8>,
+        count: Option<usize>,
+    },
+
+    Rpop {
+        size: usize,
+        key: Vec<u8>,
+        count: Option<usize>,
+    },
+
+    Lrange {
+        size: usize,
+        key: Vec<u8>,
+        start: i64,
+        stop: i64,
+    },
+
+    Llen {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Lindex {
+        size: usize,
+        key: Vec<u8>,
+        index: i64,
+    },
+
+    Lset {
+        size: usize,
+        key: Vec<u8>,
+        index: i64,
+        element: Vec<u8>,
+    },
+
+    Lrem {
+        size: usize,
+        key: Vec<u8>,
+        count: i64,
+        element: Vec<u8>,
+    },
+
+    Ltrim {
+        size: usize,
+        key: Vec<u8>,
+        start: i64,
+        stop: i64,
+    },
+
+    Linsert {
+        size: usize,
+        key: Vec<u8>,
+        before: bool,
+        pivot: Vec<u8>,
+        element: Vec<u8>,
+    },
+
+    Lmove {
+        size: usize,
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        from: ListEnd,
+        to: ListEnd,
+    },
+
+    /// Pops from the first non-empty list among `keys`, blocking for up to `timeout` (zero
+    /// blocks forever) until one of them gets an element.
+    Blpop {
+        size: usize,
+        keys: Vec<Vec<u8>>,
+        timeout: Duration,
+    },
+
+    Brpop {
+        size: usize,
+        keys: Vec<Vec<u8>>,
+        timeout: Duration,
+    },
+
+    Blmove {
+        size: usize,
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        from: ListEnd,
+        to: ListEnd,
+        timeout: Duration,
+    },
+
+    Hset {
+        size: usize,
+        key: Vec<u8>,
+        pairs: Vec<(Vec<u8>, Vec<u8>)>,
+    },
+
+    Hget {
+        size: usize,
+        key: Vec<u8>,
+        field: Vec<u8>,
+    },
+
+    Hmget {
+        size: usize,
+        key: Vec<u8>,
+        fields: Vec<Vec<u8>>,
+    },
+
+    Hdel {
+        size: usize,
+        key: Vec<u8>,
+        fields: Vec<Vec<u8>>,
+    },
+
+    Hgetall {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Hincrby {
+        size: usize,
+        key: Vec<u8>,
+        field: Vec<u8>,
+        increment: i64,
+    },
+
+    Hincrbyfloat {
+        size: usize,
+        key: Vec<u8>,
+        field: Vec<u8>,
+        increment: f64,
+    },
+
+    Hexists {
+        size: usize,
+        key: Vec<u8>,
+        field: Vec<u8>,
+    },
+
+    Hlen {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Hkeys {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Hvals {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    /// `HSCAN key cursor [MATCH pattern] [COUNT count] [NOVALUES]`
+    Hscan {
+        size: usize,
+        key: Vec<u8>,
+        cursor: u64,
+        pattern: Option<Vec<u8>>,
+        count: Option<usize>,
+        novalues: bool,
+    },
+
+    Sadd {
+        size: usize,
+        key: Vec<u8>,
+        members: Vec<Vec<u8>>,
+    },
+
+    Srem {
+        size: usize,
+        key: Vec<u8>,
+        members: Vec<Vec<u8>>,
+    },
+
+    Smembers {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Sismember {
+        size: usize,
+        key: Vec<u8>,
+        member: Vec<u8>,
+    },
+
+    Smismember {
+        size: usize,
+        key: Vec<u8>,
+        members: Vec<Vec<u8>>,
+    },
+
+    Scard {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Spop {
+        size: usize,
+        key: Vec<u8>,
+        count: Option<usize>,
+    },
+
+    /// A negative `count` may return the same member more than once.
+    Srandmember {
+        size: usize,
+        key: Vec<u8>,
+        count: Option<i64>,
+    },
+
+    Sinter {
+        size: usize,
+        keys: Vec<Vec<u8>>,
+    },
+
+    Sunion {
+        size: usize,
+        keys: Vec<Vec<u8>>,
+    },
+
+    Sdiff {
+        size: usize,
+        keys: Vec<Vec<u8>>,
+    },
+
+    Sinterstore {
+        size: usize,
+        destination: Vec<u8>,
+        keys: Vec<Vec<u8>>,
+    },
+
+    Sunionstore {
+        size: usize,
+        destination: Vec<u8>,
+        keys: Vec<Vec<u8>>,
+    },
+
+    Sdiffstore {
+        size: usize,
+        destination: Vec<u8>,
+        keys: Vec<Vec<u8>>,
+    },
+
+    Zadd {
+        size: usize,
+        key: Vec<u8>,
+        flags: ZaddFlags,
+        elements: Vec<(f64, Vec<u8>)>,
+    },
+
+    /// With `rev`, elements come from the highest down, and ranks count from the highest.
+    Zrange {
+        size: usize,
+        key: Vec<u8>,
+        by: ZrangeBy,
+        rev: bool,
+        limit: Option<(i64, i64)>,
+        withscores: bool,
+    },
+
+    Zrank {
+        size: usize,
+        key: Vec<u8>,
+        member: Vec<u8>,
+    },
+
+    Zscore {
+        size: usize,
+        key: Vec<u8>,
+        member: Vec<u8>,
+    },
+
+    Zcard {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Zincrby {
+        size: usize,
+        key: Vec<u8>,
+        increment: f64,
+        member: Vec<u8>,
+    },
+
+    Zrem {
+        size: usize,
+        key: Vec<u8>,
+        members: Vec<Vec<u8>>,
+    },
+
+    Zcount {
+        size: usize,
+        key: Vec<u8>,
+        min: ScoreBound,
+        max: ScoreBound,
+    },
+
+    Zpopmin {
+        size: usize,
+        key: Vec<u8>,
+        count: Option<usize>,
+    },
+
+    /// Missing weights count as 1.
+    Zunionstore {
+        size: usize,
+        destination: Vec<u8>,
+        keys: Vec<Vec<u8>>,
+        weights: Option<Vec<f64>>,
+        aggregate: Aggregate,
+    },
+
+    Zinterstore {
+        size: usize,
+        destination: Vec<u8>,
+        keys: Vec<Vec<u8>>,
+        weights: Option<Vec<f64>>,
+        aggregate: Aggregate,
+    },
+
+    Xadd {
+        size: usize,
+        key: Vec<u8>,
+        nomkstream: bool,
+        trim: Option<StreamTrim>,
+        id: XaddId,
+        fields: Vec<(Vec<u8>, Vec<u8>)>,
+    },
+
+    /// Exclusive and partial IDs are resolved while parsing, so `start` and `end` are inclusive.
+    Xrange {
+        size: usize,
+        key: Vec<u8>,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+    },
+
+    Xrevrange {
+        size: usize,
+        key: Vec<u8>,
+        end: StreamId,
+        start: StreamId,
+        count: Option<usize>,
+    },
+
+    Xlen {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    Xdel {
+        size: usize,
+        key: Vec<u8>,
+        ids: Vec<StreamId>,
+    },
+
+    Xtrim {
+        size: usize,
+        key: Vec<u8>,
+        trim: StreamTrim,
+    },
+
+    /// Sets the last ID of a stream, and optionally its other counters, as written by AOF
+    /// rewrites.
+    Xsetid {
+        size: usize,
+        key: Vec<u8>,
+        last_id: StreamId,
+        entries_added: Option<u64>,
+        max_deleted_id: Option<StreamId>,
+    },
+
+    /// Reads the entries after the given ID of each stream, up to `count` per stream. With
+    /// `block`, waits for up to that long (zero waits forever) if there's nothing to read yet.
+    Xread {
+        size: usize,
+        count: Option<usize>,
+        block: Option<Duration>,
+        streams: Vec<(Vec<u8>, XreadId)>,
+    },
+
+    XgroupCreate {
+        size: usize,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        id: XreadId,
+        mkstream: bool,
+        entries_read: Option<u64>,
+    },
+
+    XgroupSetid {
+        size: usize,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        id: XreadId,
+        entries_read: Option<u64>,
+    },
+
+    XgroupDestroy {
+        size: usize,
+        key: Vec<u8>,
+        group: Vec<u8>,
+    },
+
+    XgroupCreateconsumer {
+        size: usize,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        consumer: Vec<u8>,
+    },
+
+    XgroupDelconsumer {
+        size: usize,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        consumer: Vec<u8>,
+    },
+
+    /// Reads from streams on behalf of a consumer of a group, adding what it gets to the
+    /// group's pending entries unless `noack`.
+    Xreadgroup {
+        size: usize,
+        group: Vec<u8>,
+        consumer: Vec<u8>,
+        count: Option<usize>,
+        block: Option<Duration>,
+        noack: bool,
+        streams: Vec<(Vec<u8>, XreadgroupId)>,
+    },
+
+    Xack {
+        size: usize,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        ids: Vec<StreamId>,
+    },
+
+    Xpending {
+        size: usize,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        range: Option<XpendingRange>,
+    },
+
+    /// Takes pending entries idle for at least `min_idle_time` milliseconds over for
+    /// `consumer`.
+    Xclaim {
+        size: usize,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        consumer: Vec<u8>,
+        min_idle_time: u64,
+        ids: Vec<StreamId>,
+        options: XclaimOptions,
+    },
+
+    Xautoclaim {
+        size: usize,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        consumer: Vec<u8>,
+        min_idle_time: u64,
+        start: StreamId,
+        count: usize,
+        justid: bool,
+    },
+
+    XinfoStream {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    XinfoGroups {
+        size: usize,
+        key: Vec<u8>,
+    },
+
+    XinfoConsumers {
+        size: usize,
+        key: Vec<u8>,
+        group: Vec<u8>,
+    },
+
+    /// `REPLICAOF host port`, or `REPLICAOF NO ONE` (`master: None`) to become a master.
+    Replicaof {
+        size: usize,
+        master: Option<(String, u16)>,
+    },
 }
 
 impl Command {
     pub fn is_write(&self) -> bool {
-        matches!(self, Self::Set { .. })
+        matches!(
+            self,
+            Self::Set { .. }
+                | Self::Incr { .. }
+                | Self::Decr { .. }
+                | Self::Incrby { .. }
+                | Self::Decrby { .. }
+                | Self::Incrbyfloat { .. }
+                | Self::Append { .. }
+                | Self::Setrange { .. }
+                | Self::Getdel { .. }
+                | Self::Getex { .. }
+                | Self::Getset { .. }
+                | Self::Mset { .. }
+                | Self::Msetnx { .. }
+                | Self::Setnx { .. }
+                | Self::Expire { .. }
+                | Self::Persist { .. }
+                | Self::Lpush { .. }
+                | Self::Rpush { .. }
+                | Self::Lpop { .. }
+                | Self::Rpop { .. }
+                | Self::Lset { .. }
+                | Self::Lrem { .. }
+                | Self::Ltrim { .. }
+                | Self::Linsert { .. }
+                | Self::Lmove { .. }
+                | Self::Blpop { .. }
+                | Self::Brpop { .. }
+                | Self::Blmove { .. }
+                | Self::Hset { .. }
+                | Self::Hdel { .. }
+                | Self::Hincrby { .. }
+                | Self::Hincrbyfloat { .. }
+                | Self::Sadd { .. }
+                | Self::Srem { .. }
+                | Self::Spop { .. }
+                | Self::Sinterstore { .. }
+                | Self::Sunionstore { .. }
+                | Self::Sdiffstore { .. }
+                | Self::Zadd { .. }
+                | Self::Zincrby { .. }
+                | Self::Zrem { .. }
+                | Self::Zpopmin { .. }
+                | Self::Zunionstore { .. }
+                | Self::Zinterstore { .. }
+                | Self::Xadd { .. }
+                | Self::Xdel { .. }
+                | Self::Xtrim { .. }
+                | Self::Xsetid { .. }
+                | Self::XgroupCreate { .. }
+                | Self::XgroupSetid { .. }
+                | Self::XgroupDestroy { .. }
+                | Self::XgroupCreateconsumer { .. }
+                | Self::XgroupDelconsumer { .. }
+                | Self::Xreadgroup { .. }
+                | Self::Xack { .. }
+                | Self::Xclaim { .. }
+                | Self::Xautoclaim { .. }
+        )
     }
 
     pub fn to_value(&self) -> Value {
@@ -1,6 +1,22 @@
-use crate::protocol::{Command, Value};
-use std::{collections::VecDeque, fmt::Display, str::FromStr, time::Duration};
-
+use crate::protocol::{
+    Aggregate, Command, ExpireFlags, GetexExpiry, LexBound, ListEnd, ScoreBound, SetCondition,
+    SetExpiry, StreamId, StreamTrim, Value, XaddId, XclaimOptions, XpendingRange, XreadId,
+    XreadgroupId, ZaddFlags, ZrangeBy,
+};
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The longest string SETRANGE can make, 512 MB like Redis' default `proto-max-bulk-len`.
+const MAX_STRING_LEN: usize = 512 * 1024 * 1024;
+
+/// How many entries XAUTOCLAIM looks at when the client doesn't give a COUNT.
+const DEFAULT_AUTOCLAIM_COUNT: usize = 100;
+
+/// Why a value isn't a valid command, without the `ERR` prefix the reply to the client gets.
 #[derive(Debug, thiserror::Error)]
 #[error("{0}")]
 pub struct FromValueError(String);
@@ -55,10 +71,111 @@ fn from_parts(size: usize, mut values: VecDeque<Vec<u8>>) -> Result<Command, Fro
         "ECHO" => parse_echo(size, values),
         "GET" => parse_get(size, values),
         "SET" => parse_set(size, values),
+        "INCR" => parse_incr(size, values),
+        "DECR" => parse_decr(size, values),
+        "INCRBY" => parse_incrby(size, values),
+        "DECRBY" => parse_decrby(size, values),
+        "INCRBYFLOAT" => parse_incrbyfloat(size, values),
+        "APPEND" => parse_append(size, values),
+        "STRLEN" => parse_strlen(size, values),
+        "GETRANGE" => parse_getrange(size, values),
+        "SETRANGE" => parse_setrange(size, values),
+        "GETDEL" => parse_getdel(size, values),
+        "GETEX" => parse_getex(size, values),
+        "GETSET" => parse_getset(size, values),
+        "MGET" => parse_mget(size, values),
+        "MSET" => parse_mset(size, values),
+        "MSETNX" => parse_msetnx(size, values),
+        "SETNX" => parse_setnx(size, values),
+        "SETEX" => parse_setex(size, values, "SETEX", "EX"),
+        "PSETEX" => parse_setex(size, values, "PSETEX", "PX"),
+        "EXPIRE" => parse_expire(size, values, "EXPIRE", "EX"),
+        "PEXPIRE" => parse_expire(size, values, "PEXPIRE", "PX"),
+        "EXPIREAT" => parse_expire(size, values, "EXPIREAT", "EXAT"),
+        "PEXPIREAT" => parse_expire(size, values, "PEXPIREAT", "PXAT"),
+        "TTL" => parse_ttl(size, values),
+        "PTTL" => parse_pttl(size, values),
+        "EXPIRETIME" => parse_expiretime(size, values),
+        "PEXPIRETIME" => parse_pexpiretime(size, values),
+        "PERSIST" => parse_persist(size, values),
         "INFO" => parse_info(size, values),
         "REPLCONF" => parse_replconf(size, values),
         "PSYNC" => parse_psync(size, values),
         "WAIT" => parse_wait(size, values),
+        "SAVE" => Ok(Command::Save { size }),
+        "BGSAVE" => Ok(Command::Bgsave { size }),
+        "LASTSAVE" => Ok(Command::Lastsave { size }),
+        "BGREWRITEAOF" => Ok(Command::Bgrewriteaof { size }),
+        "REPLICAOF" | "SLAVEOF" => parse_replicaof(size, values),
+        "ROLE" => Ok(Command::Role { size }),
+        "TYPE" => parse_type(size, values),
+        "LPUSH" => parse_push(size, values, ListEnd::Left),
+        "RPUSH" => parse_push(size, values, ListEnd::Right),
+        "LPOP" => parse_pop(size, values, ListEnd::Left),
+        "RPOP" => parse_pop(size, values, ListEnd::Right),
+        "LRANGE" => parse_lrange(size, values),
+        "LLEN" => parse_llen(size, values),
+        "LINDEX" => parse_lindex(size, values),
+        "LSET" => parse_lset(size, values),
+        "LREM" => parse_lrem(size, values),
+        "LTRIM" => parse_ltrim(size, values),
+        "LINSERT" => parse_linsert(size, values),
+        "LMOVE" => parse_lmove(size, values),
+        "BLPOP" => parse_blocking_pop(size, values, ListEnd::Left),
+        "BRPOP" => parse_blocking_pop(size, values, ListEnd::Right),
+        "BLMOVE" => parse_blmove(size, values),
+        "HSET" => parse_hset(size, values),
+        "HGET" => parse_hget(size, values),
+        "HMGET" => parse_hmget(size, values),
+        "HDEL" => parse_hdel(size, values),
+        "HGETALL" => parse_key_only(size, values, "HGETALL"),
+        "HINCRBY" => parse_hincrby(size, values),
+        "HINCRBYFLOAT" => parse_hincrbyfloat(size, values),
+        "HEXISTS" => parse_hexists(size, values),
+        "HLEN" => parse_key_only(size, values, "HLEN"),
+        "HKEYS" => parse_key_only(size, values, "HKEYS"),
+        "HVALS" => parse_key_only(size, values, "HVALS"),
+        "HSCAN" => parse_hscan(size, values),
+        "SADD" => parse_key_and_members(size, values, "SADD"),
+        "SREM" => parse_key_and_members(size, values, "SREM"),
+        "SMEMBERS" => parse_key_only(size, values, "SMEMBERS"),
+        "SISMEMBER" => parse_sismember(size, values),
+        "SMISMEMBER" => parse_key_and_members(size, values, "SMISMEMBER"),
+        "SCARD" => parse_key_only(size, values, "SCARD"),
+        "SPOP" => parse_spop(size, values),
+        "SRANDMEMBER" => parse_srandmember(size, values),
+        "SINTER" => parse_keys(size, values, "SINTER"),
+        "SUNION" => parse_keys(size, values, "SUNION"),
+        "SDIFF" => parse_keys(size, values, "SDIFF"),
+        "SINTERSTORE" => parse_store(size, values, "SINTERSTORE"),
+        "SUNIONSTORE" => parse_store(size, values, "SUNIONSTORE"),
+        "SDIFFSTORE" => parse_store(size, values, "SDIFFSTORE"),
+        "ZADD" => parse_zadd(size, values),
+        "ZRANGE" => parse_zrange(size, values),
+        "ZRANK" => parse_zrank(size, values),
+        "ZSCORE" => parse_zscore(size, values),
+        "ZCARD" => parse_key_only(size, values, "ZCARD"),
+        "ZINCRBY" => parse_zincrby(size, values),
+        "ZREM" => parse_key_and_members(size, values, "ZREM"),
+        "ZCOUNT" => parse_zcount(size, values),
+        "ZPOPMIN" => parse_zpopmin(size, values),
+        "ZUNIONSTORE" => parse_zstore(size, values, "ZUNIONSTORE"),
+        "ZINTERSTORE" => parse_zstore(size, values, "ZINTERSTORE"),
+        "XADD" => parse_xadd(size, values),
+        "XRANGE" => parse_xrange(size, values, false),
+        "XREVRANGE" => parse_xrange(size, values, true),
+        "XLEN" => parse_key_only(size, values, "XLEN"),
+        "XDEL" => parse_xdel(size, values),
+        "XTRIM" => parse_xtrim(size, values),
+        "XSETID" => parse_xsetid(size, values),
+        "XREAD" => parse_xread(size, values),
+        "XGROUP" => parse_xgroup(size, values),
+        "XREADGROUP" => parse_xreadgroup(size, values),
+        "XACK" => parse_xack(size, values),
+        "XPENDING" => parse_xpending(size, values),
+        "XCLAIM" => parse_xclaim(size, values),
+        "XAUTOCLAIM" => parse_xautoclaim(size, values),
+        "XINFO" => parse_xinfo(size, values),
         cmd => invalid_command(cmd),
     }
 }
@@ -90,33 +207,460 @@ fn parse_set(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromVa
 
     let key = args.pop_front().unwrap();
     let value = args.pop_front().unwrap();
-    let expiry = parse_set_args(args)?;
+    let mut condition = None;
+    let mut expiry = None;
+    let mut get = false;
+
+    while let Some(arg) = args.pop_front() {
+        let arg = from_utf8(arg)?.to_uppercase();
+
+        match arg.as_str() {
+            "NX" | "XX" => {
+                let new = match arg.as_str() {
+                    "NX" => SetCondition::Nx,
+                    _ => SetCondition::Xx,
+                };
+
+                if condition.is_some_and(|condition| condition != new) {
+                    return Err("syntax error".into());
+                }
+
+                condition = Some(new);
+            }
+            "GET" => get = true,
+            "EX" | "PX" | "EXAT" | "PXAT" | "KEEPTTL" => {
+                if expiry.is_some() {
+                    return Err("syntax error".into());
+                }
+
+                expiry = Some(match arg.as_str() {
+                    "KEEPTTL" => SetExpiry::KeepTtl,
+                    unit => SetExpiry::At(parse_set_deadline(&mut args, unit)?),
+                });
+            }
+            arg => return Err(FromValueError(format!("invalid SET argument: {}", arg))),
+        }
+    }
 
     Ok(Command::Set {
         size,
         key,
         value,
+        condition,
         expiry,
+        get,
+    })
+}
+
+/// Parses the argument of EX, PX, EXAT or PXAT into the time the key expires.
+fn parse_set_deadline(
+    args: &mut VecDeque<Vec<u8>>,
+    unit: &str,
+) -> Result<SystemTime, FromValueError> {
+    let bytes = args
+        .pop_front()
+        .ok_or_else(|| wrong_number_of_arguments(&format!("SET {}", unit)))?;
+
+    let desc = format!("integer value for SET {} argument", unit);
+    positive_deadline(parse_number(bytes, &desc)?, "set", unit)
+}
+
+/// Turns a positive time to live or Unix time into a deadline, as SET and the commands like it
+/// take them. `command` names the command in the error for times that aren't positive or
+/// overflow.
+fn positive_deadline(time: i64, command: &str, unit: &str) -> Result<SystemTime, FromValueError> {
+    if time <= 0 {
+        return Err(invalid_expire_time(command));
+    }
+
+    deadline(time, command, unit)
+}
+
+/// The deadline `time` stands for: a time to live from now with EX and PX, or a Unix time with
+/// EXAT and PXAT, in seconds or milliseconds.
+///
+/// Deadlines are propagated as Unix milliseconds, parsed back as an i64, so like Redis this
+/// rejects any later than that.
+fn deadline(time: i64, command: &str, unit: &str) -> Result<SystemTime, FromValueError> {
+    let millis = match unit {
+        "EX" | "EXAT" => time
+            .checked_mul(1000)
+            .ok_or_else(|| invalid_expire_time(command))?,
+        _ => time,
+    };
+
+    let base = match unit {
+        "EX" | "PX" => SystemTime::now(),
+        _ => UNIX_EPOCH,
+    };
+
+    let offset = Duration::from_millis(millis.unsigned_abs());
+
+    let deadline = match millis {
+        0.. => base.checked_add(offset),
+        _ => base.checked_sub(offset),
+    }
+    .ok_or_else(|| invalid_expire_time(command))?;
+
+    let unix_millis = deadline
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis());
+
+    if unix_millis > i64::MAX as u128 {
+        return Err(invalid_expire_time(command));
+    }
+
+    Ok(deadline)
+}
+
+fn invalid_expire_time(command: &str) -> FromValueError {
+    FromValueError(format!("invalid expire time in '{}' command", command))
+}
+
+fn parse_incr(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 1 {
+        return Err(wrong_number_of_arguments("INCR"));
+    }
+
+    let key = args.pop_front().unwrap();
+    Ok(Command::Incr { size, key })
+}
+
+fn parse_decr(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 1 {
+        return Err(wrong_number_of_arguments("DECR"));
+    }
+
+    let key = args.pop_front().unwrap();
+    Ok(Command::Decr { size, key })
+}
+
+fn parse_incrby(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 2 {
+        return Err(wrong_number_of_arguments("INCRBY"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let increment = parse_integer(args.pop_front().unwrap())?;
+
+    Ok(Command::Incrby {
+        size,
+        key,
+        increment,
+    })
+}
+
+fn parse_decrby(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 2 {
+        return Err(wrong_number_of_arguments("DECRBY"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let decrement = parse_integer(args.pop_front().unwrap())?;
+
+    Ok(Command::Decrby {
+        size,
+        key,
+        decrement,
+    })
+}
+
+fn parse_incrbyfloat(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 2 {
+        return Err(wrong_number_of_arguments("INCRBYFLOAT"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let increment: f64 = parse_number(args.pop_front().unwrap(), "increment")
+        .map_err(|_| FromValueError::from("value is not a valid float"))?;
+
+    if !increment.is_finite() {
+        return Err("increment would produce NaN or Infinity".into());
+    }
+
+    Ok(Command::Incrbyfloat {
+        size,
+        key,
+        increment,
+    })
+}
+
+fn parse_append(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 2 {
+        return Err(wrong_number_of_arguments("APPEND"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let value = args.pop_front().unwrap();
+
+    Ok(Command::Append { size, key, value })
+}
+
+fn parse_strlen(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 1 {
+        return Err(wrong_number_of_arguments("STRLEN"));
+    }
+
+    let key = args.pop_front().unwrap();
+    Ok(Command::Strlen { size, key })
+}
+
+fn parse_getrange(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 3 {
+        return Err(wrong_number_of_arguments("GETRANGE"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let start = parse_integer(args.pop_front().unwrap())?;
+    let end = parse_integer(args.pop_front().unwrap())?;
+
+    Ok(Command::Getrange {
+        size,
+        key,
+        start,
+        end,
+    })
+}
+
+fn parse_setrange(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 3 {
+        return Err(wrong_number_of_arguments("SETRANGE"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let offset = parse_integer(args.pop_front().unwrap())?;
+    let value = args.pop_front().unwrap();
+
+    let offset = match usize::try_from(offset) {
+        Ok(offset) if offset <= MAX_STRING_LEN => offset,
+        _ => return Err("offset is out of range".into()),
+    };
+
+    if offset + value.len() > MAX_STRING_LEN {
+        return Err("string exceeds maximum allowed size (proto-max-bulk-len)".into());
+    }
+
+    Ok(Command::Setrange {
+        size,
+        key,
+        offset,
+        value,
     })
 }
 
-fn parse_set_args(mut args: VecDeque<Vec<u8>>) -> Result<Option<Duration>, FromValueError> {
-    match args.pop_front() {
+fn parse_getdel(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 1 {
+        return Err(wrong_number_of_arguments("GETDEL"));
+    }
+
+    let key = args.pop_front().unwrap();
+    Ok(Command::Getdel { size, key })
+}
+
+fn parse_getex(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    let key = args
+        .pop_front()
+        .ok_or_else(|| wrong_number_of_arguments("GETEX"))?;
+
+    let expiry = match args.pop_front() {
         Some(arg) => match from_utf8(arg)?.to_uppercase().as_str() {
-            "PX" => parse_set_expiry(args).map(Some),
-            arg => Err(FromValueError(format!("invalid SET argument: {}", arg))),
+            "PERSIST" => Some(GetexExpiry::Persist),
+            unit @ ("EX" | "PX" | "EXAT" | "PXAT") => {
+                let time = parse_integer(args.pop_front().ok_or("syntax error")?)?;
+                Some(GetexExpiry::At(positive_deadline(time, "getex", unit)?))
+            }
+            arg => return Err(FromValueError(format!("invalid GETEX argument: {}", arg))),
         },
-        None => Ok(None),
+        None => None,
+    };
+
+    if !args.is_empty() {
+        return Err("syntax error".into());
+    }
+
+    Ok(Command::Getex { size, key, expiry })
+}
+
+fn parse_getset(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 2 {
+        return Err(wrong_number_of_arguments("GETSET"));
     }
+
+    let key = args.pop_front().unwrap();
+    let value = args.pop_front().unwrap();
+
+    Ok(Command::Getset { size, key, value })
 }
 
-fn parse_set_expiry(mut args: VecDeque<Vec<u8>>) -> Result<Duration, FromValueError> {
-    let bytes = args
-        .pop_front()
-        .ok_or_else(|| wrong_number_of_arguments("SET PX"))?;
+fn parse_mget(size: usize, args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.is_empty() {
+        return Err(wrong_number_of_arguments("MGET"));
+    }
+
+    Ok(Command::Mget {
+        size,
+        keys: args.into(),
+    })
+}
+
+fn parse_mset(size: usize, args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.is_empty() || args.len() % 2 != 0 {
+        return Err(wrong_number_of_arguments("MSET"));
+    }
 
-    let expiry = parse_number(bytes, "integer value for SET PX argument")?;
-    Ok(Duration::from_millis(expiry))
+    let pairs = key_value_pairs(args);
+    Ok(Command::Mset { size, pairs })
+}
+
+fn parse_msetnx(size: usize, args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.is_empty() || args.len() % 2 != 0 {
+        return Err(wrong_number_of_arguments("MSETNX"));
+    }
+
+    let pairs = key_value_pairs(args);
+    Ok(Command::Msetnx { size, pairs })
+}
+
+fn key_value_pairs(mut args: VecDeque<Vec<u8>>) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let mut pairs = Vec::with_capacity(args.len() / 2);
+
+    while let (Some(key), Some(value)) = (args.pop_front(), args.pop_front()) {
+        pairs.push((key, value));
+    }
+
+    pairs
+}
+
+fn parse_setnx(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 2 {
+        return Err(wrong_number_of_arguments("SETNX"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let value = args.pop_front().unwrap();
+
+    Ok(Command::Setnx { size, key, value })
+}
+
+/// SETEX and PSETEX are a SET with EX or PX, so they parse into one.
+fn parse_setex(
+    size: usize,
+    mut args: VecDeque<Vec<u8>>,
+    command: &str,
+    unit: &str,
+) -> Result<Command, FromValueError> {
+    if args.len() != 3 {
+        return Err(wrong_number_of_arguments(command));
+    }
+
+    let key = args.pop_front().unwrap();
+    let time = parse_integer(args.pop_front().unwrap())?;
+    let value = args.pop_front().unwrap();
+
+    let deadline = positive_deadline(time, &command.to_lowercase(), unit)?;
+
+    Ok(Command::Set {
+        size,
+        key,
+        value,
+        condition: None,
+        expiry: Some(SetExpiry::At(deadline)),
+        get: false,
+    })
+}
+
+/// EXPIRE, PEXPIRE, EXPIREAT and PEXPIREAT only differ in what their time means, so they all
+/// parse into a deadline. Unlike SET, they take times that aren't positive, which delete the key.
+fn parse_expire(
+    size: usize,
+    mut args: VecDeque<Vec<u8>>,
+    command: &str,
+    unit: &str,
+) -> Result<Command, FromValueError> {
+    if args.len() < 2 {
+        return Err(wrong_number_of_arguments(command));
+    }
+
+    let key = args.pop_front().unwrap();
+    let time = parse_number(args.pop_front().unwrap(), "expire time")?;
+    let mut flags = ExpireFlags::default();
+
+    for arg in args {
+        match from_utf8(arg)?.to_uppercase().as_str() {
+            "NX" => flags.nx = true,
+            "XX" => flags.xx = true,
+            "GT" => flags.gt = true,
+            "LT" => flags.lt = true,
+            arg => {
+                return Err(FromValueError(format!(
+                    "invalid {} argument: {}",
+                    command, arg
+                )))
+            }
+        }
+    }
+
+    if flags.nx && (flags.xx || flags.gt || flags.lt) {
+        return Err("NX and XX, GT or LT options at the same time are not compatible".into());
+    }
+
+    if flags.gt && flags.lt {
+        return Err("GT and LT options at the same time are not compatible".into());
+    }
+
+    let deadline = deadline(time, &command.to_lowercase(), unit)?;
+
+    Ok(Command::Expire {
+        size,
+        key,
+        deadline,
+        flags,
+    })
+}
+
+fn parse_ttl(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 1 {
+        return Err(wrong_number_of_arguments("TTL"));
+    }
+
+    let key = args.pop_front().unwrap();
+    Ok(Command::Ttl { size, key })
+}
+
+fn parse_pttl(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 1 {
+        return Err(wrong_number_of_arguments("PTTL"));
+    }
+
+    let key = args.pop_front().unwrap();
+    Ok(Command::Pttl { size, key })
+}
+
+fn parse_expiretime(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 1 {
+        return Err(wrong_number_of_arguments("EXPIRETIME"));
+    }
+
+    let key = args.pop_front().unwrap();
+    Ok(Command::Expiretime { size, key })
+}
+
+fn parse_pexpiretime(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 1 {
+        return Err(wrong_number_of_arguments("PEXPIRETIME"));
+    }
+
+    let key = args.pop_front().unwrap();
+    Ok(Command::Pexpiretime { size, key })
+}
+
+fn parse_persist(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 1 {
+        return Err(wrong_number_of_arguments("PERSIST"));
+    }
+
+    let key = args.pop_front().unwrap();
+    Ok(Command::Persist { size, key })
 }
 
 fn parse_info(size: usize, args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
@@ -160,13 +704,13 @@ fn parse_psync_replid(replid: Vec<u8>) -> Option<Vec<u8>> {
     }
 }
 
-fn parse_psync_offset(offset: Vec<u8>) -> Result<Option<u32>, FromValueError> {
+fn parse_psync_offset(offset: Vec<u8>) -> Result<Option<u64>, FromValueError> {
     let offset: i64 = parse_number(offset, "PSYNC offset")?;
 
     if offset < 0 {
         Ok(None)
     } else {
-        Ok(Some(offset as u32))
+        Ok(Some(offset as u64))
     }
 }
 
@@ -185,373 +729,2969 @@ fn parse_wait(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromV
     })
 }
 
-fn invalid_command(command: &str) -> Result<Command, FromValueError> {
-    Err(FromValueError(format!("invalid command: {}", command)))
+fn parse_replicaof(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 2 {
+        return Err(wrong_number_of_arguments("REPLICAOF"));
+    }
+
+    let host = from_utf8(args.pop_front().unwrap())?;
+    let port = from_utf8(args.pop_front().unwrap())?;
+
+    if host.eq_ignore_ascii_case("NO") && port.eq_ignore_ascii_case("ONE") {
+        return Ok(Command::Replicaof { size, master: None });
+    }
+
+    let port = parse_number(port.into_bytes(), "master port")?;
+
+    Ok(Command::Replicaof {
+        size,
+        master: Some((host, port)),
+    })
 }
 
-fn from_utf8(bytes: Vec<u8>) -> Result<String, FromValueError> {
-    String::from_utf8(bytes).map_err(|_| "invalid UTF-8".into())
+fn parse_type(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 1 {
+        return Err(wrong_number_of_arguments("TYPE"));
+    }
+
+    let key = args.pop_front().unwrap();
+    Ok(Command::Type { size, key })
 }
 
-fn parse_number<T>(bytes: Vec<u8>, desc: &str) -> Result<T, FromValueError>
-where
-    T: FromStr,
-    T::Err: Display,
-{
-    let text = from_utf8(bytes)?;
-    text.parse().map_err(|_| {
-        let message = format!("invalid {}: {}", desc, text);
-        FromValueError(message)
+fn parse_push(
+    size: usize,
+    mut args: VecDeque<Vec<u8>>,
+    end: ListEnd,
+) -> Result<Command, FromValueError> {
+    let name = match end {
+        ListEnd::Left => "LPUSH",
+        ListEnd::Right => "RPUSH",
+    };
+
+    if args.len() < 2 {
+        return Err(wrong_number_of_arguments(name));
+    }
+
+    let key = args.pop_front().unwrap();
+    let elements = args.into();
+
+    Ok(match end {
+        ListEnd::Left => Command::Lpush {
+            size,
+            key,
+            elements,
+        },
+        ListEnd::Right => Command::Rpush {
+            size,
+            key,
+            elements,
+        },
     })
 }
 
-#[cfg(test)]
-mod tests {
-    use core::panic;
+fn parse_pop(
+    size: usize,
+    mut args: VecDeque<Vec<u8>>,
+    end: ListEnd,
+) -> Result<Command, FromValueError> {
+    let name = match end {
+        ListEnd::Left => "LPOP",
+        ListEnd::Right => "RPOP",
+    };
+
+    if args.is_empty() || args.len() > 2 {
+        return Err(wrong_number_of_arguments(name));
+    }
 
-    use super::*;
+    let key = args.pop_front().unwrap();
+    let count = match args.pop_front() {
+        Some(count) => Some(parse_number(count, "count")?),
+        None => None,
+    };
+
+    Ok(match end {
+        ListEnd::Left => Command::Lpop { size, key, count },
+        ListEnd::Right => Command::Rpop { size, key, count },
+    })
+}
 
-    #[test]
-    fn invalid_redis_value() {
-        match from_value(Value::NullBulkString) {
-            Err(FromValueError(message)) => assert_eq!(message, "value must be a RESP array"),
-            value => panic!("expected protocol error, got {:?}", value),
-        }
+fn parse_lrange(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 3 {
+        return Err(wrong_number_of_arguments("LRANGE"));
     }
 
-    #[test]
-    fn empty_array() {
-        match from_value(Value::Array((0, vec![]))) {
-            Err(FromValueError(message)) => assert_eq!(message, "RESP array must not be empty"),
-            value => panic!("expected protocol error, got {:?}", value),
-        }
-    }
+    let key = args.pop_front().unwrap();
+    let start = parse_number(args.pop_front().unwrap(), "start index")?;
+    let stop = parse_number(args.pop_front().unwrap(), "stop index")?;
 
-    #[test]
+    Ok(Command::Lrange {
+        size,
+        key,
+        start,
+        stop,
+    })
+}
+
+fn parse_llen(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 1 {
+        return Err(wrong_number_of_arguments("LLEN"));
+    }
+
+    let key = args.pop_front().unwrap();
+    Ok(Command::Llen { size, key })
+}
+
+fn parse_lindex(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 2 {
+        return Err(wrong_number_of_arguments("LINDEX"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let index = parse_number(args.pop_front().unwrap(), "index")?;
+
+    Ok(Command::Lindex { size, key, index })
+}
+
+fn parse_lset(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 3 {
+        return Err(wrong_number_of_arguments("LSET"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let index = parse_number(args.pop_front().unwrap(), "index")?;
+    let element = args.pop_front().unwrap();
+
+    Ok(Command::Lset {
+        size,
+        key,
+        index,
+        element,
+    })
+}
+
+fn parse_lrem(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 3 {
+        return Err(wrong_number_of_arguments("LREM"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let count = parse_number(args.pop_front().unwrap(), "count")?;
+    let element = args.pop_front().unwrap();
+
+    Ok(Command::Lrem {
+        size,
+        key,
+        count,
+        element,
+    })
+}
+
+fn parse_ltrim(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 3 {
+        return Err(wrong_number_of_arguments("LTRIM"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let start = parse_number(args.pop_front().unwrap(), "start index")?;
+    let stop = parse_number(args.pop_front().unwrap(), "stop index")?;
+
+    Ok(Command::Ltrim {
+        size,
+        key,
+        start,
+        stop,
+    })
+}
+
+fn parse_linsert(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 4 {
+        return Err(wrong_number_of_arguments("LINSERT"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let before = match from_utf8(args.pop_front().unwrap())?
+        .to_uppercase()
+        .as_str()
+    {
+        "BEFORE" => true,
+        "AFTER" => false,
+        arg => return Err(FromValueError(format!("invalid LINSERT argument: {}", arg))),
+    };
+    let pivot = args.pop_front().unwrap();
+    let element = args.pop_front().unwrap();
+
+    Ok(Command::Linsert {
+        size,
+        key,
+        before,
+        pivot,
+        element,
+    })
+}
+
+fn parse_lmove(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 4 {
+        return Err(wrong_number_of_arguments("LMOVE"));
+    }
+
+    let source = args.pop_front().unwrap();
+    let destination = args.pop_front().unwrap();
+    let from = parse_list_end(args.pop_front().unwrap())?;
+    let to = parse_list_end(args.pop_front().unwrap())?;
+
+    Ok(Command::Lmove {
+        size,
+        source,
+        destination,
+        from,
+        to,
+    })
+}
+
+fn parse_blocking_pop(
+    size: usize,
+    mut args: VecDeque<Vec<u8>>,
+    end: ListEnd,
+) -> Result<Command, FromValueError> {
+    if args.len() < 2 {
+        return Err(match end {
+            ListEnd::Left => wrong_number_of_arguments("BLPOP"),
+            ListEnd::Right => wrong_number_of_arguments("BRPOP"),
+        });
+    }
+
+    let timeout = parse_timeout(args.pop_back().unwrap())?;
+    let keys = args.into();
+
+    Ok(match end {
+        ListEnd::Left => Command::Blpop {
+            size,
+            keys,
+            timeout,
+        },
+        ListEnd::Right => Command::Brpop {
+            size,
+            keys,
+            timeout,
+        },
+    })
+}
+
+fn parse_blmove(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 5 {
+        return Err(wrong_number_of_arguments("BLMOVE"));
+    }
+
+    let source = args.pop_front().unwrap();
+    let destination = args.pop_front().unwrap();
+    let from = parse_list_end(args.pop_front().unwrap())?;
+    let to = parse_list_end(args.pop_front().unwrap())?;
+    let timeout = parse_timeout(args.pop_front().unwrap())?;
+
+    Ok(Command::Blmove {
+        size,
+        source,
+        destination,
+        from,
+        to,
+        timeout,
+    })
+}
+
+/// Parses a blocking timeout, given in seconds with an optional fractional part.
+fn parse_timeout(bytes: Vec<u8>) -> Result<Duration, FromValueError> {
+    let timeout: f64 = parse_number(bytes, "timeout")?;
+
+    if timeout < 0.0 {
+        return Err("timeout is negative".into());
+    }
+
+    Duration::try_from_secs_f64(timeout).map_err(|_| "timeout is out of range".into())
+}
+
+/// Parses the commands that take nothing but a key.
+fn parse_key_only(
+    size: usize,
+    mut args: VecDeque<Vec<u8>>,
+    name: &str,
+) -> Result<Command, FromValueError> {
+    if args.len() != 1 {
+        return Err(wrong_number_of_arguments(name));
+    }
+
+    let key = args.pop_front().unwrap();
+
+    Ok(match name {
+        "HGETALL" => Command::Hgetall { size, key },
+        "HLEN" => Command::Hlen { size, key },
+        "HKEYS" => Command::Hkeys { size, key },
+        "HVALS" => Command::Hvals { size, key },
+        "SMEMBERS" => Command::Smembers { size, key },
+        "SCARD" => Command::Scard { size, key },
+        "ZCARD" => Command::Zcard { size, key },
+        "XLEN" => Command::Xlen { size, key },
+        _ => unreachable!("{} takes more than a key", name),
+    })
+}
+
+fn parse_hset(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() < 3 || args.len() % 2 == 0 {
+        return Err(wrong_number_of_arguments("HSET"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let mut pairs = Vec::with_capacity(args.len() / 2);
+
+    while let (Some(field), Some(value)) = (args.pop_front(), args.pop_front()) {
+        pairs.push((field, value));
+    }
+
+    Ok(Command::Hset { size, key, pairs })
+}
+
+fn parse_hget(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 2 {
+        return Err(wrong_number_of_arguments("HGET"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let field = args.pop_front().unwrap();
+
+    Ok(Command::Hget { size, key, field })
+}
+
+fn parse_hmget(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() < 2 {
+        return Err(wrong_number_of_arguments("HMGET"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let fields = args.into();
+
+    Ok(Command::Hmget { size, key, fields })
+}
+
+fn parse_hdel(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() < 2 {
+        return Err(wrong_number_of_arguments("HDEL"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let fields = args.into();
+
+    Ok(Command::Hdel { size, key, fields })
+}
+
+fn parse_hincrby(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 3 {
+        return Err(wrong_number_of_arguments("HINCRBY"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let field = args.pop_front().unwrap();
+    let increment = parse_number(args.pop_front().unwrap(), "increment")?;
+
+    Ok(Command::Hincrby {
+        size,
+        key,
+        field,
+        increment,
+    })
+}
+
+fn parse_hincrbyfloat(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 3 {
+        return Err(wrong_number_of_arguments("HINCRBYFLOAT"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let field = args.pop_front().unwrap();
+    let increment: f64 = parse_number(args.pop_front().unwrap(), "increment")?;
+
+    if !increment.is_finite() {
+        return Err("increment would produce NaN or Infinity".into());
+    }
+
+    Ok(Command::Hincrbyfloat {
+        size,
+        key,
+        field,
+        increment,
+    })
+}
+
+fn parse_hexists(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 2 {
+        return Err(wrong_number_of_arguments("HEXISTS"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let field = args.pop_front().unwrap();
+
+    Ok(Command::Hexists { size, key, field })
+}
+
+fn parse_hscan(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() < 2 {
+        return Err(wrong_number_of_arguments("HSCAN"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let cursor = parse_number(args.pop_front().unwrap(), "cursor")?;
+    let mut pattern = None;
+    let mut count = None;
+    let mut novalues = false;
+
+    while let Some(arg) = args.pop_front() {
+        match from_utf8(arg)?.to_uppercase().as_str() {
+            "MATCH" => {
+                let arg = args
+                    .pop_front()
+                    .ok_or_else(|| wrong_number_of_arguments("HSCAN MATCH"))?;
+                pattern = Some(arg);
+            }
+            "COUNT" => {
+                let arg = args
+                    .pop_front()
+                    .ok_or_else(|| wrong_number_of_arguments("HSCAN COUNT"))?;
+                let arg: usize = parse_number(arg, "count")?;
+
+                if arg == 0 {
+                    return Err("invalid count: 0".into());
+                }
+
+                count = Some(arg);
+            }
+            "NOVALUES" => novalues = true,
+            arg => return Err(FromValueError(format!("invalid HSCAN argument: {}", arg))),
+        }
+    }
+
+    Ok(Command::Hscan {
+        size,
+        key,
+        cursor,
+        pattern,
+        count,
+        novalues,
+    })
+}
+
+/// Parses the commands that take a key followed by one or more members.
+fn parse_key_and_members(
+    size: usize,
+    mut args: VecDeque<Vec<u8>>,
+    name: &str,
+) -> Result<Command, FromValueError> {
+    if args.len() < 2 {
+        return Err(wrong_number_of_arguments(name));
+    }
+
+    let key = args.pop_front().unwrap();
+    let members = args.into();
+
+    Ok(match name {
+        "SADD" => Command::Sadd { size, key, members },
+        "SREM" => Command::Srem { size, key, members },
+        "SMISMEMBER" => Command::Smismember { size, key, members },
+        "ZREM" => Command::Zrem { size, key, members },
+        _ => unreachable!("{} doesn't take members", name),
+    })
+}
+
+/// Parses the commands that take one or more keys.
+fn parse_keys(size: usize, args: VecDeque<Vec<u8>>, name: &str) -> Result<Command, FromValueError> {
+    if args.is_empty() {
+        return Err(wrong_number_of_arguments(name));
+    }
+
+    let keys = args.into();
+
+    Ok(match name {
+        "SINTER" => Command::Sinter { size, keys },
+        "SUNION" => Command::Sunion { size, keys },
+        "SDIFF" => Command::Sdiff { size, keys },
+        _ => unreachable!("{} doesn't take keys", name),
+    })
+}
+
+/// Parses the commands that store the result of an operation on one or more keys.
+fn parse_store(
+    size: usize,
+    mut args: VecDeque<Vec<u8>>,
+    name: &str,
+) -> Result<Command, FromValueError> {
+    if args.len() < 2 {
+        return Err(wrong_number_of_arguments(name));
+    }
+
+    let destination = args.pop_front().unwrap();
+    let keys = args.into();
+
+    Ok(match name {
+        "SINTERSTORE" => Command::Sinterstore {
+            size,
+            destination,
+            keys,
+        },
+        "SUNIONSTORE" => Command::Sunionstore {
+            size,
+            destination,
+            keys,
+        },
+        "SDIFFSTORE" => Command::Sdiffstore {
+            size,
+            destination,
+            keys,
+        },
+        _ => unreachable!("{} doesn't store", name),
+    })
+}
+
+fn parse_sismember(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 2 {
+        return Err(wrong_number_of_arguments("SISMEMBER"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let member = args.pop_front().unwrap();
+
+    Ok(Command::Sismember { size, key, member })
+}
+
+fn parse_spop(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(wrong_number_of_arguments("SPOP"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let count = match args.pop_front() {
+        Some(count) => Some(parse_number(count, "count")?),
+        None => None,
+    };
+
+    Ok(Command::Spop { size, key, count })
+}
+
+fn parse_srandmember(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(wrong_number_of_arguments("SRANDMEMBER"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let count = match args.pop_front() {
+        Some(count) => Some(parse_number(count, "count")?),
+        None => None,
+    };
+
+    Ok(Command::Srandmember { size, key, count })
+}
+
+fn parse_zadd(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() < 3 {
+        return Err(wrong_number_of_arguments("ZADD"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let mut flags = ZaddFlags::default();
+
+    while let Some(arg) = args.front() {
+        match String::from_utf8_lossy(arg).to_uppercase().as_str() {
+            "NX" => flags.nx = true,
+            "XX" => flags.xx = true,
+            "GT" => flags.gt = true,
+            "LT" => flags.lt = true,
+            "CH" => flags.ch = true,
+            "INCR" => flags.incr = true,
+            _ => break,
+        }
+
+        args.pop_front();
+    }
+
+    if args.is_empty() || args.len() % 2 != 0 {
+        return Err(wrong_number_of_arguments("ZADD"));
+    }
+
+    if flags.nx && flags.xx {
+        return Err("XX and NX options at the same time are not compatible".into());
+    }
+
+    if [flags.nx, flags.gt, flags.lt]
+        .iter()
+        .filter(|flag| **flag)
+        .count()
+        > 1
+    {
+        return Err("GT, LT, and/or NX options at the same time are not compatible".into());
+    }
+
+    if flags.incr && args.len() > 2 {
+        return Err("INCR option supports a single increment-element pair".into());
+    }
+
+    let mut elements = Vec::with_capacity(args.len() / 2);
+
+    while let (Some(score), Some(member)) = (args.pop_front(), args.pop_front()) {
+        elements.push((parse_score(score, "score")?, member));
+    }
+
+    Ok(Command::Zadd {
+        size,
+        key,
+        flags,
+        elements,
+    })
+}
+
+fn parse_zrange(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    enum RangeKind {
+        Rank,
+        Score,
+        Lex,
+    }
+
+    if args.len() < 3 {
+        return Err(wrong_number_of_arguments("ZRANGE"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let start = args.pop_front().unwrap();
+    let stop = args.pop_front().unwrap();
+    let mut kind = RangeKind::Rank;
+    let mut rev = false;
+    let mut limit = None;
+    let mut withscores = false;
+
+    while let Some(arg) = args.pop_front() {
+        match from_utf8(arg)?.to_uppercase().as_str() {
+            "BYSCORE" => kind = RangeKind::Score,
+            "BYLEX" => kind = RangeKind::Lex,
+            "REV" => rev = true,
+            "LIMIT" => {
+                let (Some(offset), Some(count)) = (args.pop_front(), args.pop_front()) else {
+                    return Err(wrong_number_of_arguments("ZRANGE LIMIT"));
+                };
+
+                limit = Some((
+                    parse_number(offset, "offset")?,
+                    parse_number(count, "count")?,
+                ));
+            }
+            "WITHSCORES" => withscores = true,
+            arg => return Err(FromValueError(format!("invalid ZRANGE argument: {}", arg))),
+        }
+    }
+
+    // with REV, the range is given from the highest end
+    let (low, high) = if rev { (stop, start) } else { (start, stop) };
+
+    let by = match kind {
+        RangeKind::Rank if limit.is_some() => {
+            return Err("LIMIT is only supported with BYSCORE or BYLEX".into());
+        }
+        RangeKind::Lex if withscores => {
+            return Err("WITHSCORES is not supported with BYLEX".into());
+        }
+        RangeKind::Rank if rev => ZrangeBy::Rank {
+            start: parse_number(high, "start")?,
+            stop: parse_number(low, "stop")?,
+        },
+        RangeKind::Rank => ZrangeBy::Rank {
+            start: parse_number(low, "start")?,
+            stop: parse_number(high, "stop")?,
+        },
+        RangeKind::Score => ZrangeBy::Score {
+            min: parse_score_bound(low)?,
+            max: parse_score_bound(high)?,
+        },
+        RangeKind::Lex => ZrangeBy::Lex {
+            min: parse_lex_bound(low)?,
+            max: parse_lex_bound(high)?,
+        },
+    };
+
+    Ok(Command::Zrange {
+        size,
+        key,
+        by,
+        rev,
+        limit,
+        withscores,
+    })
+}
+
+fn parse_zrank(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 2 {
+        return Err(wrong_number_of_arguments("ZRANK"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let member = args.pop_front().unwrap();
+
+    Ok(Command::Zrank { size, key, member })
+}
+
+fn parse_zscore(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 2 {
+        return Err(wrong_number_of_arguments("ZSCORE"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let member = args.pop_front().unwrap();
+
+    Ok(Command::Zscore { size, key, member })
+}
+
+fn parse_zincrby(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 3 {
+        return Err(wrong_number_of_arguments("ZINCRBY"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let increment = parse_score(args.pop_front().unwrap(), "increment")?;
+    let member = args.pop_front().unwrap();
+
+    Ok(Command::Zincrby {
+        size,
+        key,
+        increment,
+        member,
+    })
+}
+
+fn parse_zcount(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() != 3 {
+        return Err(wrong_number_of_arguments("ZCOUNT"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let min = parse_score_bound(args.pop_front().unwrap())?;
+    let max = parse_score_bound(args.pop_front().unwrap())?;
+
+    Ok(Command::Zcount {
+        size,
+        key,
+        min,
+        max,
+    })
+}
+
+fn parse_zpopmin(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(wrong_number_of_arguments("ZPOPMIN"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let count = match args.pop_front() {
+        Some(count) => Some(parse_number(count, "count")?),
+        None => None,
+    };
+
+    Ok(Command::Zpopmin { size, key, count })
+}
+
+/// Parses `ZUNIONSTORE` and `ZINTERSTORE`: a destination, the number of keys, the keys and
+/// then the optional `WEIGHTS` and `AGGREGATE`.
+fn parse_zstore(
+    size: usize,
+    mut args: VecDeque<Vec<u8>>,
+    name: &str,
+) -> Result<Command, FromValueError> {
+    if args.len() < 3 {
+        return Err(wrong_number_of_arguments(name));
+    }
+
+    let destination = args.pop_front().unwrap();
+    let numkeys: usize = parse_number(args.pop_front().unwrap(), "numkeys")?;
+
+    if numkeys == 0 {
+        return Err(FromValueError(format!(
+            "at least 1 input key is needed for {}",
+            name
+        )));
+    }
+
+    if args.len() < numkeys {
+        return Err(wrong_number_of_arguments(name));
+    }
+
+    let keys: Vec<_> = args.drain(..numkeys).collect();
+    let mut weights = None;
+    let mut aggregate = Aggregate::Sum;
+
+    while let Some(arg) = args.pop_front() {
+        match from_utf8(arg)?.to_uppercase().as_str() {
+            "WEIGHTS" => {
+                if args.len() < numkeys {
+                    return Err(wrong_number_of_arguments(&format!("{} WEIGHTS", name)));
+                }
+
+                let parsed: Result<_, _> = args
+                    .drain(..numkeys)
+                    .map(|weight| parse_score(weight, "weight"))
+                    .collect();
+                weights = Some(parsed?);
+            }
+            "AGGREGATE" => {
+                let arg = args
+                    .pop_front()
+                    .ok_or_else(|| wrong_number_of_arguments(&format!("{} AGGREGATE", name)))?;
+
+                aggregate = match from_utf8(arg)?.to_uppercase().as_str() {
+                    "SUM" => Aggregate::Sum,
+                    "MIN" => Aggregate::Min,
+                    "MAX" => Aggregate::Max,
+                    arg => return Err(FromValueError(format!("invalid aggregate: {}", arg))),
+                };
+            }
+            arg => {
+                return Err(FromValueError(format!(
+                    "invalid {} argument: {}",
+                    name, arg
+                )))
+            }
+        }
+    }
+
+    Ok(match name {
+        "ZUNIONSTORE" => Command::Zunionstore {
+            size,
+            destination,
+            keys,
+            weights,
+            aggregate,
+        },
+        "ZINTERSTORE" => Command::Zinterstore {
+            size,
+            destination,
+            keys,
+            weights,
+            aggregate,
+        },
+        _ => unreachable!("{} doesn't combine sorted sets", name),
+    })
+}
+
+fn parse_xadd(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() < 4 {
+        return Err(wrong_number_of_arguments("XADD"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let mut nomkstream = false;
+    let mut trim = None;
+
+    loop {
+        match args
+            .front()
+            .map(|arg| String::from_utf8_lossy(arg).to_uppercase())
+        {
+            Some(arg) if arg == "NOMKSTREAM" => {
+                args.pop_front();
+                nomkstream = true;
+            }
+            Some(arg) if arg == "MAXLEN" || arg == "MINID" => {
+                trim = Some(parse_stream_trim(&mut args, "XADD")?);
+            }
+            _ => break,
+        }
+    }
+
+    let id = args
+        .pop_front()
+        .ok_or_else(|| wrong_number_of_arguments("XADD"))?;
+
+    let id = if id == b"*" {
+        XaddId::Auto
+    } else if let Some(ms) = id.strip_suffix(b"-*") {
+        XaddId::AutoSequence(parse_number(ms.to_vec(), "stream ID")?)
+    } else {
+        XaddId::Explicit(parse_stream_id(id, 0)?)
+    };
+
+    if args.is_empty() || args.len() % 2 != 0 {
+        return Err(wrong_number_of_arguments("XADD"));
+    }
+
+    let mut fields = Vec::with_capacity(args.len() / 2);
+
+    while let (Some(field), Some(value)) = (args.pop_front(), args.pop_front()) {
+        fields.push((field, value));
+    }
+
+    Ok(Command::Xadd {
+        size,
+        key,
+        nomkstream,
+        trim,
+        id,
+        fields,
+    })
+}
+
+/// Parses XRANGE, or XREVRANGE, which takes the end before the start.
+fn parse_xrange(
+    size: usize,
+    mut args: VecDeque<Vec<u8>>,
+    rev: bool,
+) -> Result<Command, FromValueError> {
+    let name = if rev { "XREVRANGE" } else { "XRANGE" };
+
+    if args.len() != 3 && args.len() != 5 {
+        return Err(wrong_number_of_arguments(name));
+    }
+
+    let key = args.pop_front().unwrap();
+    let first = args.pop_front().unwrap();
+    let second = args.pop_front().unwrap();
+    let (start, end) = if rev {
+        (second, first)
+    } else {
+        (first, second)
+    };
+    let start = parse_range_id(start, true)?;
+    let end = parse_range_id(end, false)?;
+
+    let count = match args.pop_front() {
+        Some(arg) if from_utf8(arg.clone())?.eq_ignore_ascii_case("COUNT") => {
+            Some(parse_number(args.pop_front().unwrap(), "count")?)
+        }
+        Some(arg) => {
+            let arg = from_utf8(arg)?;
+            return Err(FromValueError(format!(
+                "invalid {} argument: {}",
+                name, arg
+            )));
+        }
+        None => None,
+    };
+
+    Ok(match rev {
+        true => Command::Xrevrange {
+            size,
+            key,
+            end,
+            start,
+            count,
+        },
+        false => Command::Xrange {
+            size,
+            key,
+            start,
+            end,
+            count,
+        },
+    })
+}
+
+fn parse_xdel(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() < 2 {
+        return Err(wrong_number_of_arguments("XDEL"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let ids = args
+        .into_iter()
+        .map(|id| parse_stream_id(id, 0))
+        .collect::<Result<_, _>>()?;
+
+    Ok(Command::Xdel { size, key, ids })
+}
+
+fn parse_xtrim(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() < 3 {
+        return Err(wrong_number_of_arguments("XTRIM"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let trim = parse_stream_trim(&mut args, "XTRIM")?;
+
+    if let Some(arg) = args.pop_front() {
+        let arg = from_utf8(arg)?;
+        return Err(FromValueError(format!("invalid XTRIM argument: {}", arg)));
+    }
+
+    Ok(Command::Xtrim { size, key, trim })
+}
+
+fn parse_xsetid(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() < 2 {
+        return Err(wrong_number_of_arguments("XSETID"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let last_id = parse_stream_id(args.pop_front().unwrap(), 0)?;
+    let mut entries_added = None;
+    let mut max_deleted_id = None;
+
+    while let Some(arg) = args.pop_front() {
+        match from_utf8(arg)?.to_uppercase().as_str() {
+            "ENTRIESADDED" => {
+                let arg = args
+                    .pop_front()
+                    .ok_or_else(|| wrong_number_of_arguments("XSETID ENTRIESADDED"))?;
+                entries_added = Some(parse_number(arg, "entries added")?);
+            }
+            "MAXDELETEDID" => {
+                let arg = args
+                    .pop_front()
+                    .ok_or_else(|| wrong_number_of_arguments("XSETID MAXDELETEDID"))?;
+                max_deleted_id = Some(parse_stream_id(arg, 0)?);
+            }
+            arg => return Err(FromValueError(format!("invalid XSETID argument: {}", arg))),
+        }
+    }
+
+    Ok(Command::Xsetid {
+        size,
+        key,
+        last_id,
+        entries_added,
+        max_deleted_id,
+    })
+}
+
+fn parse_xread(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    let mut count = None;
+    let mut block = None;
+
+    loop {
+        let arg = args
+            .pop_front()
+            .ok_or_else(|| wrong_number_of_arguments("XREAD"))?;
+
+        match from_utf8(arg)?.to_uppercase().as_str() {
+            "COUNT" => {
+                let arg = args
+                    .pop_front()
+                    .ok_or_else(|| wrong_number_of_arguments("XREAD COUNT"))?;
+
+                // like Redis, a zero count means no limit
+                count = Some(parse_number(arg, "count")?).filter(|&count| count > 0);
+            }
+            "BLOCK" => {
+                let arg = args
+                    .pop_front()
+                    .ok_or_else(|| wrong_number_of_arguments("XREAD BLOCK"))?;
+                block = Some(Duration::from_millis(parse_number(arg, "timeout")?));
+            }
+            "STREAMS" => break,
+            arg => return Err(FromValueError(format!("invalid XREAD argument: {}", arg))),
+        }
+    }
+
+    let streams = parse_streams(args, "XREAD", parse_xread_id)?;
+
+    Ok(Command::Xread {
+        size,
+        count,
+        block,
+        streams,
+    })
+}
+
+fn parse_xgroup(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    let subcommand = args
+        .pop_front()
+        .ok_or_else(|| wrong_number_of_arguments("XGROUP"))?;
+    let subcommand = from_utf8(subcommand)?.to_uppercase();
+    let name = format!("XGROUP {}", subcommand);
+
+    let arity_ok = match subcommand.as_str() {
+        "CREATE" | "SETID" => args.len() >= 3,
+        "DESTROY" => args.len() == 2,
+        "CREATECONSUMER" | "DELCONSUMER" => args.len() == 3,
+        subcommand => {
+            return Err(FromValueError(format!(
+                "invalid XGROUP subcommand: {}",
+                subcommand
+            )))
+        }
+    };
+
+    if !arity_ok {
+        return Err(wrong_number_of_arguments(&name));
+    }
+
+    let key = args.pop_front().unwrap();
+    let group = args.pop_front().unwrap();
+
+    match subcommand.as_str() {
+        "CREATE" | "SETID" => {
+            let id = parse_xread_id(args.pop_front().unwrap())?;
+            let mut mkstream = false;
+            let mut entries_read = None;
+
+            while let Some(arg) = args.pop_front() {
+                match from_utf8(arg)?.to_uppercase().as_str() {
+                    "MKSTREAM" if subcommand == "CREATE" => mkstream = true,
+                    "ENTRIESREAD" => {
+                        let arg = args
+                            .pop_front()
+                            .ok_or_else(|| wrong_number_of_arguments(&name))?;
+                        entries_read = Some(parse_number(arg, "entries read")?);
+                    }
+                    arg => {
+                        return Err(FromValueError(format!(
+                            "invalid {} argument: {}",
+                            name, arg
+                        )))
+                    }
+                }
+            }
+
+            Ok(match subcommand.as_str() {
+                "CREATE" => Command::XgroupCreate {
+                    size,
+                    key,
+                    group,
+                    id,
+                    mkstream,
+                    entries_read,
+                },
+                _ => Command::XgroupSetid {
+                    size,
+                    key,
+                    group,
+                    id,
+                    entries_read,
+                },
+            })
+        }
+        "DESTROY" => Ok(Command::XgroupDestroy { size, key, group }),
+        "CREATECONSUMER" => Ok(Command::XgroupCreateconsumer {
+            size,
+            key,
+            group,
+            consumer: args.pop_front().unwrap(),
+        }),
+        _ => Ok(Command::XgroupDelconsumer {
+            size,
+            key,
+            group,
+            consumer: args.pop_front().unwrap(),
+        }),
+    }
+}
+
+fn parse_xreadgroup(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() < 6 {
+        return Err(wrong_number_of_arguments("XREADGROUP"));
+    }
+
+    if !from_utf8(args.pop_front().unwrap())?.eq_ignore_ascii_case("GROUP") {
+        return Err("XREADGROUP must start with GROUP".into());
+    }
+
+    let group = args.pop_front().unwrap();
+    let consumer = args.pop_front().unwrap();
+    let mut count = None;
+    let mut block = None;
+    let mut noack = false;
+
+    loop {
+        let arg = args
+            .pop_front()
+            .ok_or_else(|| wrong_number_of_arguments("XREADGROUP"))?;
+
+        match from_utf8(arg)?.to_uppercase().as_str() {
+            "COUNT" => {
+                let arg = args
+                    .pop_front()
+                    .ok_or_else(|| wrong_number_of_arguments("XREADGROUP COUNT"))?;
+                count = Some(parse_number(arg, "count")?).filter(|&count| count > 0);
+            }
+            "BLOCK" => {
+                let arg = args
+                    .pop_front()
+                    .ok_or_else(|| wrong_number_of_arguments("XREADGROUP BLOCK"))?;
+                block = Some(Duration::from_millis(parse_number(arg, "timeout")?));
+            }
+            "NOACK" => noack = true,
+            "STREAMS" => break,
+            arg => {
+                return Err(FromValueError(format!(
+                    "invalid XREADGROUP argument: {}",
+                    arg
+                )))
+            }
+        }
+    }
+
+    let streams = parse_streams(args, "XREADGROUP", |id| match id.as_slice() {
+        b">" => Ok(XreadgroupId::Undelivered),
+        _ => Ok(XreadgroupId::Pending(parse_stream_id(id, 0)?)),
+    })?;
+
+    Ok(Command::Xreadgroup {
+        size,
+        group,
+        consumer,
+        count,
+        block,
+        noack,
+        streams,
+    })
+}
+
+fn parse_xack(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() < 3 {
+        return Err(wrong_number_of_arguments("XACK"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let group = args.pop_front().unwrap();
+    let ids = args
+        .into_iter()
+        .map(|id| parse_stream_id(id, 0))
+        .collect::<Result<_, _>>()?;
+
+    Ok(Command::Xack {
+        size,
+        key,
+        group,
+        ids,
+    })
+}
+
+fn parse_xpending(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() < 2 {
+        return Err(wrong_number_of_arguments("XPENDING"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let group = args.pop_front().unwrap();
+
+    if args.is_empty() {
+        return Ok(Command::Xpending {
+            size,
+            key,
+            group,
+            range: None,
+        });
+    }
+
+    let mut min_idle_time = None;
+
+    if args
+        .front()
+        .is_some_and(|arg| arg.eq_ignore_ascii_case(b"IDLE"))
+    {
+        args.pop_front();
+        let arg = args
+            .pop_front()
+            .ok_or_else(|| wrong_number_of_arguments("XPENDING"))?;
+        min_idle_time = Some(parse_number(arg, "min idle time")?);
+    }
+
+    if args.len() != 3 && args.len() != 4 {
+        return Err(wrong_number_of_arguments("XPENDING"));
+    }
+
+    let start = parse_range_id(args.pop_front().unwrap(), true)?;
+    let end = parse_range_id(args.pop_front().unwrap(), false)?;
+    let count = parse_number(args.pop_front().unwrap(), "count")?;
+
+    Ok(Command::Xpending {
+        size,
+        key,
+        group,
+        range: Some(XpendingRange {
+            min_idle_time,
+            start,
+            end,
+            count,
+            consumer: args.pop_front(),
+        }),
+    })
+}
+
+fn parse_xclaim(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() < 5 {
+        return Err(wrong_number_of_arguments("XCLAIM"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let group = args.pop_front().unwrap();
+    let consumer = args.pop_front().unwrap();
+    let min_idle_time = parse_number(args.pop_front().unwrap(), "min idle time")?;
+
+    // the IDs run until the first option
+    let mut ids = vec![];
+
+    while let Some(id) = args.front() {
+        match parse_stream_id(id.clone(), 0) {
+            Ok(id) => ids.push(id),
+            Err(_) if !ids.is_empty() => break,
+            Err(err) => return Err(err),
+        }
+
+        args.pop_front();
+    }
+
+    let mut options = XclaimOptions::default();
+
+    while let Some(arg) = args.pop_front() {
+        let option = from_utf8(arg)?.to_uppercase();
+        let mut value = || {
+            args.pop_front()
+                .ok_or_else(|| wrong_number_of_arguments(&format!("XCLAIM {}", option)))
+        };
+
+        match option.as_str() {
+            "IDLE" => options.idle = Some(parse_number(value()?, "idle time")?),
+            "TIME" => options.time = Some(parse_number(value()?, "time")?),
+            "RETRYCOUNT" => options.retry_count = Some(parse_number(value()?, "retry count")?),
+            "LASTID" => options.last_id = Some(parse_stream_id(value()?, 0)?),
+            "FORCE" => options.force = true,
+            "JUSTID" => options.justid = true,
+            option => {
+                return Err(FromValueError(format!(
+                    "invalid XCLAIM argument: {}",
+                    option
+                )))
+            }
+        }
+    }
+
+    Ok(Command::Xclaim {
+        size,
+        key,
+        group,
+        consumer,
+        min_idle_time,
+        ids,
+        options,
+    })
+}
+
+fn parse_xautoclaim(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    if args.len() < 5 {
+        return Err(wrong_number_of_arguments("XAUTOCLAIM"));
+    }
+
+    let key = args.pop_front().unwrap();
+    let group = args.pop_front().unwrap();
+    let consumer = args.pop_front().unwrap();
+    let min_idle_time = parse_number(args.pop_front().unwrap(), "min idle time")?;
+    let start = parse_range_id(args.pop_front().unwrap(), true)?;
+    let mut count = DEFAULT_AUTOCLAIM_COUNT;
+    let mut justid = false;
+
+    while let Some(arg) = args.pop_front() {
+        match from_utf8(arg)?.to_uppercase().as_str() {
+            "COUNT" => {
+                let arg = args
+                    .pop_front()
+                    .ok_or_else(|| wrong_number_of_arguments("XAUTOCLAIM COUNT"))?;
+
+                count = match parse_number(arg, "count")? {
+                    0 => return Err("COUNT must be > 0".into()),
+                    count => count,
+                };
+            }
+            "JUSTID" => justid = true,
+            arg => {
+                return Err(FromValueError(format!(
+                    "invalid XAUTOCLAIM argument: {}",
+                    arg
+                )))
+            }
+        }
+    }
+
+    Ok(Command::Xautoclaim {
+        size,
+        key,
+        group,
+        consumer,
+        min_idle_time,
+        start,
+        count,
+        justid,
+    })
+}
+
+fn parse_xinfo(size: usize, mut args: VecDeque<Vec<u8>>) -> Result<Command, FromValueError> {
+    let subcommand = args
+        .pop_front()
+        .ok_or_else(|| wrong_number_of_arguments("XINFO"))?;
+    let subcommand = from_utf8(subcommand)?.to_uppercase();
+
+    match (subcommand.as_str(), args.len()) {
+        ("STREAM", 1) => Ok(Command::XinfoStream {
+            size,
+            key: args.pop_front().unwrap(),
+        }),
+        ("GROUPS", 1) => Ok(Command::XinfoGroups {
+            size,
+            key: args.pop_front().unwrap(),
+        }),
+        ("CONSUMERS", 2) => Ok(Command::XinfoConsumers {
+            size,
+            key: args.pop_front().unwrap(),
+            group: args.pop_front().unwrap(),
+        }),
+        ("STREAM", _) | ("GROUPS", _) | ("CONSUMERS", _) => {
+            Err(wrong_number_of_arguments(&format!("XINFO {}", subcommand)))
+        }
+        (subcommand, _) => Err(FromValueError(format!(
+            "invalid XINFO subcommand: {}",
+            subcommand
+        ))),
+    }
+}
+
+/// Splits the arguments after STREAMS into keys and the IDs that follow them, one for each.
+fn parse_streams<T>(
+    mut args: VecDeque<Vec<u8>>,
+    name: &str,
+    parse_id: impl Fn(Vec<u8>) -> Result<T, FromValueError>,
+) -> Result<Vec<(Vec<u8>, T)>, FromValueError> {
+    if args.is_empty() || args.len() % 2 != 0 {
+        return Err(FromValueError(format!(
+            "unbalanced {} list of streams: each stream key needs an ID",
+            name
+        )));
+    }
+
+    let ids = args.split_off(args.len() / 2);
+    let mut streams = Vec::with_capacity(ids.len());
+
+    for (key, id) in args.into_iter().zip(ids) {
+        streams.push((key, parse_id(id)?));
+    }
+
+    Ok(streams)
+}
+
+/// Parses an ID or `$`, the stream's last ID.
+fn parse_xread_id(bytes: Vec<u8>) -> Result<XreadId, FromValueError> {
+    match bytes.as_slice() {
+        b"$" => Ok(XreadId::Last),
+        _ => Ok(XreadId::After(parse_stream_id(bytes, 0)?)),
+    }
+}
+
+/// Parses `MAXLEN|MINID [=|~] threshold`. Approximate trimming is done exactly, which keeps
+/// no more entries than asked for.
+fn parse_stream_trim(
+    args: &mut VecDeque<Vec<u8>>,
+    name: &str,
+) -> Result<StreamTrim, FromValueError> {
+    let strategy = from_utf8(args.pop_front().unwrap_or_default())?.to_uppercase();
+    let mut threshold = args.pop_front();
+
+    if matches!(threshold.as_deref(), Some(b"=") | Some(b"~")) {
+        threshold = args.pop_front();
+    }
+
+    let threshold =
+        threshold.ok_or_else(|| wrong_number_of_arguments(&format!("{} {}", name, strategy)))?;
+
+    match strategy.as_str() {
+        "MAXLEN" => Ok(StreamTrim::MaxLen(parse_number(threshold, "maxlen")?)),
+        "MINID" => Ok(StreamTrim::MinId(parse_stream_id(threshold, 0)?)),
+        strategy => Err(FromValueError(format!(
+            "invalid trim strategy: {}",
+            strategy
+        ))),
+    }
+}
+
+/// Parses a stream ID, where a missing sequence number defaults to `default_seq`.
+fn parse_stream_id(bytes: Vec<u8>, default_seq: u64) -> Result<StreamId, FromValueError> {
+    let text = from_utf8(bytes)?;
+    let invalid = || FromValueError(format!("invalid stream ID: {}", text));
+
+    let (ms, seq) = match text.split_once('-') {
+        Some((ms, seq)) => (ms.parse(), seq.parse()),
+        None => (text.parse(), Ok(default_seq)),
+    };
+
+    Ok(StreamId {
+        ms: ms.map_err(|_| invalid())?,
+        seq: seq.map_err(|_| invalid())?,
+    })
+}
+
+/// Parses an XRANGE endpoint: `-`, `+`, or an ID that covers its whole millisecond when it
+/// has no sequence number, and is left out of the range when it starts with `(`.
+fn parse_range_id(bytes: Vec<u8>, start: bool) -> Result<StreamId, FromValueError> {
+    let default_seq = if start { 0 } else { u64::MAX };
+
+    match bytes.as_slice() {
+        b"-" => Ok(StreamId::MIN),
+        b"+" => Ok(StreamId::MAX),
+        [b'(', id @ ..] => {
+            let id = parse_stream_id(id.to_vec(), default_seq)?;
+            let id = if start { id.next() } else { id.prev() };
+            id.ok_or_else(|| "invalid exclusive range ID".into())
+        }
+        _ => parse_stream_id(bytes, default_seq),
+    }
+}
+
+/// Parses a score, which may be infinite but not NaN.
+fn parse_score(bytes: Vec<u8>, desc: &str) -> Result<f64, FromValueError> {
+    let text = String::from_utf8_lossy(&bytes).to_string();
+
+    match parse_number::<f64>(bytes, desc)? {
+        score if score.is_nan() => Err(FromValueError(format!("invalid {}: {}", desc, text))),
+        score => Ok(score),
+    }
+}
+
+fn parse_score_bound(bytes: Vec<u8>) -> Result<ScoreBound, FromValueError> {
+    match bytes.strip_prefix(b"(") {
+        Some(value) => Ok(ScoreBound {
+            value: parse_score(value.to_vec(), "min or max")?,
+            exclusive: true,
+        }),
+        None => Ok(ScoreBound {
+            value: parse_score(bytes, "min or max")?,
+            exclusive: false,
+        }),
+    }
+}
+
+fn parse_lex_bound(bytes: Vec<u8>) -> Result<LexBound, FromValueError> {
+    match bytes.split_first() {
+        Some((b'-', [])) => Ok(LexBound::Lowest),
+        Some((b'+', [])) => Ok(LexBound::Highest),
+        Some((b'[', member)) => Ok(LexBound::Inclusive(member.to_vec())),
+        Some((b'(', member)) => Ok(LexBound::Exclusive(member.to_vec())),
+        _ => Err(FromValueError(format!(
+            "invalid lex range item: {}",
+            String::from_utf8_lossy(&bytes)
+        ))),
+    }
+}
+
+fn parse_list_end(bytes: Vec<u8>) -> Result<ListEnd, FromValueError> {
+    match from_utf8(bytes)?.to_uppercase().as_str() {
+        "LEFT" => Ok(ListEnd::Left),
+        "RIGHT" => Ok(ListEnd::Right),
+        arg => Err(FromValueError(format!("invalid list end: {}", arg))),
+    }
+}
+
+fn invalid_command(command: &str) -> Result<Command, FromValueError> {
+    Err(FromValueError(format!("invalid command: {}", command)))
+}
+
+fn from_utf8(bytes: Vec<u8>) -> Result<String, FromValueError> {
+    String::from_utf8(bytes).map_err(|_| "invalid UTF-8".into())
+}
+
+fn parse_number<T>(bytes: Vec<u8>, desc: &str) -> Result<T, FromValueError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let text = from_utf8(bytes)?;
+    text.parse().map_err(|_| {
+        let message = format!("invalid {}: {}", desc, text);
+        FromValueError(message)
+    })
+}
+
+/// Parses an integer argument, failing with the error Redis gives for one that isn't.
+fn parse_integer(bytes: Vec<u8>) -> Result<i64, FromValueError> {
+    parse_number(bytes, "integer")
+        .map_err(|_| FromValueError::from("value is not an integer or out of range"))
+}
+
+#[cfg(test)]
+mod tests {
+    use core::panic;
+
+    use super::*;
+
+    #[test]
+    fn invalid_redis_value() {
+        match from_value(Value::NullBulkString) {
+            Err(FromValueError(message)) => assert_eq!(message, "value must be a RESP array"),
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn empty_array() {
+        match from_value(Value::Array((0, vec![]))) {
+            Err(FromValueError(message)) => assert_eq!(message, "RESP array must not be empty"),
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
     fn malformed_array() {
         let value = Value::Array((0, vec![Value::simple_string("OK")]));
         match from_value(value) {
             Err(FromValueError(message)) => assert_eq!(
                 message,
-                "RESP array element at index 0 must be a bulk string"
+                "RESP array element at index 0 must be a bulk string"
+            ),
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn ping_without_message() {
+        let command = Command::Ping {
+            size: 0,
+            message: None,
+        };
+        assert_command_value(command, &["PING"]);
+    }
+
+    #[test]
+    fn ping_with_message() {
+        let command = Command::Ping {
+            size: 0,
+            message: Some("message".as_bytes().to_vec()),
+        };
+        assert_command_value(command, &["PING", "message"]);
+    }
+
+    #[test]
+    fn echo() {
+        let command = Command::Echo {
+            size: 0,
+            message: "message".as_bytes().to_vec(),
+        };
+
+        assert_command_value(command, &["ECHO", "message"]);
+    }
+
+    #[test]
+    fn get() {
+        let command = Command::Get {
+            size: 0,
+            key: "key".as_bytes().to_vec(),
+        };
+
+        assert_command_value(command, &["GET", "key"]);
+    }
+
+    #[test]
+    fn set_without_expiry() {
+        let command = Command::Set {
+            size: 0,
+            key: "key".as_bytes().to_vec(),
+            value: "value".as_bytes().to_vec(),
+            condition: None,
+            expiry: None,
+            get: false,
+        };
+
+        assert_command_value(command, &["SET", "key", "value"]);
+    }
+
+    #[test]
+    fn set_with_expiry() {
+        let command = Command::Set {
+            size: 0,
+            key: "key".as_bytes().to_vec(),
+            value: "value".as_bytes().to_vec(),
+            condition: Some(SetCondition::Xx),
+            expiry: Some(SetExpiry::KeepTtl),
+            get: false,
+        };
+
+        assert_command_value(command, &["SET", "key", "value", "XX", "KEEPTTL"]);
+    }
+
+    #[test]
+    fn info() {
+        let command = Command::Info {
+            size: 0,
+            sections: vec![
+                "section1".as_bytes().to_vec(),
+                "section2".as_bytes().to_vec(),
+            ],
+        };
+
+        assert_command_value(command, &["INFO", "section1", "section2"]);
+    }
+
+    #[test]
+    fn replconf() {
+        let command = Command::Replconf {
+            size: 0,
+            key: "key".as_bytes().to_vec(),
+            value: "value".as_bytes().to_vec(),
+        };
+
+        assert_command_value(command, &["REPLCONF", "key", "value"]);
+    }
+
+    #[test]
+    fn psync_empty() {
+        let command = Command::Psync {
+            size: 0,
+            master_replid: None,
+            master_repl_offset: None,
+        };
+
+        assert_command_value(command, &["PSYNC", "?", "-1"]);
+    }
+
+    #[test]
+    fn psync_present() {
+        let command = Command::Psync {
+            size: 0,
+            master_replid: Some("replid".as_bytes().to_vec()),
+            master_repl_offset: Some(100),
+        };
+
+        assert_command_value(command, &["PSYNC", "replid", "100"]);
+    }
+
+    #[test]
+    fn replicaof() {
+        let command = Command::Replicaof {
+            size: 0,
+            master: Some(("localhost".to_string(), 6379)),
+        };
+
+        assert_command_value(command, &["REPLICAOF", "localhost", "6379"]);
+    }
+
+    #[test]
+    fn replicaof_no_one() {
+        let command = Command::Replicaof {
+            size: 0,
+            master: None,
+        };
+
+        assert_command_value(command, &["REPLICAOF", "NO", "ONE"]);
+    }
+
+    #[test]
+    fn parse_ping_no_message() {
+        match from_parts(&["PING"]) {
+            Ok(Command::Ping { message: None, .. }) => {}
+            value => panic!("expected PING, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_ping_with_message() {
+        match from_parts(&["PING", "message"]) {
+            Ok(Command::Ping {
+                message: Some(bytes),
+                ..
+            }) => assert_eq!(bytes, "message".as_bytes().to_vec()),
+            value => panic!("expected PING, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_echo_ok() {
+        match from_parts(&["ECHO", "message"]) {
+            Ok(Command::Echo { message, .. }) => {
+                assert_eq!(message, "message".as_bytes().to_vec())
+            }
+            value => panic!("expected ECHO message, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_echo_wrong_args() {
+        match from_parts(&["ECHO"]) {
+            Err(FromValueError(_)) => {}
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_get_ok() {
+        match from_parts(&["GET", "key"]) {
+            Ok(Command::Get { key, .. }) => assert_eq!(key, "key".as_bytes().to_vec()),
+            value => panic!("expected GET key, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_get_wrong_args() {
+        match from_parts(&["GET"]) {
+            Err(FromValueError(_)) => {}
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_set_without_expiry() {
+        match from_parts(&["SET", "key", "value"]) {
+            Ok(Command::Set {
+                key,
+                value,
+                condition: None,
+                expiry: None,
+                get: false,
+                ..
+            }) => {
+                assert_eq!(key, "key".as_bytes().to_vec());
+                assert_eq!(value, "value".as_bytes().to_vec());
+            }
+            value => panic!("expected SET key value, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_set_px() {
+        let before = SystemTime::now();
+
+        match from_parts(&["SET", "key", "value", "PX", "1000"]) {
+            Ok(Command::Set {
+                expiry: Some(SetExpiry::At(deadline)),
+                ..
+            }) => {
+                let expiry = deadline.duration_since(before).unwrap();
+                assert!(expiry.as_millis() >= 1000 && expiry.as_millis() < 2000);
+            }
+            value => panic!("expected SET key value PX 1000, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_set_pxat() {
+        match from_parts(&["SET", "key", "value", "PXAT", "1000"]) {
+            Ok(Command::Set {
+                expiry: Some(SetExpiry::At(deadline)),
+                ..
+            }) => assert_eq!(deadline, UNIX_EPOCH + Duration::from_millis(1000)),
+            value => panic!("expected SET key value PXAT 1000, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_set_exat() {
+        match from_parts(&["SET", "key", "value", "exat", "1000"]) {
+            Ok(Command::Set {
+                expiry: Some(SetExpiry::At(deadline)),
+                ..
+            }) => assert_eq!(deadline, UNIX_EPOCH + Duration::from_secs(1000)),
+            value => panic!("expected SET key value EXAT 1000, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_set_options() {
+        match from_parts(&["SET", "key", "value", "nx", "GET", "KEEPTTL"]) {
+            Ok(Command::Set {
+                condition: Some(SetCondition::Nx),
+                expiry: Some(SetExpiry::KeepTtl),
+                get: true,
+                ..
+            }) => {}
+            value => panic!("expected SET key value NX GET KEEPTTL, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_set_conflicting_options() {
+        for args in [
+            ["NX", "XX", "GET"],
+            ["EX", "10", "KEEPTTL"],
+            ["KEEPTTL", "PX", "10"],
+        ] {
+            let mut parts = vec!["SET", "key", "value"];
+            parts.extend(args);
+
+            match from_parts(&parts) {
+                Err(FromValueError(message)) => assert_eq!(message, "syntax error"),
+                value => panic!("expected syntax error for {:?}, got {:?}", args, value),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_set_invalid_expire_time() {
+        for args in [
+            ["EX", "0"],
+            ["PX", "-5"],
+            ["EX", "9223372036854775807"],
+            ["EX", "9223372036854775"],
+            ["PX", "9223372036854775807"],
+            ["EXAT", "9223372036854776"],
+        ] {
+            let mut parts = vec!["SET", "key", "value"];
+            parts.extend(args);
+
+            match from_parts(&parts) {
+                Err(FromValueError(message)) => {
+                    assert_eq!(message, "invalid expire time in 'set' command")
+                }
+                value => panic!(
+                    "expected invalid expire time for {:?}, got {:?}",
+                    args, value
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_set_largest_expire_time_round_trip() {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let seconds = (i64::MAX as u128 - now.as_millis()) / 1000 - 60;
+
+        let command = from_parts(&["SET", "key", "value", "EX", &seconds.to_string()]).unwrap();
+        let deadline = match &command {
+            Command::Set {
+                expiry: Some(SetExpiry::At(deadline)),
+                ..
+            } => *deadline,
+            command => panic!("expected SET key value EX, got {:?}", command),
+        };
+
+        // the PXAT it's propagated as parses back to the same deadline, to the millisecond
+        match from_value(command.to_value()) {
+            Ok(Command::Set {
+                expiry: Some(SetExpiry::At(parsed)),
+                ..
+            }) => assert!(deadline.duration_since(parsed).unwrap() < Duration::from_millis(1)),
+            value => panic!("expected SET key value PXAT, got {:?}", value),
+        }
+
+        let largest = from_parts(&["SET", "key", "value", "PXAT", &i64::MAX.to_string()]);
+        assert!(from_value(largest.unwrap().to_value()).is_ok());
+    }
+
+    #[test]
+    fn parse_set_px_missing_arg() {
+        match from_parts(&["SET", "key", "value", "PX"]) {
+            Err(FromValueError(message)) => {
+                assert!(message.starts_with("wrong number of arguments"))
+            }
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_set_px_not_utf8() {
+        let value = Value::Array((
+            0,
+            vec![
+                Value::bulk_string("SET"),
+                Value::bulk_string("key"),
+                Value::bulk_string("value"),
+                Value::bulk_string("PX"),
+                Value::BulkString((0, vec![0xC3, 0x28])),
+            ],
+        ));
+
+        match Command::try_from(value) {
+            Err(FromValueError(message)) => assert_eq!(message, "invalid UTF-8"),
+            value => panic!("expected invalid UTF-8, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_set_px_not_numeric() {
+        match from_parts(&["SET", "key", "value", "PX", "abc"]) {
+            Err(FromValueError(message)) => assert!(message.starts_with("invalid integer")),
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_set_unknown_arg() {
+        match from_parts(&["SET", "key", "value", "EXPIRE", "10"]) {
+            Err(FromValueError(message)) => {
+                assert!(message.starts_with("invalid SET argument"))
+            }
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_set_wrong_number_of_args() {
+        match from_parts(&["SET", "key"]) {
+            Err(FromValueError(message)) => {
+                assert!(message.starts_with("wrong number of arguments"))
+            }
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_setex() {
+        let before = SystemTime::now();
+
+        match from_parts(&["SETEX", "key", "10", "value"]) {
+            Ok(Command::Set {
+                value,
+                expiry: Some(SetExpiry::At(deadline)),
+                ..
+            }) => {
+                assert_eq!(value, "value".as_bytes().to_vec());
+                assert_eq!(deadline.duration_since(before).unwrap().as_secs(), 10);
+            }
+            value => panic!("expected SET key value with a deadline, got {:?}", value),
+        }
+
+        match from_parts(&["PSETEX", "key", "0", "value"]) {
+            Err(FromValueError(message)) => {
+                assert_eq!(message, "invalid expire time in 'psetex' command")
+            }
+            value => panic!("expected invalid expire time, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_getex() {
+        match from_parts(&["GETEX", "key", "persist"]) {
+            Ok(Command::Getex {
+                expiry: Some(GetexExpiry::Persist),
+                ..
+            }) => {}
+            value => panic!("expected GETEX key PERSIST, got {:?}", value),
+        }
+
+        match from_parts(&["GETEX", "key", "PXAT", "1000"]) {
+            Ok(Command::Getex {
+                expiry: Some(GetexExpiry::At(deadline)),
+                ..
+            }) => assert_eq!(deadline, UNIX_EPOCH + Duration::from_millis(1000)),
+            value => panic!("expected GETEX key PXAT 1000, got {:?}", value),
+        }
+
+        match from_parts(&["GETEX", "key", "EX", "10", "PERSIST"]) {
+            Err(FromValueError(message)) => assert_eq!(message, "syntax error"),
+            value => panic!("expected syntax error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_setrange_out_of_range() {
+        match from_parts(&["SETRANGE", "key", "-1", "value"]) {
+            Err(FromValueError(message)) => assert_eq!(message, "offset is out of range"),
+            value => panic!("expected offset out of range, got {:?}", value),
+        }
+
+        match from_parts(&["SETRANGE", "key", "536870911", "value"]) {
+            Err(FromValueError(message)) => assert_eq!(
+                message,
+                "string exceeds maximum allowed size (proto-max-bulk-len)"
+            ),
+            value => panic!("expected string too large, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_integer_arguments_not_integers() {
+        let commands: [&[&str]; 9] = [
+            &["INCRBY", "key", "x"],
+            &["DECRBY", "key", "1.5"],
+            &["GETRANGE", "key", "x", "-1"],
+            &["GETRANGE", "key", "0", "x"],
+            &["SETRANGE", "key", "x", "value"],
+            &["SETEX", "key", "abc", "value"],
+            &["PSETEX", "key", "1.5", "value"],
+            &["GETEX", "key", "EX", "abc"],
+            &["GETEX", "key", "PXAT", "9223372036854775808"],
+        ];
+
+        for command in commands {
+            match from_parts(command) {
+                Err(FromValueError(message)) => {
+                    assert_eq!(message, "value is not an integer or out of range")
+                }
+                value => panic!("expected integer error for {:?}, got {:?}", command, value),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_incrbyfloat_errors() {
+        for (parts, expected) in [
+            (["INCRBYFLOAT", "key", "abc"], "value is not a valid float"),
+            (
+                ["INCRBYFLOAT", "key", "inf"],
+                "increment would produce NaN or Infinity",
+            ),
+            (
+                ["INCRBYFLOAT", "key", "nan"],
+                "increment would produce NaN or Infinity",
+            ),
+        ] {
+            match from_parts(&parts) {
+                Err(FromValueError(message)) => assert_eq!(message, expected),
+                value => panic!("expected {:?} for {:?}, got {:?}", expected, parts, value),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_mset() {
+        match from_parts(&["MSET", "a", "1", "b", "2"]) {
+            Ok(Command::Mset { pairs, .. }) => assert_eq!(
+                pairs,
+                vec![
+                    ("a".as_bytes().to_vec(), "1".as_bytes().to_vec()),
+                    ("b".as_bytes().to_vec(), "2".as_bytes().to_vec()),
+                ]
             ),
+            value => panic!("expected MSET a 1 b 2, got {:?}", value),
+        }
+
+        match from_parts(&["MSETNX", "a", "1", "b"]) {
+            Err(FromValueError(message)) => {
+                assert!(message.starts_with("wrong number of arguments"))
+            }
             value => panic!("expected protocol error, got {:?}", value),
         }
     }
 
     #[test]
-    fn ping_without_message() {
-        let command = Command::Ping {
+    fn parse_expire() {
+        match from_parts(&["PEXPIREAT", "key", "1000", "xx", "GT"]) {
+            Ok(Command::Expire {
+                deadline, flags, ..
+            }) => {
+                assert_eq!(deadline, UNIX_EPOCH + Duration::from_millis(1000));
+                assert!(flags.xx && flags.gt && !flags.nx && !flags.lt);
+            }
+            value => panic!("expected PEXPIREAT key 1000 XX GT, got {:?}", value),
+        }
+
+        // times that aren't positive are allowed, and delete the key
+        match from_parts(&["EXPIREAT", "key", "-10"]) {
+            Ok(Command::Expire { deadline, .. }) => {
+                assert_eq!(deadline, UNIX_EPOCH - Duration::from_secs(10))
+            }
+            value => panic!("expected EXPIREAT key -10, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_expire_errors() {
+        for (parts, expected) in [
+            (
+                ["EXPIRE", "key", "10", "NX", "GT"],
+                "NX and XX, GT or LT options at the same time are not compatible",
+            ),
+            (
+                ["EXPIRE", "key", "10", "GT", "LT"],
+                "GT and LT options at the same time are not compatible",
+            ),
+            (
+                ["EXPIRE", "key", "9223372036854775807", "NX", "NX"],
+                "invalid expire time in 'expire' command",
+            ),
+        ] {
+            match from_parts(&parts) {
+                Err(FromValueError(message)) => assert_eq!(message, expected),
+                value => panic!("expected {:?} for {:?}, got {:?}", expected, parts, value),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_expire_deadline_past_i64_millis() {
+        for (parts, expected) in [
+            (
+                &["EXPIRE", "key", "9223372036854775"][..],
+                "invalid expire time in 'expire' command",
+            ),
+            (
+                &["GETEX", "key", "EX", "9223372036854775"][..],
+                "invalid expire time in 'getex' command",
+            ),
+            (
+                &["SETEX", "key", "9223372036854775", "value"][..],
+                "invalid expire time in 'setex' command",
+            ),
+        ] {
+            match from_parts(parts) {
+                Err(FromValueError(message)) => assert_eq!(message, expected),
+                value => panic!("expected {:?} for {:?}, got {:?}", expected, parts, value),
+            }
+        }
+
+        // the latest deadline that fits is still propagated as a PEXPIREAT that parses back
+        let largest = from_parts(&["PEXPIREAT", "key", &i64::MAX.to_string()]).unwrap();
+        match from_value(largest.to_value()) {
+            Ok(Command::Expire { deadline, .. }) => assert_eq!(
+                deadline,
+                UNIX_EPOCH + Duration::from_millis(i64::MAX as u64)
+            ),
+            value => panic!("expected PEXPIREAT key {}, got {:?}", i64::MAX, value),
+        }
+    }
+
+    #[test]
+    fn parse_info_one_section() {
+        match from_parts(&["INFO", "replication"]) {
+            Ok(Command::Info { sections, .. }) => {
+                assert_eq!(sections, vec!["replication".as_bytes().to_vec()])
+            }
+            value => panic!("expected INFO replication, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_info_empty_sections() {
+        match from_parts(&["INFO"]) {
+            Ok(Command::Info { sections, .. }) => assert!(sections.is_empty()),
+            value => panic!("expected INFO, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_replconf_ok() {
+        match from_parts(&["REPLCONF", "key", "value"]) {
+            Ok(Command::Replconf { key, value, .. }) => {
+                assert_eq!(key, "key".as_bytes().to_vec());
+                assert_eq!(value, "value".as_bytes().to_vec());
+            }
+            value => panic!("expected REPLCONF key value, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_replconf_wrong_args() {
+        match from_parts(&["REPLCONF"]) {
+            Err(FromValueError(message)) => assert!(message.starts_with("wrong number")),
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_psync_defaults() {
+        match from_parts(&["PSYNC", "?", "-1"]) {
+            Ok(Command::Psync {
+                master_replid: None,
+                master_repl_offset: None,
+                ..
+            }) => {}
+            value => panic!("expected PSYNC with defaults, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_psync_with_options() {
+        match from_parts(&["PSYNC", "id", "0"]) {
+            Ok(Command::Psync {
+                master_replid: Some(id),
+                master_repl_offset: Some(0),
+                ..
+            }) => assert_eq!(id, "id".as_bytes().to_vec()),
+            value => panic!("expected PSYNC id 0, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_psync_invalid_offset() {
+        match from_parts(&["PSYNC", "?", "abc"]) {
+            Err(FromValueError(message)) => {
+                assert!(message.starts_with("invalid PSYNC offset"))
+            }
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_save() {
+        match from_parts(&["SAVE"]) {
+            Ok(Command::Save { .. }) => {}
+            value => panic!("expected SAVE, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_bgsave() {
+        match from_parts(&["bgsave"]) {
+            Ok(Command::Bgsave { .. }) => {}
+            value => panic!("expected BGSAVE, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_lastsave() {
+        match from_parts(&["LASTSAVE"]) {
+            Ok(Command::Lastsave { .. }) => {}
+            value => panic!("expected LASTSAVE, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_bgrewriteaof() {
+        match from_parts(&["BGREWRITEAOF"]) {
+            Ok(Command::Bgrewriteaof { .. }) => {}
+            value => panic!("expected BGREWRITEAOF, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_role() {
+        match from_parts(&["role"]) {
+            Ok(Command::Role { .. }) => {}
+            value => panic!("expected ROLE, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_replicaof_master() {
+        match from_parts(&["REPLICAOF", "localhost", "6380"]) {
+            Ok(Command::Replicaof { master, .. }) => {
+                assert_eq!(master, Some(("localhost".to_string(), 6380)))
+            }
+            value => panic!("expected REPLICAOF, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_slaveof_no_one() {
+        match from_parts(&["slaveof", "no", "one"]) {
+            Ok(Command::Replicaof { master, .. }) => assert_eq!(master, None),
+            value => panic!("expected REPLICAOF, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_replicaof_invalid_port() {
+        match from_parts(&["REPLICAOF", "localhost", "port"]) {
+            Err(FromValueError(message)) => {
+                assert!(message.starts_with("invalid master port"))
+            }
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn lpush() {
+        let command = Command::Lpush {
             size: 0,
-            message: None,
+            key: "key".as_bytes().to_vec(),
+            elements: vec!["a".as_bytes().to_vec(), "b".as_bytes().to_vec()],
         };
-        assert_command_value(command, &["PING"]);
+
+        assert_command_value(command, &["LPUSH", "key", "a", "b"]);
     }
 
     #[test]
-    fn ping_with_message() {
-        let command = Command::Ping {
+    fn lmove() {
+        let command = Command::Lmove {
             size: 0,
-            message: Some("message".as_bytes().to_vec()),
+            source: "source".as_bytes().to_vec(),
+            destination: "destination".as_bytes().to_vec(),
+            from: ListEnd::Left,
+            to: ListEnd::Right,
         };
-        assert_command_value(command, &["PING", "message"]);
+
+        assert_command_value(
+            command,
+            &["LMOVE", "source", "destination", "LEFT", "RIGHT"],
+        );
     }
 
     #[test]
-    fn echo() {
-        let command = Command::Echo {
+    fn parse_rpop_with_count() {
+        match from_parts(&["rpop", "key", "2"]) {
+            Ok(Command::Rpop {
+                key,
+                count: Some(2),
+                ..
+            }) => assert_eq!(key, "key".as_bytes().to_vec()),
+            value => panic!("expected RPOP key 2, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_lpop_negative_count() {
+        match from_parts(&["LPOP", "key", "-1"]) {
+            Err(FromValueError(message)) => assert!(message.starts_with("invalid count")),
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_lrange_negative_indexes() {
+        match from_parts(&["LRANGE", "key", "-3", "-1"]) {
+            Ok(Command::Lrange {
+                start: -3,
+                stop: -1,
+                ..
+            }) => {}
+            value => panic!("expected LRANGE key -3 -1, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_linsert_invalid_position() {
+        match from_parts(&["LINSERT", "key", "BETWEEN", "pivot", "element"]) {
+            Err(FromValueError(message)) => {
+                assert!(message.starts_with("invalid LINSERT argument"))
+            }
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_lmove_wrong_args() {
+        match from_parts(&["LMOVE", "source", "destination", "LEFT"]) {
+            Err(FromValueError(message)) => assert!(message.starts_with("wrong number")),
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn blmove() {
+        let command = Command::Blmove {
             size: 0,
-            message: "message".as_bytes().to_vec(),
+            source: "source".as_bytes().to_vec(),
+            destination: "destination".as_bytes().to_vec(),
+            from: ListEnd::Right,
+            to: ListEnd::Left,
+            timeout: Duration::from_millis(1500),
         };
 
-        assert_command_value(command, &["ECHO", "message"]);
+        assert_command_value(
+            command,
+            &["BLMOVE", "source", "destination", "RIGHT", "LEFT", "1.5"],
+        );
     }
 
     #[test]
-    fn get() {
-        let command = Command::Get {
+    fn parse_blpop_keys_and_timeout() {
+        match from_parts(&["BLPOP", "a", "b", "0.25"]) {
+            Ok(Command::Blpop { keys, timeout, .. }) => {
+                assert_eq!(keys, vec!["a".as_bytes().to_vec(), "b".as_bytes().to_vec()]);
+                assert_eq!(timeout, Duration::from_millis(250));
+            }
+            value => panic!("expected BLPOP a b 0.25, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_brpop_negative_timeout() {
+        match from_parts(&["BRPOP", "a", "-1"]) {
+            Err(FromValueError(message)) => assert_eq!(message, "timeout is negative"),
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_blpop_wrong_args() {
+        match from_parts(&["BLPOP", "a"]) {
+            Err(FromValueError(message)) => assert!(message.starts_with("wrong number")),
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn hset() {
+        let command = Command::Hset {
             size: 0,
             key: "key".as_bytes().to_vec(),
+            pairs: vec![
+                ("a".as_bytes().to_vec(), "1".as_bytes().to_vec()),
+                ("b".as_bytes().to_vec(), "2".as_bytes().to_vec()),
+            ],
         };
 
-        assert_command_value(command, &["GET", "key"]);
+        assert_command_value(command, &["HSET", "key", "a", "1", "b", "2"]);
     }
 
     #[test]
-    fn set_without_expiry() {
-        let command = Command::Set {
+    fn hscan() {
+        let command = Command::Hscan {
             size: 0,
             key: "key".as_bytes().to_vec(),
-            value: "value".as_bytes().to_vec(),
-            expiry: None,
+            cursor: 42,
+            pattern: Some("f*".as_bytes().to_vec()),
+            count: Some(5),
+            novalues: true,
         };
 
-        assert_command_value(command, &["SET", "key", "value"]);
+        assert_command_value(
+            command,
+            &[
+                "HSCAN", "key", "42", "MATCH", "f*", "COUNT", "5", "NOVALUES",
+            ],
+        );
     }
 
     #[test]
-    fn set_with_expiry() {
-        let command = Command::Set {
-            size: 0,
-            key: "key".as_bytes().to_vec(),
-            value: "value".as_bytes().to_vec(),
-            expiry: Some(Duration::from_millis(1000)),
-        };
+    fn parse_hset_missing_value() {
+        match from_parts(&["HSET", "key", "a", "1", "b"]) {
+            Err(FromValueError(message)) => assert!(message.starts_with("wrong number")),
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
 
-        assert_command_value(command, &["SET", "key", "value", "PX", "1000"]);
+    #[test]
+    fn parse_hincrbyfloat_ok() {
+        match from_parts(&["HINCRBYFLOAT", "key", "field", "-1.5"]) {
+            Ok(Command::Hincrbyfloat { increment, .. }) => assert_eq!(increment, -1.5),
+            value => panic!("expected HINCRBYFLOAT, got {:?}", value),
+        }
     }
 
     #[test]
-    fn info() {
-        let command = Command::Info {
-            size: 0,
-            sections: vec![
-                "section1".as_bytes().to_vec(),
-                "section2".as_bytes().to_vec(),
-            ],
-        };
+    fn parse_hincrby_not_an_integer() {
+        match from_parts(&["HINCRBY", "key", "field", "1.5"]) {
+            Err(FromValueError(message)) => assert!(message.starts_with("invalid increment")),
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
 
-        assert_command_value(command, &["INFO", "section1", "section2"]);
+    #[test]
+    fn parse_hscan_defaults() {
+        match from_parts(&["hscan", "key", "0"]) {
+            Ok(Command::Hscan {
+                cursor: 0,
+                pattern: None,
+                count: None,
+                novalues: false,
+                ..
+            }) => {}
+            value => panic!("expected HSCAN key 0, got {:?}", value),
+        }
     }
 
     #[test]
-    fn replconf() {
-        let command = Command::Replconf {
+    fn parse_hscan_zero_count() {
+        match from_parts(&["HSCAN", "key", "0", "COUNT", "0"]) {
+            Err(FromValueError(message)) => assert_eq!(message, "invalid count: 0"),
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn sinterstore() {
+        let command = Command::Sinterstore {
             size: 0,
-            key: "key".as_bytes().to_vec(),
-            value: "value".as_bytes().to_vec(),
+            destination: "destination".as_bytes().to_vec(),
+            keys: vec!["a".as_bytes().to_vec(), "b".as_bytes().to_vec()],
         };
 
-        assert_command_value(command, &["REPLCONF", "key", "value"]);
+        assert_command_value(command, &["SINTERSTORE", "destination", "a", "b"]);
     }
 
     #[test]
-    fn psync_empty() {
-        let command = Command::Psync {
+    fn parse_sadd_ok() {
+        match from_parts(&["sadd", "key", "a", "b"]) {
+            Ok(Command::Sadd { key, members, .. }) => {
+                assert_eq!(key, "key".as_bytes().to_vec());
+                assert_eq!(
+                    members,
+                    vec!["a".as_bytes().to_vec(), "b".as_bytes().to_vec()]
+                );
+            }
+            value => panic!("expected SADD key a b, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_srandmember_negative_count() {
+        match from_parts(&["SRANDMEMBER", "key", "-3"]) {
+            Ok(Command::Srandmember {
+                count: Some(-3), ..
+            }) => {}
+            value => panic!("expected SRANDMEMBER key -3, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn parse_sunionstore_wrong_args() {
+        match from_parts(&["SUNIONSTORE", "destination"]) {
+            Err(FromValueError(message)) => assert!(message.starts_with("wrong number")),
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+    }
+
+    #[test]
+    fn zadd() {
+        let command = Command::Zadd {
             size: 0,
-            master_replid: None,
-            master_repl_offset: None,
+            key: "key".as_bytes().to_vec(),
+            flags: ZaddFlags {
+                xx: true,
+                ch: true,
+                ..Default::default()
+            },
+            elements: vec![(1.5, "a".as_bytes().to_vec())],
         };
 
-        assert_command_value(command, &["PSYNC", "?", "-1"]);
+        assert_command_value(command, &["ZADD", "key", "XX", "CH", "1.5", "a"]);
     }
 
     #[test]
-    fn psync_present() {
-        let command = Command::Psync {
+    fn zrange_by_score_rev() {
+        let command = Command::Zrange {
             size: 0,
-            master_replid: Some("replid".as_bytes().to_vec()),
-            master_repl_offset: Some(100),
+            key: "key".as_bytes().to_vec(),
+            by: ZrangeBy::Score {
+                min: ScoreBound {
+                    value: f64::NEG_INFINITY,
+                    exclusive: false,
+                },
+                max: ScoreBound {
+                    value: 5.0,
+                    exclusive: true,
+                },
+            },
+            rev: true,
+            limit: Some((0, 10)),
+            withscores: true,
         };
 
-        assert_command_value(command, &["PSYNC", "replid", "100"]);
+        assert_command_value(
+            command,
+            &[
+                "ZRANGE",
+                "key",
+                "(5",
+                "-inf",
+                "BYSCORE",
+                "REV",
+                "LIMIT",
+                "0",
+                "10",
+                "WITHSCORES",
+            ],
+        );
     }
 
     #[test]
-    fn parse_ping_no_message() {
-        match from_parts(&["PING"]) {
-            Ok(Command::Ping { message: None, .. }) => {}
-            value => panic!("expected PING, got {:?}", value),
+    fn parse_zadd_flags() {
+        match from_parts(&["ZADD", "key", "gt", "CH", "1", "a", "+inf", "b"]) {
+            Ok(Command::Zadd {
+                flags, elements, ..
+            }) => {
+                assert!(flags.gt && flags.ch && !flags.nx);
+                assert_eq!(elements[1], (f64::INFINITY, "b".as_bytes().to_vec()));
+            }
+            value => panic!("expected ZADD key GT CH 1 a +inf b, got {:?}", value),
         }
     }
 
     #[test]
-    fn parse_ping_with_message() {
-        match from_parts(&["PING", "message"]) {
-            Ok(Command::Ping {
-                message: Some(bytes),
-                ..
-            }) => assert_eq!(bytes, "message".as_bytes().to_vec()),
-            value => panic!("expected PING, got {:?}", value),
+    fn parse_zadd_conflicting_flags() {
+        match from_parts(&["ZADD", "key", "NX", "XX", "1", "a"]) {
+            Err(FromValueError(message)) => assert!(message.contains("not compatible")),
+            value => panic!("expected protocol error, got {:?}", value),
         }
-    }
 
-    #[test]
-    fn parse_echo_ok() {
-        match from_parts(&["ECHO", "message"]) {
-            Ok(Command::Echo { message, .. }) => {
-                assert_eq!(message, "message".as_bytes().to_vec())
-            }
-            value => panic!("expected ECHO message, got {:?}", value),
+        match from_parts(&["ZADD", "key", "NX", "GT", "1", "a"]) {
+            Err(FromValueError(message)) => assert!(message.contains("not compatible")),
+            value => panic!("expected protocol error, got {:?}", value),
+        }
+
+        match from_parts(&["ZADD", "key", "INCR", "1", "a", "2", "b"]) {
+            Err(FromValueError(message)) => assert!(message.contains("single increment")),
+            value => panic!("expected protocol error, got {:?}", value),
         }
     }
 
     #[test]
-    fn parse_echo_wrong_args() {
-        match from_parts(&["ECHO"]) {
-            Err(FromValueError(_)) => {}
+    fn parse_zadd_nan_score() {
+        match from_parts(&["ZADD", "key", "nan", "a"]) {
+            Err(FromValueError(message)) => assert_eq!(message, "invalid score: nan"),
             value => panic!("expected protocol error, got {:?}", value),
         }
     }
 
     #[test]
-    fn parse_get_ok() {
-        match from_parts(&["GET", "key"]) {
-            Ok(Command::Get { key, .. }) => assert_eq!(key, "key".as_bytes().to_vec()),
-            value => panic!("expected GET key, got {:?}", value),
+    fn parse_zrange_by_lex() {
+        match from_parts(&[
+            "ZRANGE", "key", "[b", "-", "BYLEX", "REV", "LIMIT", "1", "2",
+        ]) {
+            Ok(Command::Zrange {
+                by: ZrangeBy::Lex { min, max },
+                rev: true,
+                limit: Some((1, 2)),
+                ..
+            }) => {
+                assert_eq!(min, LexBound::Lowest);
+                assert_eq!(max, LexBound::Inclusive("b".as_bytes().to_vec()));
+            }
+            value => panic!("expected ZRANGE BYLEX, got {:?}", value),
         }
     }
 
     #[test]
-    fn parse_get_wrong_args() {
-        match from_parts(&["GET"]) {
-            Err(FromValueError(_)) => {}
+    fn parse_zrange_limit_by_rank() {
+        match from_parts(&["ZRANGE", "key", "0", "-1", "LIMIT", "0", "1"]) {
+            Err(FromValueError(message)) => assert!(message.starts_with("LIMIT is only")),
             value => panic!("expected protocol error, got {:?}", value),
         }
     }
 
     #[test]
-    fn parse_set_without_expiry() {
-        match from_parts(&["SET", "key", "value"]) {
-            Ok(Command::Set {
-                key,
-                value,
-                expiry: None,
+    fn parse_zinterstore_weights() {
+        match from_parts(&[
+            "ZINTERSTORE",
+            "dest",
+            "2",
+            "a",
+            "b",
+            "WEIGHTS",
+            "2",
+            "0.5",
+            "AGGREGATE",
+            "max",
+        ]) {
+            Ok(Command::Zinterstore {
+                keys,
+                weights,
+                aggregate,
                 ..
             }) => {
-                assert_eq!(key, "key".as_bytes().to_vec());
-                assert_eq!(value, "value".as_bytes().to_vec());
+                assert_eq!(keys.len(), 2);
+                assert_eq!(weights, Some(vec![2.0, 0.5]));
+                assert_eq!(aggregate, Aggregate::Max);
             }
-            value => panic!("expected SET key value, got {:?}", value),
+            value => panic!("expected ZINTERSTORE with weights, got {:?}", value),
         }
     }
 
     #[test]
-    fn parse_set_px() {
-        match from_parts(&["SET", "key", "value", "PX", "1000"]) {
-            Ok(Command::Set {
-                expiry: Some(duration),
-                ..
-            }) if duration.as_millis() == 1000 => {}
-            value => panic!("expected SET key value PX 1000, got {:?}", value),
+    fn parse_zunionstore_missing_keys() {
+        match from_parts(&["ZUNIONSTORE", "dest", "3", "a", "b"]) {
+            Err(FromValueError(message)) => assert!(message.starts_with("wrong number")),
+            value => panic!("expected protocol error, got {:?}", value),
         }
-    }
 
-    #[test]
-    fn parse_set_px_missing_arg() {
-        match from_parts(&["SET", "key", "value", "PX"]) {
-            Err(FromValueError(message)) => {
-                assert!(message.starts_with("wrong number of arguments"))
-            }
+        match from_parts(&["ZUNIONSTORE", "dest", "0", "a"]) {
+            Err(FromValueError(message)) => assert!(message.starts_with("at least 1")),
             value => panic!("expected protocol error, got {:?}", value),
         }
     }
 
     #[test]
-    fn parse_set_px_not_utf8() {
-        let value = Value::Array((
-            0,
-            vec![
-                Value::bulk_string("SET"),
-                Value::bulk_string("key"),
-                Value::bulk_string("value"),
-                Value::bulk_string("PX"),
-                Value::BulkString((0, vec![0xC3, 0x28])),
-            ],
-        ));
+    fn xadd() {
+        let command = Command::Xadd {
+            size: 0,
+            key: "key".as_bytes().to_vec(),
+            nomkstream: false,
+            trim: Some(StreamTrim::MaxLen(10)),
+            id: XaddId::AutoSequence(5),
+            fields: vec![("field".as_bytes().to_vec(), "value".as_bytes().to_vec())],
+        };
 
-        match Command::try_from(value) {
-            Err(FromValueError(message)) => assert_eq!(message, "invalid UTF-8"),
-            value => panic!("expected invalid UTF-8, got {:?}", value),
-        }
+        assert_command_value(
+            command,
+            &["XADD", "key", "MAXLEN", "10", "5-*", "field", "value"],
+        );
     }
 
     #[test]
-    fn parse_set_px_not_numeric() {
-        match from_parts(&["SET", "key", "value", "PX", "abc"]) {
-            Err(FromValueError(message)) => assert!(message.starts_with("invalid integer")),
-            value => panic!("expected protocol error, got {:?}", value),
+    fn parse_xadd_options() {
+        match from_parts(&[
+            "XADD",
+            "key",
+            "NOMKSTREAM",
+            "MINID",
+            "~",
+            "3-1",
+            "*",
+            "f",
+            "v",
+        ]) {
+            Ok(Command::Xadd {
+                nomkstream: true,
+                trim: Some(StreamTrim::MinId(StreamId { ms: 3, seq: 1 })),
+                id: XaddId::Auto,
+                fields,
+                ..
+            }) => assert_eq!(fields.len(), 1),
+            value => panic!("expected XADD with options, got {:?}", value),
+        }
+
+        match from_parts(&["XADD", "key", "7", "f", "v"]) {
+            Ok(Command::Xadd {
+                id: XaddId::Explicit(StreamId { ms: 7, seq: 0 }),
+                ..
+            }) => {}
+            value => panic!("expected XADD key 7, got {:?}", value),
         }
     }
 
     #[test]
-    fn parse_set_unknown_arg() {
-        match from_parts(&["SET", "key", "value", "EX"]) {
-            Err(FromValueError(message)) => {
-                assert!(message.starts_with("invalid SET argument"))
-            }
-            value => panic!("expected protocol error, got {:?}", value),
-        }
+    fn xread() {
+        let command = Command::Xread {
+            size: 0,
+            count: Some(2),
+            block: Some(Duration::from_millis(1500)),
+            streams: vec![
+                (
+                    "a".as_bytes().to_vec(),
+                    XreadId::After(StreamId { ms: 5, seq: 1 }),
+                ),
+                ("b".as_bytes().to_vec(), XreadId::Last),
+            ],
+        };
+
+        assert_command_value(
+            command,
+            &[
+                "XREAD", "COUNT", "2", "BLOCK", "1500", "STREAMS", "a", "b", "5-1", "$",
+            ],
+        );
     }
 
     #[test]
-    fn parse_set_wrong_number_of_args() {
-        match from_parts(&["SET", "key"]) {
-            Err(FromValueError(message)) => {
-                assert!(message.starts_with("wrong number of arguments"))
-            }
+    fn parse_xread_unbalanced_streams() {
+        match from_parts(&["XREAD", "STREAMS", "a", "b", "0"]) {
+            Err(FromValueError(message)) => assert!(message.starts_with("unbalanced")),
             value => panic!("expected protocol error, got {:?}", value),
         }
-    }
 
-    #[test]
-    fn parse_info_one_section() {
-        match from_parts(&["INFO", "replication"]) {
-            Ok(Command::Info { sections, .. }) => {
-                assert_eq!(sections, vec!["replication".as_bytes().to_vec()])
-            }
-            value => panic!("expected INFO replication, got {:?}", value),
+        match from_parts(&["XREAD", "COUNT", "1"]) {
+            Err(FromValueError(message)) => assert!(message.starts_with("wrong number")),
+            value => panic!("expected protocol error, got {:?}", value),
         }
     }
 
     #[test]
-    fn parse_info_empty_sections() {
-        match from_parts(&["INFO"]) {
-            Ok(Command::Info { sections, .. }) => assert!(sections.is_empty()),
-            value => panic!("expected INFO, got {:?}", value),
-        }
+    fn xreadgroup() {
+        let command = Command::Xreadgroup {
+            size: 0,
+            group: "group".as_bytes().to_vec(),
+            consumer: "alice".as_bytes().to_vec(),
+            count: Some(10),
+            block: None,
+            noack: true,
+            streams: vec![
+                ("a".as_bytes().to_vec(), XreadgroupId::Undelivered),
+                (
+                    "b".as_bytes().to_vec(),
+                    XreadgroupId::Pending(StreamId::MIN),
+                ),
+            ],
+        };
+
+        assert_command_value(
+            command,
+            &[
+                "XREADGROUP",
+                "GROUP",
+                "group",
+                "alice",
+                "COUNT",
+                "10",
+                "NOACK",
+                "STREAMS",
+                "a",
+                "b",
+                ">",
+                "0-0",
+            ],
+        );
     }
 
     #[test]
-    fn parse_replconf_ok() {
-        match from_parts(&["REPLCONF", "key", "value"]) {
-            Ok(Command::Replconf { key, value, .. }) => {
-                assert_eq!(key, "key".as_bytes().to_vec());
-                assert_eq!(value, "value".as_bytes().to_vec());
-            }
-            value => panic!("expected REPLCONF key value, got {:?}", value),
+    fn parse_xgroup() {
+        match from_parts(&["XGROUP", "CREATE", "key", "group", "$", "MKSTREAM"]) {
+            Ok(Command::XgroupCreate {
+                id: XreadId::Last,
+                mkstream: true,
+                entries_read: None,
+                ..
+            }) => {}
+            value => panic!("expected XGROUP CREATE, got {:?}", value),
         }
-    }
 
-    #[test]
-    fn parse_replconf_wrong_args() {
-        match from_parts(&["REPLCONF"]) {
+        match from_parts(&["XGROUP", "SETID", "key", "group", "5", "ENTRIESREAD", "3"]) {
+            Ok(Command::XgroupSetid {
+                id: XreadId::After(StreamId { ms: 5, seq: 0 }),
+                entries_read: Some(3),
+                ..
+            }) => {}
+            value => panic!("expected XGROUP SETID, got {:?}", value),
+        }
+
+        match from_parts(&["XGROUP", "DESTROY", "key"]) {
             Err(FromValueError(message)) => assert!(message.starts_with("wrong number")),
             value => panic!("expected protocol error, got {:?}", value),
         }
     }
 
     #[test]
-    fn parse_psync_defaults() {
-        match from_parts(&["PSYNC", "?", "-1"]) {
-            Ok(Command::Psync {
-                master_replid: None,
-                master_repl_offset: None,
+    fn parse_xclaim_options() {
+        match from_parts(&[
+            "XCLAIM",
+            "key",
+            "group",
+            "bob",
+            "3600",
+            "1-0",
+            "2-0",
+            "TIME",
+            "123",
+            "RETRYCOUNT",
+            "2",
+            "FORCE",
+            "JUSTID",
+        ]) {
+            Ok(Command::Xclaim {
+                min_idle_time: 3600,
+                ids,
+                options,
                 ..
-            }) => {}
-            value => panic!("expected PSYNC with defaults, got {:?}", value),
+            }) => {
+                assert_eq!(ids.len(), 2);
+                assert_eq!(
+                    options,
+                    XclaimOptions {
+                        time: Some(123),
+                        retry_count: Some(2),
+                        force: true,
+                        justid: true,
+                        ..XclaimOptions::default()
+                    }
+                );
+            }
+            value => panic!("expected XCLAIM with options, got {:?}", value),
+        }
+
+        match from_parts(&["XAUTOCLAIM", "key", "group", "bob", "0", "0", "COUNT", "0"]) {
+            Err(FromValueError(message)) => assert_eq!(message, "COUNT must be > 0"),
+            value => panic!("expected protocol error, got {:?}", value),
         }
     }
 
     #[test]
-    fn parse_psync_with_options() {
-        match from_parts(&["PSYNC", "id", "0"]) {
-            Ok(Command::Psync {
-                master_replid: Some(id),
-                master_repl_offset: Some(0),
-                ..
-            }) => assert_eq!(id, "id".as_bytes().to_vec()),
-            value => panic!("expected PSYNC id 0, got {:?}", value),
+    fn parse_xadd_invalid_id() {
+        match from_parts(&["XADD", "key", "1-x", "f", "v"]) {
+            Err(FromValueError(message)) => assert_eq!(message, "invalid stream ID: 1-x"),
+            value => panic!("expected protocol error, got {:?}", value),
         }
     }
 
     #[test]
-    fn parse_psync_invalid_offset() {
-        match from_parts(&["PSYNC", "?", "abc"]) {
-            Err(FromValueError(message)) => {
-                assert!(message.starts_with("invalid PSYNC offset"))
+    fn parse_xrange_bounds() {
+        match from_parts(&["XRANGE", "key", "(5-3", "7", "COUNT", "2"]) {
+            Ok(Command::Xrange {
+                start,
+                end,
+                count: Some(2),
+                ..
+            }) => {
+                assert_eq!(start, StreamId { ms: 5, seq: 4 });
+                assert_eq!(
+                    end,
+                    StreamId {
+                        ms: 7,
+                        seq: u64::MAX
+                    }
+                );
             }
-            value => panic!("expected protocol error, got {:?}", value),
+            value => panic!("expected XRANGE, got {:?}", value),
+        }
+
+        match from_parts(&["XREVRANGE", "key", "+", "(5-0"]) {
+            Ok(Command::Xrevrange { start, end, .. }) => {
+                assert_eq!(start, StreamId { ms: 5, seq: 1 });
+                assert_eq!(end, StreamId::MAX);
+            }
+            value => panic!("expected XREVRANGE, got {:?}", value),
         }
     }
 
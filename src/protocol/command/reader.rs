@@ -5,6 +5,7 @@ use tokio::io::AsyncBufRead;
 
 pub struct CommandReader<R> {
     reader: ValueReader<R>,
+    bytes_read: usize,
 }
 #[derive(Debug, thiserror::Error)]
 pub enum CommandReadError {
@@ -20,12 +21,51 @@ where
     R: AsyncBufRead + Unpin,
 {
     pub fn new(reader: ValueReader<R>) -> Self {
-        Self { reader }
+        Self {
+            reader,
+            bytes_read: 0,
+        }
+    }
+
+    /// Total size of the complete values read so far, in bytes.
+    pub fn bytes_read(&self) -> usize {
+        self.bytes_read
     }
 
     pub async fn read(&mut self) -> Result<Command, CommandReadError> {
-        match self.reader.read().await {
-            Ok(value) => parse_command(value),
+        let value = self.read_value().await?;
+        parse_command(value)
+    }
+
+    /// Reads a command along with the exact bytes it was sent as, as needed to pass a
+    /// replication stream on unchanged. The bytes come back for invalid commands too.
+    pub async fn read_with_bytes(&mut self) -> (Result<Command, CommandReadError>, Vec<u8>) {
+        let (result, bytes) = self.reader.read_with_bytes().await;
+
+        // an invalid value still takes up its part of the stream
+        if let Err(ValueReadError::Invalid { .. }) = result {
+            self.bytes_read += bytes.len();
+        }
+
+        let result = self.check_value(result).and_then(parse_command);
+        (result, bytes)
+    }
+
+    async fn read_value(&mut self) -> Result<Value, CommandReadError> {
+        let result = self.reader.read().await;
+        self.check_value(result)
+    }
+
+    /// Counts the bytes of a value that was read, or turns a failed read into the matching error.
+    fn check_value(
+        &mut self,
+        result: Result<Value, ValueReadError>,
+    ) -> Result<Value, CommandReadError> {
+        match result {
+            Ok(value) => {
+                self.bytes_read += value.size();
+                Ok(value)
+            }
             Err(ValueReadError::EndOfInput) => Err(CommandReadError::Stop(None)),
             Err(ValueReadError::Invalid { message, .. }) => {
                 let message = format!("invalid RESP value: {}", message);
@@ -45,10 +85,83 @@ fn parse_command(value: Value) -> Result<Command, CommandReadError> {
     match Command::try_from(value) {
         Ok(command) => Ok(command),
         Err(err) => {
-            let message = format!("{}", err);
+            let message = format!("ERR {}", err);
             Err(CommandReadError::Invalid(vec![Value::simple_error(
                 &message,
             )]))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn read_with_bytes_keeps_encoding() {
+        let input = "*3\r\n$3\r\nset\r\n$3\r\nkey\r\n$5\r\nvalue\r\n*1\r\n$4\r\nPING\r\n";
+        let mut reader = CommandReader::new(ValueReader::new(BufReader::new(input.as_bytes())));
+
+        let (command, bytes) = reader.read_with_bytes().await;
+        assert!(matches!(command, Ok(Command::Set { .. })));
+        assert_eq!(bytes, input.as_bytes()[..33].to_vec());
+        assert_eq!(reader.bytes_read(), 33);
+
+        let (command, bytes) = reader.read_with_bytes().await;
+        assert!(matches!(command, Ok(Command::Ping { .. })));
+        assert_eq!(bytes, "*1\r\n$4\r\nPING\r\n".as_bytes().to_vec());
+        assert_eq!(reader.bytes_read(), input.len());
+    }
+
+    #[tokio::test]
+    async fn read_with_bytes_keeps_non_canonical_encoding() {
+        let input = "*3\r\n$3\r\nset\r\n$03\r\nkey\r\n$5\r\nvalue\r\n";
+        let mut reader = CommandReader::new(ValueReader::new(BufReader::new(input.as_bytes())));
+
+        let command = match reader.read_with_bytes().await {
+            (Ok(command @ Command::Set { .. }), bytes) => {
+                assert_eq!(bytes, input.as_bytes());
+                command
+            }
+            (command, _) => panic!("expected SET, got {:?}", command),
+        };
+
+        assert_ne!(command.to_value().to_bytes(), input.as_bytes());
+        assert_eq!(reader.bytes_read(), input.len());
+    }
+
+    #[tokio::test]
+    async fn read_with_bytes_of_invalid_commands() {
+        let input = "*1\r\n$4\r\nNOPE\r\n:x\r\n*1\r\n$4\r\nPING\r\n";
+        let mut reader = CommandReader::new(ValueReader::new(BufReader::new(input.as_bytes())));
+
+        let (command, bytes) = reader.read_with_bytes().await;
+        assert!(matches!(command, Err(CommandReadError::Invalid(_))));
+        assert_eq!(bytes, "*1\r\n$4\r\nNOPE\r\n".as_bytes());
+
+        let (command, bytes) = reader.read_with_bytes().await;
+        assert!(matches!(command, Err(CommandReadError::Invalid(_))));
+        assert_eq!(bytes, ":x\r\n".as_bytes());
+        assert_eq!(reader.bytes_read(), 18);
+
+        let (command, _) = reader.read_with_bytes().await;
+        assert!(matches!(command, Ok(Command::Ping { .. })));
+        assert_eq!(reader.bytes_read(), input.len());
+    }
+    #[tokio::test]
+    async fn read_replies_to_invalid_commands_with_err() {
+        let input = "*3\r\n$6\r\nINCRBY\r\n$3\r\nkey\r\n$1\r\nx\r\n";
+        let mut reader = CommandReader::new(ValueReader::new(BufReader::new(input.as_bytes())));
+
+        match reader.read().await {
+            Err(CommandReadError::Invalid(values)) => assert_eq!(
+                values,
+                vec![Value::simple_error(
+                    "ERR value is not an integer or out of range"
+                )]
+            ),
+            command => panic!("expected an invalid command, got {:?}", command),
+        }
+    }
+}
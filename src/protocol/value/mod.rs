@@ -16,6 +16,8 @@ pub enum Value {
 
     BulkBytes((usize, Vec<u8>)),
 
+    Raw((usize, Vec<u8>)),
+
     Array((usize, Vec<Value>)),
 
     NullBulkString,
@@ -51,6 +53,17 @@ impl Value {
         build_command(command, args.iter().map(|arg| arg.as_bytes().to_vec()))
     }
 
+    /// Bytes that are already RESP encoded, written to the wire verbatim.
+    pub fn raw(bytes: Vec<u8>) -> Self {
+        Self::Raw((bytes.len(), bytes))
+    }
+
+    pub fn array(values: Vec<Value>) -> Self {
+        let len_size = values.len().to_string().len();
+        let parts_size: usize = values.iter().map(Value::size).sum();
+        Self::Array((len_size + 3 + parts_size, values))
+    }
+
     pub fn integer(value: i64) -> Self {
         let size = value.to_string().len();
         Self::Integer((size, value))
@@ -67,6 +80,7 @@ impl Value {
             Value::Integer((size, _)) => *size,
             Value::BulkString((size, _)) => *size,
             Value::BulkBytes((size, _)) => *size,
+            Value::Raw((size, _)) => *size,
             Value::Array((size, _)) => *size,
             Value::NullBulkString => 5,
             Value::NullArray => 5,
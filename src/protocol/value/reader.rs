@@ -22,6 +22,9 @@ pub enum ValueReadError {
 
 pub struct ValueReader<R> {
     reader: R,
+    /// A copy of the bytes consumed while reading a value, kept by
+    /// [`read_with_bytes`](Self::read_with_bytes).
+    captured: Option<Vec<u8>>,
 }
 
 impl<R> ValueReader<R>
@@ -29,7 +32,10 @@ where
     R: AsyncBufRead + Unpin,
 {
     pub fn new(reader: R) -> Self {
-        Self { reader }
+        Self {
+            reader,
+            captured: None,
+        }
     }
 
     pub async fn read(&mut self) -> Result<Value, ValueReadError> {
@@ -51,18 +57,46 @@ where
         }
     }
 
+    /// Reads a value along with the exact bytes it was read from, which differ from
+    /// [`Value::to_bytes`] when the sender didn't use the canonical encoding. The bytes come
+    /// back even when reading fails, up to where it stopped.
+    pub async fn read_with_bytes(&mut self) -> (Result<Value, ValueReadError>, Vec<u8>) {
+        self.captured = Some(Vec::new());
+        let result = self.read().await;
+        (result, self.captured.take().unwrap_or_default())
+    }
+
     async fn read_char(&mut self) -> Result<char, ValueReadError> {
-        let ch = self
+        let byte = self
             .reader
             .read_u8()
             .await
             .map_err(|error| match error.kind() {
                 io::ErrorKind::UnexpectedEof => ValueReadError::EndOfInput,
                 _ => ValueReadError::Io(error),
-            })?
-            .into();
+            })?;
+
+        self.capture(&[byte]);
+        Ok(byte.into())
+    }
 
-        Ok(ch)
+    /// Reads exactly enough bytes to fill `data`.
+    async fn read_exact(&mut self, data: &mut [u8]) -> Result<(), ValueReadError> {
+        self.reader.read_exact(data).await?;
+        self.capture(data);
+        Ok(())
+    }
+
+    /// Consumes `len` bytes from the reader's buffer, which start with `bytes`.
+    fn consume(&mut self, bytes: &[u8], len: usize) {
+        self.reader.consume(len);
+        self.capture(&bytes[..len]);
+    }
+
+    fn capture(&mut self, bytes: &[u8]) {
+        if let Some(captured) = &mut self.captured {
+            captured.extend_from_slice(bytes);
+        }
     }
 
     pub async fn read_bytes(&mut self) -> Result<(usize, Vec<u8>), ValueReadError> {
@@ -82,7 +116,7 @@ where
         }
 
         let mut data = vec![0u8; length as usize];
-        self.reader.read_exact(&mut data).await?;
+        self.read_exact(&mut data).await?;
         Ok((length as usize + length_size + 1, data))
     }
 
@@ -108,7 +142,7 @@ where
         }
 
         let mut data = vec![0u8; length as usize + 2];
-        self.reader.read_exact(&mut data).await?;
+        self.read_exact(&mut data).await?;
 
         if data[data.len() - 2..] != [0xd, 0xa] {
             return Err(ValueReadError::Invalid {
@@ -144,11 +178,10 @@ where
     async fn read_line_bytes(&mut self) -> Result<(usize, Vec<u8>), ValueReadError> {
         let mut line = Vec::new();
         let mut cr_found = false;
-        let reader = &mut self.reader;
         let mut total_bytes_read = 0;
 
         loop {
-            let bytes = reader.fill_buf().await?.to_vec();
+            let bytes = self.reader.fill_buf().await?.to_vec();
             let bytes_read = bytes.len();
 
             if bytes_read == 0 {
@@ -158,14 +191,14 @@ where
             // edge case: CR and LF across two reads
             if cr_found && bytes[0] == 0xa {
                 line.pop();
-                reader.consume(1);
+                self.consume(&bytes, 1);
                 return Ok((total_bytes_read + 1, line));
             }
 
             for i in 0..bytes_read - 1 {
                 if bytes[i] == 0xd && bytes[i + 1] == 0xa {
                     line.extend(&bytes[0..i]);
-                    reader.consume(i + 2);
+                    self.consume(&bytes, i + 2);
                     return Ok((total_bytes_read + i + 2, line));
                 }
             }
@@ -175,7 +208,7 @@ where
             }
 
             // EOL not reached; append data to buffer and loop
-            reader.consume(bytes_read);
+            self.consume(&bytes, bytes_read);
             line.extend(bytes);
             total_bytes_read += bytes_read;
         }
@@ -390,6 +423,31 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn read_with_bytes_non_canonical() {
+        let input = "*2\r\n$04\r\nECHO\r\n:+1\r\n+OK\r\n";
+        let mut parser = ValueReader::new(BufReader::new(input.as_bytes()));
+
+        let (value, bytes) = parser.read_with_bytes().await;
+        assert_eq!(
+            value.unwrap().to_bytes(),
+            "*2\r\n$4\r\nECHO\r\n:1\r\n".as_bytes()
+        );
+        assert_eq!(bytes, "*2\r\n$04\r\nECHO\r\n:+1\r\n".as_bytes());
+
+        let (_, bytes) = parser.read_with_bytes().await;
+        assert_eq!(bytes, "+OK\r\n".as_bytes());
+    }
+
+    #[tokio::test]
+    async fn read_with_bytes_invalid() {
+        let mut parser = ValueReader::new(BufReader::new(":x\r\n".as_bytes()));
+
+        let (value, bytes) = parser.read_with_bytes().await;
+        assert!(matches!(value, Err(ValueReadError::Invalid { .. })));
+        assert_eq!(bytes, ":x\r\n".as_bytes());
+    }
+
     async fn read_value(buffer: &str) -> Result<Value, ValueReadError> {
         let bytes = buffer.as_bytes();
         let reader = BufReader::new(bytes);
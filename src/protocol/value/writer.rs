@@ -15,67 +15,106 @@ where
     }
 
     pub async fn write(&mut self, value: &Value) -> io::Result<()> {
-        let _ = match value {
-            Value::SimpleString((_, val)) => self.write_simple_string(val).await,
-            Value::SimpleError((_, val)) => self.write_simple_error(val).await,
-            Value::Integer((_, val)) => self.write_integer(*val).await,
-            Value::BulkString((_, bytes)) => self.write_bulk_string(bytes).await,
-            Value::BulkBytes((_, bytes)) => self.write_bulk_bytes(bytes).await,
-            Value::Array((_, values)) => self.write_array(values).await,
-            Value::NullBulkString => self.write_null_bulk_string().await,
-            Value::NullArray => self.write_null_array().await,
-        };
-
+        self.writer.write_all(&value.to_bytes()).await?;
         self.writer.flush().await
     }
+}
 
-    async fn write_simple_string(&mut self, val: &str) -> io::Result<()> {
-        let value = format!("+{}\r\n", val);
-        self.writer.write_all(value.as_bytes()).await
+impl Value {
+    /// Serializes this value using the RESP wire format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        encode(self, &mut buffer);
+        buffer
     }
+}
 
-    async fn write_simple_error(&mut self, val: &str) -> io::Result<()> {
-        let value = format!("-{}\r\n", val);
-        self.writer.write_all(value.as_bytes()).await
-    }
+fn encode(value: &Value, buffer: &mut Vec<u8>) {
+    match value {
+        Value::SimpleString((_, val)) => encode_line(buffer, '+', val),
+        Value::SimpleError((_, val)) => encode_line(buffer, '-', val),
+        Value::Integer((_, val)) => encode_line(buffer, ':', &val.to_string()),
+        Value::BulkString((_, bytes)) => {
+            encode_bulk_bytes(buffer, bytes);
+            buffer.extend_from_slice("\r\n".as_bytes());
+        }
+        Value::BulkBytes((_, bytes)) => encode_bulk_bytes(buffer, bytes),
+        Value::Raw((_, bytes)) => buffer.extend_from_slice(bytes),
+        Value::Array((_, values)) => {
+            encode_line(buffer, '*', &values.len().to_string());
 
-    async fn write_integer(&mut self, val: i64) -> io::Result<()> {
-        let value = format!(":{}\r\n", val);
-        self.writer.write_all(value.as_bytes()).await
+            for value in values {
+                encode(value, buffer);
+            }
+        }
+        Value::NullBulkString => buffer.extend_from_slice("$-1\r\n".as_bytes()),
+        Value::NullArray => buffer.extend_from_slice("*-1\r\n".as_bytes()),
     }
+}
 
-    async fn write_bulk_string(&mut self, bytes: &[u8]) -> io::Result<()> {
-        self.write_bulk_bytes(bytes).await?;
-        self.writer.write_all("\r\n".as_bytes()).await
-    }
+fn encode_line(buffer: &mut Vec<u8>, prefix: char, line: &str) {
+    buffer.push(prefix as u8);
+    buffer.extend_from_slice(line.as_bytes());
+    buffer.extend_from_slice("\r\n".as_bytes());
+}
+
+fn encode_bulk_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    encode_line(buffer, '$', &bytes.len().to_string());
+    buffer.extend_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    async fn write_bulk_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
-        let len = bytes.len().to_string();
-        self.writer.write_all("$".as_bytes()).await?;
-        self.writer.write_all(len.as_bytes()).await?;
-        self.writer.write_all("\r\n".as_bytes()).await?;
-        self.writer.write_all(bytes).await
+    #[test]
+    fn simple_values() {
+        assert_eq!(Value::simple_string("OK").to_bytes(), "+OK\r\n".as_bytes());
+        assert_eq!(
+            Value::simple_error("ERR x").to_bytes(),
+            "-ERR x\r\n".as_bytes()
+        );
+        assert_eq!(Value::integer(-12).to_bytes(), ":-12\r\n".as_bytes());
+        assert_eq!(Value::NullBulkString.to_bytes(), "$-1\r\n".as_bytes());
+        assert_eq!(Value::NullArray.to_bytes(), "*-1\r\n".as_bytes());
     }
 
-    async fn write_array(&mut self, values: &[Value]) -> io::Result<()> {
-        self.writer.write_all("*".as_bytes()).await?;
-        self.writer
-            .write_all(values.len().to_string().as_bytes())
-            .await?;
-        self.writer.write_all("\r\n".as_bytes()).await?;
+    #[test]
+    fn bulk_bytes_have_no_trailing_crlf() {
+        let value = Value::BulkBytes((0, "RDB".as_bytes().to_vec()));
+        assert_eq!(value.to_bytes(), "$3\r\nRDB".as_bytes());
+    }
 
-        for value in values {
-            Box::pin(self.write(value)).await?;
-        }
+    #[test]
+    fn command() {
+        let value = Value::command_str("SET", &["key", "value"]);
+        let expected = "*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n";
+        assert_eq!(value.to_bytes(), expected.as_bytes());
+    }
 
-        Ok(())
+    #[test]
+    fn nested_array() {
+        let value = Value::array(vec![
+            Value::bulk_string("master"),
+            Value::integer(0),
+            Value::array(vec![]),
+        ]);
+        let expected = "*3\r\n$6\r\nmaster\r\n:0\r\n*0\r\n";
+        assert_eq!(value.to_bytes(), expected.as_bytes());
     }
 
-    async fn write_null_bulk_string(&mut self) -> io::Result<()> {
-        self.writer.write_all("$-1\r\n".as_bytes()).await
+    #[test]
+    fn raw_bytes_are_verbatim() {
+        let value = Value::raw("+OK\r\n:1\r\n".as_bytes().to_vec());
+        assert_eq!(value.to_bytes(), "+OK\r\n:1\r\n".as_bytes());
     }
 
-    async fn write_null_array(&mut self) -> io::Result<()> {
-        self.writer.write_all("*-1\r\n".as_bytes()).await
+    #[tokio::test]
+    async fn write_flushes_value() {
+        let mut output = Vec::new();
+        let mut writer = ValueWriter::new(&mut output);
+        writer.write(&Value::bulk_string("hi")).await.unwrap();
+
+        assert_eq!(output, "$2\r\nhi\r\n".as_bytes());
     }
 }
@@ -0,0 +1,648 @@
+use crate::{
+    protocol::{
+        Command, CommandReadError, CommandReader, ExpireFlags, SetExpiry, StreamId, Value,
+        ValueReader, XclaimOptions, XreadId,
+    },
+    server::{
+        database::{Data, Database},
+        number,
+    },
+};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{io::BufReader, task::JoinHandle};
+
+/// When data appended to the AOF is flushed to disk.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FsyncPolicy {
+    /// fsync after every write command; slowest, but loses nothing
+    Always,
+
+    /// fsync once per second in the background; loses at most one second of writes
+    EverySec,
+
+    /// never fsync explicitly and let the OS decide when to flush
+    No,
+}
+
+impl FromStr for FsyncPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "always" => Ok(Self::Always),
+            "everysec" => Ok(Self::EverySec),
+            "no" => Ok(Self::No),
+            _ => Err(format!("invalid appendfsync policy: {}", value)),
+        }
+    }
+}
+
+/// An open append-only file that write commands are logged to.
+pub struct Aof {
+    path: PathBuf,
+    file: Arc<File>,
+    policy: FsyncPolicy,
+    fsync_task: Option<JoinHandle<()>>,
+    size: u64,
+    base_size: u64,
+    rewrite_buffer: Option<Vec<u8>>,
+}
+
+impl Aof {
+    pub fn open(path: &Path, policy: FsyncPolicy) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        let file = Arc::new(file);
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            fsync_task: spawn_fsync_task(&file, policy),
+            file,
+            policy,
+            size,
+            base_size: size,
+            rewrite_buffer: None,
+        })
+    }
+
+    /// Appends `command` to the file if it's a write command.
+    ///
+    /// While a rewrite is in progress the command is also buffered, so it can be added to the
+    /// rewritten file once the background rewrite completes.
+    pub fn append(&mut self, command: &Command) -> io::Result<()> {
+        if !command.is_write() {
+            return Ok(());
+        }
+
+        let bytes = command.to_value().to_bytes();
+
+        if let Some(buffer) = &mut self.rewrite_buffer {
+            buffer.extend_from_slice(&bytes);
+        }
+
+        (&*self.file).write_all(&bytes)?;
+        self.size += bytes.len() as u64;
+
+        if self.policy == FsyncPolicy::Always {
+            self.file.sync_data()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn rewrite_in_progress(&self) -> bool {
+        self.rewrite_buffer.is_some()
+    }
+
+    /// Whether the file grew enough since the last rewrite to warrant a new one.
+    ///
+    /// A `percentage` of zero disables automatic rewrites.
+    pub fn should_rewrite(&self, percentage: u64, min_size: u64) -> bool {
+        if percentage == 0 || self.rewrite_in_progress() || self.size < min_size {
+            return false;
+        }
+
+        let base_size = self.base_size.max(1);
+        let growth = (self.size - self.base_size.min(self.size)) * 100 / base_size;
+        growth >= percentage
+    }
+
+    /// Starts buffering write commands until [`finish_rewrite`](Self::finish_rewrite) is called.
+    pub fn start_rewrite(&mut self) {
+        self.rewrite_buffer = Some(Vec::new());
+    }
+
+    /// Discards the buffered commands of a failed rewrite.
+    pub fn abort_rewrite(&mut self) {
+        self.rewrite_buffer = None;
+    }
+
+    /// Appends the commands buffered during the rewrite to `temp_path`, then atomically replaces
+    /// the current file with it.
+    pub fn finish_rewrite(&mut self, temp_path: &Path) -> io::Result<()> {
+        let buffer = self.rewrite_buffer.take().unwrap_or_default();
+
+        let mut file = OpenOptions::new().append(true).open(temp_path)?;
+        file.write_all(&buffer)?;
+        file.sync_all()?;
+        let size = file.metadata()?.len();
+
+        fs::rename(temp_path, &self.path)?;
+
+        if let Some(task) = self.fsync_task.take() {
+            task.abort();
+        }
+
+        self.file = Arc::new(file);
+        self.fsync_task = spawn_fsync_task(&self.file, self.policy);
+        self.size = size;
+        self.base_size = size;
+        Ok(())
+    }
+}
+
+impl Drop for Aof {
+    fn drop(&mut self) {
+        if let Some(task) = self.fsync_task.take() {
+            task.abort();
+        }
+    }
+}
+
+/// Maximum number of elements per command when rewriting aggregates, so no single command in
+/// the rewritten AOF gets too large.
+const REWRITE_ITEMS_PER_COMMAND: usize = 64;
+
+/// Writes the smallest AOF that recreates `database` to `path`: a `SET` per string key, with
+/// its absolute expiry as `PXAT`, batches of `RPUSH`, `SADD`, `ZADD` or `HSET` per list, set,
+/// sorted set or hash, and an `XADD` per stream entry followed by an `XSETID` and the stream's
+/// consumer groups. Other keys that expire get a `PEXPIREAT`.
+pub fn rewrite(database: &Database, path: &Path) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    for (key, data, expires_at) in database.entries() {
+        for command in rewrite_entry(key, data, expires_at) {
+            writer.write_all(&command.to_bytes())?;
+        }
+    }
+
+    writer.into_inner()?.sync_all()
+}
+
+fn rewrite_entry(key: &[u8], data: &Data, expires_at: Option<SystemTime>) -> Vec<Value> {
+    let mut commands = rewrite_data(key, data, expires_at);
+
+    // strings get their expiry from SET, other types need a PEXPIREAT once they exist
+    if let (Some(deadline), false) = (expires_at, matches!(data, Data::String(_))) {
+        let expire = Command::Expire {
+            size: 0,
+            key: key.to_vec(),
+            deadline,
+            flags: ExpireFlags::default(),
+        };
+
+        commands.push(expire.to_value());
+    }
+
+    commands
+}
+
+fn rewrite_data(key: &[u8], data: &Data, expires_at: Option<SystemTime>) -> Vec<Value> {
+    let key = key.to_vec();
+
+    match data {
+        Data::String(value) => {
+            let set = Command::Set {
+                size: 0,
+                key,
+                value: value.clone(),
+                condition: None,
+                expiry: expires_at.map(SetExpiry::At),
+                get: false,
+            };
+
+            vec![set.to_value()]
+        }
+        Data::List(list) => {
+            let elements: Vec<_> = list.iter().collect();
+
+            elements
+                .chunks(REWRITE_ITEMS_PER_COMMAND)
+                .map(|chunk| {
+                    let mut args = vec![&key];
+                    args.extend(chunk);
+                    Value::command("RPUSH", &args)
+                })
+                .collect()
+        }
+        Data::Set(set) => {
+            let members: Vec<_> = set.iter().collect();
+
+            members
+                .chunks(REWRITE_ITEMS_PER_COMMAND)
+                .map(|chunk| {
+                    let mut args = vec![&key];
+                    args.extend(chunk);
+                    Value::command("SADD", &args)
+                })
+                .collect()
+        }
+        Data::SortedSet(set) => {
+            let elements: Vec<_> = set
+                .iter()
+                .map(|(member, score)| (number::format_float(score).into_bytes(), member.to_vec()))
+                .collect();
+
+            elements
+                .chunks(REWRITE_ITEMS_PER_COMMAND)
+                .map(|chunk| {
+                    let mut args = vec![&key];
+                    chunk
+                        .iter()
+                        .for_each(|(score, member)| args.extend([score, member]));
+                    Value::command("ZADD", &args)
+                })
+                .collect()
+        }
+        Data::Hash(hash) => {
+            let pairs: Vec<_> = hash.iter().collect();
+
+            pairs
+                .chunks(REWRITE_ITEMS_PER_COMMAND)
+                .map(|chunk| {
+                    let mut args = vec![&key];
+                    chunk
+                        .iter()
+                        .for_each(|(field, value)| args.extend([*field, *value]));
+                    Value::command("HSET", &args)
+                })
+                .collect()
+        }
+        Data::Stream(stream) => {
+            let mut commands: Vec<_> = stream
+                .iter()
+                .map(|(id, fields)| {
+                    let id = id.to_string().into_bytes();
+                    let mut args = vec![&key, &id];
+                    fields
+                        .iter()
+                        .for_each(|(field, value)| args.extend([field, value]));
+                    Value::command("XADD", &args)
+                })
+                .collect();
+
+            let last_id = stream.last_id().to_string().into_bytes();
+
+            // an empty stream still exists: create it along with a group that's dropped right
+            // away, as an XADD can't add an entry with the ID 0-0 that it may have
+            if stream.len() == 0 {
+                let group = "rewrite".as_bytes().to_vec();
+
+                let create = Command::XgroupCreate {
+                    size: 0,
+                    key: key.clone(),
+                    group: group.clone(),
+                    id: XreadId::After(StreamId::MIN),
+                    mkstream: true,
+                    entries_read: None,
+                };
+
+                let destroy = Command::XgroupDestroy {
+                    size: 0,
+                    key: key.clone(),
+                    group,
+                };
+
+                commands.extend([create.to_value(), destroy.to_value()]);
+            }
+
+            // restore the last ID and counters, which deleted entries may have moved on
+            let entries_added = "ENTRIESADDED".as_bytes().to_vec();
+            let entries_added_value = stream.entries_added().to_string().into_bytes();
+            let max_deleted_id = "MAXDELETEDID".as_bytes().to_vec();
+            let max_deleted_id_value = stream.max_deleted_id().to_string().into_bytes();
+            commands.push(Value::command(
+                "XSETID",
+                &[
+                    &key,
+                    &last_id,
+                    &entries_added,
+                    &entries_added_value,
+                    &max_deleted_id,
+                    &max_deleted_id_value,
+                ],
+            ));
+
+            for (group_name, group) in stream.groups() {
+                let group_name = group_name.clone();
+
+                let mut group_commands = vec![Command::XgroupCreate {
+                    size: 0,
+                    key: key.clone(),
+                    group: group_name.clone(),
+                    id: XreadId::After(group.last_id),
+                    mkstream: false,
+                    entries_read: group.entries_read,
+                }];
+
+                group_commands.extend(group.consumers().map(|(consumer, _)| {
+                    Command::XgroupCreateconsumer {
+                        size: 0,
+                        key: key.clone(),
+                        group: group_name.clone(),
+                        consumer: consumer.clone(),
+                    }
+                }));
+
+                // a forced claim with the delivery time and count recreates each pending entry
+                group_commands.extend(group.pending_from(StreamId::MIN).map(|(id, pending)| {
+                    Command::Xclaim {
+                        size: 0,
+                        key: key.clone(),
+                        group: group_name.clone(),
+                        consumer: pending.consumer.clone(),
+                        min_idle_time: 0,
+                        ids: vec![*id],
+                        options: XclaimOptions {
+                            time: Some(pending.delivery_time),
+                            retry_count: Some(pending.delivery_count),
+                            force: true,
+                            justid: true,
+                            ..XclaimOptions::default()
+                        },
+                    }
+                }));
+
+                commands.extend(group_commands.iter().map(Command::to_value));
+            }
+
+            commands
+        }
+    }
+}
+
+fn spawn_fsync_task(file: &Arc<File>, policy: FsyncPolicy) -> Option<JoinHandle<()>> {
+    match policy {
+        FsyncPolicy::EverySec => Some(tokio::spawn(fsync_every_second(file.clone()))),
+        _ => None,
+    }
+}
+
+async fn fsync_every_second(file: Arc<File>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+    loop {
+        interval.tick().await;
+        let file = file.clone();
+
+        match tokio::task::spawn_blocking(move || file.sync_data()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => println!("failed to fsync AOF: {}", err),
+            Err(err) => println!("AOF fsync task failed: {}", err),
+        }
+    }
+}
+
+/// Reads every command in the AOF at `path` and hands it to `apply`.
+///
+/// A missing file is treated as empty. An incomplete command at the end of the file, usually
+/// left behind by a crash mid-write, is logged and truncated away so later appends start on a
+/// command boundary. Returns the number of commands read.
+pub async fn replay<F>(path: &Path, mut apply: F) -> io::Result<usize>
+where
+    F: FnMut(Command),
+{
+    let file = match tokio::fs::File::open(path).await {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+
+    let mut reader = CommandReader::new(ValueReader::new(BufReader::new(file)));
+    let mut count = 0;
+
+    loop {
+        match reader.read().await {
+            Ok(command) => {
+                apply(command);
+                count += 1;
+            }
+            Err(CommandReadError::Stop(None)) => return Ok(count),
+            Err(CommandReadError::Stop(Some(cause))) => {
+                println!(
+                    "AOF ends with an incomplete command; truncating it: {}",
+                    cause
+                );
+                let file = OpenOptions::new().write(true).open(path)?;
+                file.set_len(reader.bytes_read() as u64)?;
+                return Ok(count);
+            }
+            Err(CommandReadError::Invalid(values)) => {
+                let message = format!("invalid command in AOF: {:?}", values);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn parse_fsync_policy() {
+        assert_eq!("always".parse(), Ok(FsyncPolicy::Always));
+        assert_eq!("EVERYSEC".parse(), Ok(FsyncPolicy::EverySec));
+        assert_eq!("no".parse(), Ok(FsyncPolicy::No));
+        assert!("sometimes".parse::<FsyncPolicy>().is_err());
+    }
+
+    #[tokio::test]
+    async fn append_and_replay() {
+        let path = std::env::temp_dir().join(format!("aof-test-{}.aof", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut aof = Aof::open(&path, FsyncPolicy::Always).unwrap();
+        aof.append(&set("a", "1")).unwrap();
+        aof.append(&Command::Get {
+            size: 0,
+            key: "a".as_bytes().to_vec(),
+        })
+        .unwrap();
+        aof.append(&set("b", "2")).unwrap();
+        drop(aof);
+
+        let mut commands = Vec::new();
+        let count = replay(&path, |command| commands.push(command))
+            .await
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(count, 2);
+        assert!(matches!(&commands[0], Command::Set { key, .. } if key == "a".as_bytes()));
+        assert!(matches!(&commands[1], Command::Set { key, .. } if key == "b".as_bytes()));
+    }
+
+    #[tokio::test]
+    async fn replay_truncated_file() {
+        let path = std::env::temp_dir().join(format!("aof-trunc-{}.aof", std::process::id()));
+        let mut bytes = set("a", "1").to_value().to_bytes();
+        bytes.extend("*3\r\n$3\r\nSET\r\n$1\r\nb".as_bytes());
+        std::fs::write(&path, bytes).unwrap();
+
+        let count = replay(&path, |_| {}).await.unwrap();
+        let length = std::fs::metadata(&path).unwrap().len();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(length, set("a", "1").to_value().to_bytes().len() as u64);
+    }
+
+    #[tokio::test]
+    async fn rewrite_while_appending() {
+        let path = std::env::temp_dir().join(format!("aof-rewrite-{}.aof", std::process::id()));
+        let temp_path = path.with_extension("tmp");
+        let _ = std::fs::remove_file(&path);
+
+        let mut aof = Aof::open(&path, FsyncPolicy::No).unwrap();
+        aof.append(&set("a", "1")).unwrap();
+        aof.append(&set("a", "2")).unwrap();
+
+        let mut database = Database::new();
+        database.set("a".as_bytes().to_vec(), "2".as_bytes().to_vec(), None);
+        database.set(
+            "b".as_bytes().to_vec(),
+            "3".as_bytes().to_vec(),
+            Some(SystemTime::now() + Duration::from_secs(60)),
+        );
+
+        aof.start_rewrite();
+        rewrite(&database, &temp_path).unwrap();
+        aof.append(&set("c", "4")).unwrap();
+        aof.finish_rewrite(&temp_path).unwrap();
+        aof.append(&set("d", "5")).unwrap();
+        drop(aof);
+
+        let mut commands = Vec::new();
+        replay(&path, |command| commands.push(command))
+            .await
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut keys: Vec<_> = commands
+            .iter()
+            .map(|command| match command {
+                Command::Set { key, expiry, .. } => (key.clone(), expiry.is_some()),
+                command => panic!("expected SET, got {:?}", command),
+            })
+            .collect();
+        keys[..2].sort();
+
+        assert_eq!(
+            keys,
+            vec![
+                ("a".as_bytes().to_vec(), false),
+                ("b".as_bytes().to_vec(), true),
+                ("c".as_bytes().to_vec(), false),
+                ("d".as_bytes().to_vec(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn rewrite_list_in_batches() {
+        let elements = (0..100).map(|i| i.to_string().into_bytes()).collect();
+        let commands = rewrite_entry("list".as_bytes(), &Data::List(elements), None);
+
+        let lengths: Vec<_> = commands
+            .into_iter()
+            .map(|value| match Command::try_from(value) {
+                Ok(Command::Rpush { elements, .. }) => elements.len(),
+                command => panic!("expected RPUSH, got {:?}", command),
+            })
+            .collect();
+
+        assert_eq!(lengths, vec![64, 36]);
+    }
+
+    #[test]
+    fn rewrite_expiry_of_other_types() {
+        let deadline = SystemTime::UNIX_EPOCH + Duration::from_secs(4_000_000_000);
+        let set = Data::Set(["a".as_bytes().to_vec()].into_iter().collect());
+        let commands = rewrite_entry("set".as_bytes(), &set, Some(deadline));
+        assert_eq!(commands.len(), 2);
+
+        match commands.into_iter().last().map(Command::try_from) {
+            Some(Ok(Command::Expire {
+                key, deadline: at, ..
+            })) => {
+                assert_eq!(key, "set".as_bytes());
+                assert_eq!(at, deadline);
+            }
+            command => panic!("expected PEXPIREAT, got {:?}", command),
+        }
+
+        let string = Data::String("value".as_bytes().to_vec());
+        assert_eq!(
+            rewrite_entry("string".as_bytes(), &string, Some(deadline)).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn should_rewrite_after_growth() {
+        let path = std::env::temp_dir().join(format!("aof-growth-{}.aof", std::process::id()));
+        std::fs::write(&path, vec![b'x'; 100]).unwrap();
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let _guard = rt.enter();
+        let mut aof = Aof::open(&path, FsyncPolicy::No).unwrap();
+
+        assert!(!aof.should_rewrite(100, 0));
+        aof.size = 200;
+        assert!(aof.should_rewrite(100, 0));
+        assert!(!aof.should_rewrite(100, 1000));
+        assert!(!aof.should_rewrite(0, 0));
+
+        aof.start_rewrite();
+        assert!(!aof.should_rewrite(100, 0));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn replay_missing_file() {
+        let path = std::env::temp_dir().join("aof-test-does-not-exist.aof");
+        assert_eq!(replay(&path, |_| {}).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn append_set_with_absolute_deadline() {
+        let path = std::env::temp_dir().join(format!("aof-pxat-{}.aof", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let deadline = SystemTime::now() + Duration::from_secs(60);
+        let mut aof = Aof::open(&path, FsyncPolicy::Always).unwrap();
+        aof.append(&Command::Set {
+            size: 0,
+            key: "a".as_bytes().to_vec(),
+            value: "1".as_bytes().to_vec(),
+            condition: None,
+            expiry: Some(SetExpiry::At(deadline)),
+            get: false,
+        })
+        .unwrap();
+        drop(aof);
+
+        let logged = String::from_utf8(std::fs::read(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // replaying the deadline later doesn't restart the key's time to live
+        let parts: Vec<&str> = logged.split("\r\n").collect();
+        let pxat = parts.iter().position(|part| *part == "PXAT").unwrap();
+        let logged: u128 = parts[pxat + 2].parse().unwrap();
+        let deadline = deadline.duration_since(UNIX_EPOCH).unwrap().as_millis();
+        assert!(logged.abs_diff(deadline) < 1000);
+    }
+
+    fn set(key: &str, value: &str) -> Command {
+        Command::Set {
+            size: 0,
+            key: key.as_bytes().to_vec(),
+            value: value.as_bytes().to_vec(),
+            condition: None,
+            expiry: None,
+            get: false,
+        }
+    }
+}
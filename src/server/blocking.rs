@@ -0,0 +1,348 @@
+use super::{database::WrongType, lists::pop_end, CommandResult, Server};
+use crate::protocol::{Command, ListEnd, StreamId, Value, XreadId, XreadgroupId};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// A client parked by BLPOP, BRPOP or BLMOVE until one of its keys holds a non-empty list, or
+/// by XREAD or XREADGROUP until one of its streams gets a new entry.
+pub(super) struct BlockedClient {
+    keys: Vec<Vec<u8>>,
+    operation: BlockedOperation,
+    reply_to: mpsc::UnboundedSender<Vec<Value>>,
+    deadline: Option<Instant>,
+}
+
+enum BlockedOperation {
+    Pop(ListEnd),
+    Move {
+        destination: Vec<u8>,
+        from: ListEnd,
+        to: ListEnd,
+    },
+    /// Reads the entries after `ids`, one for each key.
+    Read {
+        ids: Vec<StreamId>,
+        count: Option<usize>,
+    },
+    /// Reads the entries not delivered to `group` yet.
+    ReadGroup {
+        group: Vec<u8>,
+        consumer: Vec<u8>,
+        count: Option<usize>,
+        noack: bool,
+    },
+}
+
+impl BlockedClient {
+    /// The reply when the client times out.
+    fn timeout_reply(&self) -> Value {
+        match self.operation {
+            BlockedOperation::Pop(_)
+            | BlockedOperation::Read { .. }
+            | BlockedOperation::ReadGroup { .. } => Value::NullArray,
+            BlockedOperation::Move { .. } => Value::NullBulkString,
+        }
+    }
+}
+
+impl Server {
+    pub(super) fn blocking_pop(
+        &mut self,
+        keys: Vec<Vec<u8>>,
+        end: ListEnd,
+        timeout: Duration,
+        reply_to: &mpsc::UnboundedSender<Vec<Value>>,
+    ) -> CommandResult {
+        for key in &keys {
+            if let Some(response) = self.pop_for_client(key, end)? {
+                return Ok(vec![response]);
+            }
+        }
+
+        self.block(keys, BlockedOperation::Pop(end), timeout, reply_to);
+        Ok(vec![])
+    }
+
+    pub(super) fn blmove(
+        &mut self,
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        from: ListEnd,
+        to: ListEnd,
+        timeout: Duration,
+        reply_to: &mpsc::UnboundedSender<Vec<Value>>,
+    ) -> CommandResult {
+        if self.database.list(&source)?.is_some() {
+            return self.move_for_client(source, destination, from, to);
+        }
+
+        let operation = BlockedOperation::Move {
+            destination,
+            from,
+            to,
+        };
+
+        self.block(vec![source], operation, timeout, reply_to);
+        Ok(vec![])
+    }
+
+    /// Reads from streams, blocking if there's nothing to read yet and the client asked to.
+    /// `$` is resolved now, so a blocked client only gets entries added after it blocked.
+    pub(super) fn xread(
+        &mut self,
+        count: Option<usize>,
+        block: Option<Duration>,
+        streams: Vec<(Vec<u8>, XreadId)>,
+        reply_to: &mpsc::UnboundedSender<Vec<Value>>,
+    ) -> CommandResult {
+        let mut keys = Vec::with_capacity(streams.len());
+        let mut ids = Vec::with_capacity(streams.len());
+
+        for (key, id) in streams {
+            let id = match id {
+                XreadId::After(id) => id,
+                XreadId::Last => self
+                    .database
+                    .stream(&key)?
+                    .map_or(StreamId::MIN, |stream| stream.last_id()),
+            };
+
+            keys.push(key);
+            ids.push(id);
+        }
+
+        if let Some(response) = self.read_streams(&keys, &ids, count)? {
+            return Ok(vec![response]);
+        }
+
+        match block {
+            Some(timeout) => {
+                self.block(
+                    keys,
+                    BlockedOperation::Read { ids, count },
+                    timeout,
+                    reply_to,
+                );
+                Ok(vec![])
+            }
+            None => Ok(vec![Value::NullArray]),
+        }
+    }
+
+    /// Reads from streams for a consumer of a group, blocking if the client asked to and only
+    /// wants entries never delivered to the group, none of which are there yet.
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn xreadgroup(
+        &mut self,
+        group: Vec<u8>,
+        consumer: Vec<u8>,
+        count: Option<usize>,
+        block: Option<Duration>,
+        noack: bool,
+        streams: Vec<(Vec<u8>, XreadgroupId)>,
+        reply_to: &mpsc::UnboundedSender<Vec<Value>>,
+    ) -> CommandResult {
+        let (keys, ids): (Vec<_>, Vec<_>) = streams.into_iter().unzip();
+
+        if let Some(response) =
+            self.read_group_streams(&keys, &ids, &group, &consumer, count, noack)?
+        {
+            return Ok(vec![response]);
+        }
+
+        match block {
+            Some(timeout) => {
+                // creating the consumer is all that happened, so keep propagating that
+                let created = self.propagate_as.take();
+
+                let operation = BlockedOperation::ReadGroup {
+                    group,
+                    consumer,
+                    count,
+                    noack,
+                };
+
+                self.block(keys, operation, timeout, reply_to);
+                self.propagate_as = created;
+                Ok(vec![])
+            }
+            None => Ok(vec![Value::NullArray]),
+        }
+    }
+
+    fn block(
+        &mut self,
+        keys: Vec<Vec<u8>>,
+        operation: BlockedOperation,
+        timeout: Duration,
+        reply_to: &mpsc::UnboundedSender<Vec<Value>>,
+    ) {
+        // nothing happened yet; whatever pops later is propagated on its own
+        self.propagate_as = Some(vec![]);
+
+        self.blocked.push(BlockedClient {
+            keys,
+            operation,
+            reply_to: reply_to.clone(),
+            deadline: (!timeout.is_zero()).then(|| Instant::now() + timeout),
+        });
+    }
+
+    /// Pops an element from `key` as a `[key, element]` reply, propagated as a plain LPOP or
+    /// RPOP. Returns `None` if there's no list at `key`.
+    fn pop_for_client(&mut self, key: &[u8], end: ListEnd) -> Result<Option<Value>, WrongType> {
+        let Some(element) = self.database.list(key)?.and_then(|list| pop_end(list, end)) else {
+            return Ok(None);
+        };
+
+        self.database.remove_if_empty(key);
+
+        let key = key.to_vec();
+        let pop = match end {
+            ListEnd::Left => Command::Lpop {
+                size: 0,
+                key: key.clone(),
+                count: None,
+            },
+            ListEnd::Right => Command::Rpop {
+                size: 0,
+                key: key.clone(),
+                count: None,
+            },
+        };
+        self.propagate_as.get_or_insert_with(Vec::new).push(pop);
+
+        let key = Value::bulk_string_from_bytes(key);
+        let element = Value::bulk_string_from_bytes(element);
+        Ok(Some(Value::array(vec![key, element])))
+    }
+
+    /// Runs an LMOVE on behalf of BLMOVE, propagated as such.
+    fn move_for_client(
+        &mut self,
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        from: ListEnd,
+        to: ListEnd,
+    ) -> CommandResult {
+        let lmove = Command::Lmove {
+            size: 0,
+            source: source.clone(),
+            destination: destination.clone(),
+            from,
+            to,
+        };
+
+        let response = self.lmove(source, destination, from, to)?;
+        self.propagate_as.get_or_insert_with(Vec::new).push(lmove);
+        Ok(response)
+    }
+
+    /// Hands elements to blocked clients whose keys got a list, in the order they blocked, and
+    /// propagates the pops they made.
+    pub(super) fn serve_blocked_clients(&mut self) {
+        self.blocked.retain(|client| !client.reply_to.is_closed());
+
+        // a BLMOVE may push to a key another client is waiting on, so go again until no one
+        // else can be served
+        let mut served = true;
+
+        while served {
+            served = false;
+            let mut index = 0;
+
+            while index < self.blocked.len() {
+                self.propagate_as = None;
+
+                match self.serve_blocked_client(index) {
+                    Some(response) => {
+                        let client = self.blocked.remove(index);
+                        let _ = client.reply_to.send(vec![response]);
+
+                        for command in self.propagate_as.take().unwrap_or_default() {
+                            self.propagate(&command);
+                        }
+
+                        served = true;
+                    }
+                    None => index += 1,
+                }
+            }
+        }
+    }
+
+    fn serve_blocked_client(&mut self, index: usize) -> Option<Value> {
+        let client = &self.blocked[index];
+        let keys = client.keys.clone();
+
+        match &client.operation {
+            BlockedOperation::Pop(end) => {
+                let end = *end;
+
+                // keys holding something other than a list are skipped, like Redis does
+                keys.iter()
+                    .find_map(|key| self.pop_for_client(key, end).ok().flatten())
+            }
+            BlockedOperation::Move {
+                destination,
+                from,
+                to,
+            } => {
+                let (destination, from, to) = (destination.clone(), *from, *to);
+                let source = keys.into_iter().next()?;
+
+                if !matches!(self.database.list(&source), Ok(Some(_))) {
+                    return None;
+                }
+
+                Some(match self.move_for_client(source, destination, from, to) {
+                    Ok(mut response) => response.remove(0),
+                    Err(err) => Value::simple_error(&err.to_string()),
+                })
+            }
+            BlockedOperation::Read { ids, count } => {
+                let (ids, count) = (ids.clone(), *count);
+
+                match self.read_streams(&keys, &ids, count) {
+                    Ok(response) => response,
+                    Err(err) => Some(Value::simple_error(&err.to_string())),
+                }
+            }
+            BlockedOperation::ReadGroup {
+                group,
+                consumer,
+                count,
+                noack,
+            } => {
+                let (group, consumer, count, noack) =
+                    (group.clone(), consumer.clone(), *count, *noack);
+                let ids = vec![XreadgroupId::Undelivered; keys.len()];
+
+                // a destroyed group or stream gets the client an error instead of an answer
+                match self.read_group_streams(&keys, &ids, &group, &consumer, count, noack) {
+                    Ok(response) => response,
+                    Err(err) => Some(Value::simple_error(&err.to_string())),
+                }
+            }
+        }
+    }
+
+    /// Replies to blocked clients whose timeout passed.
+    pub(super) fn unblock_timed_out_clients(&mut self, now: Instant) {
+        self.blocked.retain(|client| {
+            if client.deadline.is_some_and(|deadline| deadline <= now) {
+                let _ = client.reply_to.send(vec![client.timeout_reply()]);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    pub(super) fn next_unblock_deadline(&self) -> Option<Instant> {
+        self.blocked
+            .iter()
+            .filter_map(|client| client.deadline)
+            .min()
+    }
+}
@@ -0,0 +1,206 @@
+use crate::protocol::StreamId;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A consumer group of a stream: how far it has read, and the entries delivered to its
+/// consumers that they haven't acknowledged yet, the pending entries list (PEL).
+///
+/// Times are in milliseconds since the Unix epoch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConsumerGroup {
+    pub last_id: StreamId,
+    /// How many entries the group has read, when it's known. Used to report the group's lag.
+    pub entries_read: Option<u64>,
+    pending: BTreeMap<StreamId, PendingEntry>,
+    consumers: BTreeMap<Vec<u8>, Consumer>,
+}
+
+/// An entry delivered to a consumer and not acknowledged yet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingEntry {
+    pub consumer: Vec<u8>,
+    pub delivery_time: u64,
+    pub delivery_count: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Consumer {
+    /// The last time the consumer tried to read or claim entries.
+    pub seen_time: u64,
+    /// The last time the consumer actually got entries, if it ever did.
+    pub active_time: Option<u64>,
+    pending: BTreeSet<StreamId>,
+}
+
+impl ConsumerGroup {
+    pub fn new(last_id: StreamId, entries_read: Option<u64>) -> Self {
+        Self {
+            last_id,
+            entries_read,
+            pending: BTreeMap::new(),
+            consumers: BTreeMap::new(),
+        }
+    }
+
+    pub fn consumer_mut(&mut self, name: &[u8]) -> Option<&mut Consumer> {
+        self.consumers.get_mut(name)
+    }
+
+    /// Every consumer, ordered by name.
+    pub fn consumers(&self) -> impl Iterator<Item = (&Vec<u8>, &Consumer)> {
+        self.consumers.iter()
+    }
+
+    /// Adds a consumer unless it's already there. Returns whether it was added.
+    pub fn create_consumer(&mut self, name: &[u8], now: u64) -> bool {
+        if self.consumers.contains_key(name) {
+            return false;
+        }
+
+        let consumer = Consumer {
+            seen_time: now,
+            active_time: None,
+            pending: BTreeSet::new(),
+        };
+
+        self.consumers.insert(name.to_vec(), consumer);
+        true
+    }
+
+    /// Removes a consumer along with its pending entries. Returns how many entries it had
+    /// pending, or `None` if there was no such consumer.
+    pub fn remove_consumer(&mut self, name: &[u8]) -> Option<usize> {
+        let consumer = self.consumers.remove(name)?;
+
+        for id in &consumer.pending {
+            self.pending.remove(id);
+        }
+
+        Some(consumer.pending.len())
+    }
+
+    /// Records that a consumer was seen, and active if it got entries. Creates the consumer if
+    /// needed.
+    pub fn touch(&mut self, name: &[u8], now: u64, active: bool) {
+        self.create_consumer(name, now);
+
+        let consumer = self.consumers.get_mut(name).unwrap();
+        consumer.seen_time = now;
+
+        if active {
+            consumer.active_time = Some(now);
+        }
+    }
+
+    pub fn pending_entry(&self, id: StreamId) -> Option<&PendingEntry> {
+        self.pending.get(&id)
+    }
+
+    /// The pending entries from `start` on, in ID order.
+    pub fn pending_from(
+        &self,
+        start: StreamId,
+    ) -> impl DoubleEndedIterator<Item = (&StreamId, &PendingEntry)> {
+        self.pending.range(start..)
+    }
+
+    /// The pending entries of one consumer from `start` on, in ID order.
+    pub fn consumer_pending_from<'a>(
+        &'a self,
+        name: &[u8],
+        start: StreamId,
+    ) -> impl Iterator<Item = (StreamId, &'a PendingEntry)> + 'a {
+        self.consumers
+            .get(name)
+            .into_iter()
+            .flat_map(move |consumer| consumer.pending.range(start..))
+            .map(|id| (*id, &self.pending[id]))
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Makes `entry` the pending entry for `id`, taking it from whichever consumer had it.
+    pub fn set_pending(&mut self, id: StreamId, entry: PendingEntry) {
+        self.remove_pending(id);
+        self.create_consumer(&entry.consumer, entry.delivery_time);
+
+        let consumer = self.consumers.get_mut(&entry.consumer).unwrap();
+        consumer.pending.insert(id);
+        self.pending.insert(id, entry);
+    }
+
+    /// Removes a pending entry, as XACK does. Returns whether it was there.
+    pub fn remove_pending(&mut self, id: StreamId) -> bool {
+        let Some(entry) = self.pending.remove(&id) else {
+            return false;
+        };
+
+        if let Some(consumer) = self.consumers.get_mut(&entry.consumer) {
+            consumer.pending.remove(&id);
+        }
+
+        true
+    }
+}
+
+impl Consumer {
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// The IDs of the consumer's pending entries, in order.
+    pub fn pending(&self) -> impl Iterator<Item = StreamId> + '_ {
+        self.pending.iter().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_entries_follow_their_consumer() {
+        let mut group = ConsumerGroup::new(StreamId::MIN, Some(0));
+
+        group.set_pending(id(1), pending("alice", 1));
+        group.set_pending(id(2), pending("alice", 1));
+        group.set_pending(id(3), pending("bob", 1));
+        assert_eq!(pending_counts(&group), [2, 1]);
+
+        // claiming moves the entry to the new consumer
+        group.set_pending(id(2), pending("bob", 2));
+        assert_eq!(pending_counts(&group), [1, 2]);
+
+        let bob: Vec<_> = group
+            .consumer_pending_from(b"bob", StreamId::MIN)
+            .map(|(id, entry)| (id.ms, entry.delivery_count))
+            .collect();
+        assert_eq!(bob, [(2, 2), (3, 1)]);
+
+        assert!(group.remove_pending(id(3)));
+        assert!(!group.remove_pending(id(3)));
+        assert_eq!(group.remove_consumer(b"bob"), Some(1));
+        assert_eq!(group.remove_consumer(b"bob"), None);
+        assert_eq!(group.pending_len(), 1);
+    }
+
+    fn pending_counts(group: &ConsumerGroup) -> Vec<usize> {
+        group
+            .consumers()
+            .map(|(_, consumer)| consumer.pending_len())
+            .collect()
+    }
+
+    fn pending(consumer: &str, delivery_count: u64) -> PendingEntry {
+        PendingEntry {
+            consumer: consumer.as_bytes().to_vec(),
+            delivery_time: 0,
+            delivery_count,
+        }
+    }
+
+    fn id(ms: u64) -> StreamId {
+        StreamId { ms, seq: 0 }
+    }
+}
@@ -0,0 +1,787 @@
+use super::{
+    consumer_group::{ConsumerGroup, PendingEntry},
+    database::Database,
+    stream::{Fields, Stream},
+    streams::{entries_value, entry_value, now_millis},
+    CommandError, CommandResult, Server,
+};
+use crate::protocol::{
+    Command, StreamId, Value, XclaimOptions, XpendingRange, XreadId, XreadgroupId,
+};
+
+impl Server {
+    /// Creates a consumer group, propagated with `$` resolved to the stream's last ID.
+    pub(super) fn xgroup_create(
+        &mut self,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        id: XreadId,
+        mkstream: bool,
+        entries_read: Option<u64>,
+    ) -> CommandResult {
+        if self.database.stream(&key)?.is_none() && !mkstream {
+            return Err(no_key_for_xgroup());
+        }
+
+        let stream = self.database.stream_or_insert(&key)?;
+        let last_id = resolve_id(stream, id);
+
+        if !stream.create_group(group.clone(), ConsumerGroup::new(last_id, entries_read)) {
+            return Err(CommandError::Other(
+                "BUSYGROUP Consumer Group name already exists".to_string(),
+            ));
+        }
+
+        self.propagate_as = Some(vec![Command::XgroupCreate {
+            size: 0,
+            key,
+            group,
+            id: XreadId::After(last_id),
+            mkstream,
+            entries_read,
+        }]);
+
+        Ok(vec![Value::ok()])
+    }
+
+    pub(super) fn xgroup_setid(
+        &mut self,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        id: XreadId,
+        entries_read: Option<u64>,
+    ) -> CommandResult {
+        let stream = existing_stream(&mut self.database, &key)?;
+        let last_id = resolve_id(stream, id);
+
+        let consumer_group = stream
+            .group_mut(&group)
+            .ok_or_else(|| no_group_for_key(&key, &group))?;
+
+        consumer_group.last_id = last_id;
+        consumer_group.entries_read = entries_read;
+
+        self.propagate_as = Some(vec![Command::XgroupSetid {
+            size: 0,
+            key,
+            group,
+            id: XreadId::After(last_id),
+            entries_read,
+        }]);
+
+        Ok(vec![Value::ok()])
+    }
+
+    pub(super) fn xgroup_destroy(&mut self, key: Vec<u8>, group: Vec<u8>) -> CommandResult {
+        let destroyed = existing_stream(&mut self.database, &key)?.remove_group(&group);
+        Ok(vec![Value::integer(destroyed as i64)])
+    }
+
+    pub(super) fn xgroup_createconsumer(
+        &mut self,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        consumer: Vec<u8>,
+    ) -> CommandResult {
+        let created = existing_stream(&mut self.database, &key)?
+            .group_mut(&group)
+            .ok_or_else(|| no_group_for_key(&key, &group))?
+            .create_consumer(&consumer, now_millis());
+
+        Ok(vec![Value::integer(created as i64)])
+    }
+
+    /// Removes a consumer and its pending entries, replying with how many it had.
+    pub(super) fn xgroup_delconsumer(
+        &mut self,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        consumer: Vec<u8>,
+    ) -> CommandResult {
+        let pending = existing_stream(&mut self.database, &key)?
+            .group_mut(&group)
+            .ok_or_else(|| no_group_for_key(&key, &group))?
+            .remove_consumer(&consumer)
+            .unwrap_or(0);
+
+        Ok(vec![Value::integer(pending as i64)])
+    }
+
+    /// Reads entries for a consumer. New entries are added to the group's pending entries,
+    /// while history reads deliver the consumer's pending entries again. Either way, what
+    /// changed is propagated as XCLAIMs and an XGROUP SETID, so replicas get the same delivery
+    /// times and counts.
+    pub(super) fn read_group_streams(
+        &mut self,
+        keys: &[Vec<u8>],
+        ids: &[XreadgroupId],
+        group: &[u8],
+        consumer: &[u8],
+        count: Option<usize>,
+        noack: bool,
+    ) -> Result<Option<Value>, CommandError> {
+        self.propagate_as.get_or_insert_with(Vec::new);
+
+        for key in keys {
+            group_stream(&mut self.database, key, group)?;
+        }
+
+        let now = now_millis();
+        let mut streams = vec![];
+
+        for (key, id) in keys.iter().zip(ids) {
+            let stream = group_stream(&mut self.database, key, group)?;
+            let mut propagate = vec![];
+
+            if stream
+                .group_mut(group)
+                .unwrap()
+                .create_consumer(consumer, now)
+            {
+                propagate.push(Command::XgroupCreateconsumer {
+                    size: 0,
+                    key: key.clone(),
+                    group: group.to_vec(),
+                    consumer: consumer.to_vec(),
+                });
+            }
+
+            let (entries, delivered) = match id {
+                XreadgroupId::Undelivered => {
+                    let entries = read_undelivered(stream, group, consumer, count, noack, now);
+
+                    for (id, _) in entries.iter().filter(|_| !noack) {
+                        let pending = stream.group(group).unwrap().pending_entry(*id).unwrap();
+                        propagate.push(xclaim_command(key, group, *id, pending));
+                    }
+
+                    if !entries.is_empty() {
+                        let consumer_group = stream.group(group).unwrap();
+
+                        propagate.push(Command::XgroupSetid {
+                            size: 0,
+                            key: key.clone(),
+                            group: group.to_vec(),
+                            id: XreadId::After(consumer_group.last_id),
+                            entries_read: consumer_group.entries_read,
+                        });
+                    }
+
+                    let delivered = !entries.is_empty();
+                    (delivered.then(|| entries_value(entries)), delivered)
+                }
+                XreadgroupId::Pending(start) => {
+                    let entries = read_pending(stream, group, consumer, *start, count, now);
+
+                    for (id, fields) in &entries {
+                        if fields.is_some() {
+                            let pending = stream.group(group).unwrap().pending_entry(*id).unwrap();
+                            propagate.push(xclaim_command(key, group, *id, pending));
+                        }
+                    }
+
+                    let delivered = !entries.is_empty();
+                    let entries = entries
+                        .into_iter()
+                        .map(|(id, fields)| entry_value(id, fields))
+                        .collect();
+
+                    // history reads always reply for every stream
+                    (Some(Value::array(entries)), delivered)
+                }
+            };
+
+            stream
+                .group_mut(group)
+                .unwrap()
+                .touch(consumer, now, delivered);
+
+            if let Some(entries) = entries {
+                let key = Value::bulk_string_from_bytes(key.clone());
+                streams.push(Value::array(vec![key, entries]));
+            }
+
+            self.propagate_as.as_mut().unwrap().extend(propagate);
+        }
+
+        Ok((!streams.is_empty()).then(|| Value::array(streams)))
+    }
+
+    pub(super) fn xack(
+        &mut self,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        ids: Vec<StreamId>,
+    ) -> CommandResult {
+        let acknowledged = match self
+            .database
+            .stream(&key)?
+            .and_then(|stream| stream.group_mut(&group))
+        {
+            Some(group) => ids.iter().filter(|id| group.remove_pending(**id)).count(),
+            None => 0,
+        };
+
+        Ok(vec![Value::integer(acknowledged as i64)])
+    }
+
+    /// Sums up a group's pending entries, or lists them when given a range.
+    pub(super) fn xpending(
+        &mut self,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        range: Option<XpendingRange>,
+    ) -> CommandResult {
+        let consumer_group = group_stream(&mut self.database, &key, &group)?
+            .group(&group)
+            .unwrap();
+
+        let Some(range) = range else {
+            return Ok(vec![pending_summary(consumer_group)]);
+        };
+
+        let now = now_millis();
+        let entries: Box<dyn Iterator<Item = (StreamId, &PendingEntry)>> = match &range.consumer {
+            Some(consumer) => Box::new(consumer_group.consumer_pending_from(consumer, range.start)),
+            None => Box::new(
+                consumer_group
+                    .pending_from(range.start)
+                    .map(|(id, entry)| (*id, entry)),
+            ),
+        };
+
+        let entries = entries
+            .take_while(|(id, _)| *id <= range.end)
+            .filter(|(_, entry)| {
+                range
+                    .min_idle_time
+                    .map_or(true, |min_idle_time| idle(entry, now) >= min_idle_time)
+            })
+            .take(range.count)
+            .map(|(id, entry)| {
+                Value::array(vec![
+                    Value::bulk_string(&id.to_string()),
+                    Value::bulk_string_from_bytes(entry.consumer.clone()),
+                    Value::integer(idle(entry, now) as i64),
+                    Value::integer(entry.delivery_count as i64),
+                ])
+            })
+            .collect();
+
+        Ok(vec![Value::array(entries)])
+    }
+
+    /// Hands pending entries idle for long enough to `consumer`. Each claim is propagated as
+    /// an XCLAIM with the resulting delivery time and count, and pending entries whose stream
+    /// entry is gone are dropped and propagated as an XACK.
+    pub(super) fn xclaim(
+        &mut self,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        consumer: Vec<u8>,
+        min_idle_time: u64,
+        ids: Vec<StreamId>,
+        options: XclaimOptions,
+    ) -> CommandResult {
+        let stream = group_stream(&mut self.database, &key, &group)?;
+        let now = now_millis();
+        let forced_time = match (options.time, options.idle) {
+            (Some(time), _) => Some(time),
+            (None, Some(idle)) => Some(now.saturating_sub(idle)),
+            (None, None) => None,
+        };
+
+        let mut claims = Claims::new(&key, &group, &consumer, now);
+        claims.create_consumer(stream);
+
+        if let Some(last_id) = options.last_id {
+            let consumer_group = stream.group_mut(&group).unwrap();
+
+            if last_id > consumer_group.last_id {
+                consumer_group.last_id = last_id;
+                claims.propagate.push(Command::XgroupSetid {
+                    size: 0,
+                    key: key.clone(),
+                    group: group.clone(),
+                    id: XreadId::After(last_id),
+                    entries_read: consumer_group.entries_read,
+                });
+            }
+        }
+
+        for id in ids {
+            let pending = stream.group(&group).unwrap().pending_entry(id).cloned();
+
+            let (delivery_count, previous_time) = match pending {
+                Some(pending) => {
+                    if idle(&pending, now) < min_idle_time {
+                        continue;
+                    }
+
+                    (pending.delivery_count, pending.delivery_time)
+                }
+                // only entries still in the stream can be forced into the PEL
+                None if options.force && stream.entry(id).is_some() => (0, now),
+                None => continue,
+            };
+
+            let delivery_count = match options.retry_count {
+                Some(retry_count) => retry_count,
+                None if options.justid => delivery_count,
+                None => delivery_count + 1,
+            };
+
+            let delivery_time = match forced_time {
+                Some(time) => time,
+                None if options.justid => previous_time,
+                None => now,
+            };
+
+            claims.claim(stream, id, delivery_time, delivery_count);
+        }
+
+        let (claimed, _) = claims.finish(stream, options.justid, &mut self.propagate_as);
+        Ok(vec![claimed])
+    }
+
+    /// Claims up to `count` entries idle for long enough, scanning the PEL from `start`, and
+    /// replies with where to continue from.
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn xautoclaim(
+        &mut self,
+        key: Vec<u8>,
+        group: Vec<u8>,
+        consumer: Vec<u8>,
+        min_idle_time: u64,
+        start: StreamId,
+        count: usize,
+        justid: bool,
+    ) -> CommandResult {
+        let stream = group_stream(&mut self.database, &key, &group)?;
+        let now = now_millis();
+
+        // like Redis, look at no more than 10 entries for every one asked for
+        let mut attempts = count.saturating_mul(10);
+
+        let candidates: Vec<_> = stream
+            .group(&group)
+            .unwrap()
+            .pending_from(start)
+            .take(attempts.saturating_add(1))
+            .map(|(id, entry)| (*id, entry.clone()))
+            .collect();
+
+        let mut claims = Claims::new(&key, &group, &consumer, now);
+        claims.create_consumer(stream);
+
+        let mut next = StreamId::MIN;
+
+        for (id, pending) in candidates {
+            if claims.claimed.len() == count || attempts == 0 {
+                next = id;
+                break;
+            }
+
+            attempts -= 1;
+
+            if idle(&pending, now) < min_idle_time && stream.entry(id).is_some() {
+                continue;
+            }
+
+            let delivery_count = match justid {
+                true => pending.delivery_count,
+                false => pending.delivery_count + 1,
+            };
+
+            claims.claim(stream, id, now, delivery_count);
+        }
+
+        let (claimed, deleted) = claims.finish(stream, justid, &mut self.propagate_as);
+
+        Ok(vec![Value::array(vec![
+            Value::bulk_string(&next.to_string()),
+            claimed,
+            deleted,
+        ])])
+    }
+
+    pub(super) fn xinfo_stream(&mut self, key: Vec<u8>) -> CommandResult {
+        let stream = existing_stream_for_info(&mut self.database, &key)?;
+
+        let edge_entry = |entry: Option<(&StreamId, &Fields)>| match entry {
+            Some((id, fields)) => entry_value(*id, Some(fields.clone())),
+            None => Value::NullBulkString,
+        };
+
+        let info = vec![
+            Value::bulk_string("length"),
+            Value::integer(stream.len() as i64),
+            Value::bulk_string("last-generated-id"),
+            Value::bulk_string(&stream.last_id().to_string()),
+            Value::bulk_string("max-deleted-entry-id"),
+            Value::bulk_string(&stream.max_deleted_id().to_string()),
+            Value::bulk_string("entries-added"),
+            Value::integer(stream.entries_added() as i64),
+            Value::bulk_string("recorded-first-entry-id"),
+            Value::bulk_string(&stream.first_id().to_string()),
+            Value::bulk_string("groups"),
+            Value::integer(stream.groups().count() as i64),
+            Value::bulk_string("first-entry"),
+            edge_entry(stream.iter().next()),
+            Value::bulk_string("last-entry"),
+            edge_entry(stream.iter().next_back()),
+        ];
+
+        Ok(vec![Value::array(info)])
+    }
+
+    pub(super) fn xinfo_groups(&mut self, key: Vec<u8>) -> CommandResult {
+        let stream = existing_stream_for_info(&mut self.database, &key)?;
+
+        let groups = stream
+            .groups()
+            .map(|(name, group)| {
+                Value::array(vec![
+                    Value::bulk_string("name"),
+                    Value::bulk_string_from_bytes(name.clone()),
+                    Value::bulk_string("consumers"),
+                    Value::integer(group.consumers().count() as i64),
+                    Value::bulk_string("pending"),
+                    Value::integer(group.pending_len() as i64),
+                    Value::bulk_string("last-delivered-id"),
+                    Value::bulk_string(&group.last_id.to_string()),
+                    Value::bulk_string("entries-read"),
+                    integer_or_null(group.entries_read),
+                    Value::bulk_string("lag"),
+                    integer_or_null(stream.lag(group)),
+                ])
+            })
+            .collect();
+
+        Ok(vec![Value::array(groups)])
+    }
+
+    pub(super) fn xinfo_consumers(&mut self, key: Vec<u8>, group: Vec<u8>) -> CommandResult {
+        let now = now_millis();
+        let consumers = existing_stream_for_info(&mut self.database, &key)?
+            .group(&group)
+            .ok_or_else(|| no_group_for_key(&key, &group))?
+            .consumers()
+            .map(|(name, consumer)| {
+                let inactive = consumer
+                    .active_time
+                    .map_or(-1, |active_time| now.saturating_sub(active_time) as i64);
+
+                Value::array(vec![
+                    Value::bulk_string("name"),
+                    Value::bulk_string_from_bytes(name.clone()),
+                    Value::bulk_string("pending"),
+                    Value::integer(consumer.pending_len() as i64),
+                    Value::bulk_string("idle"),
+                    Value::integer(now.saturating_sub(consumer.seen_time) as i64),
+                    Value::bulk_string("inactive"),
+                    Value::integer(inactive),
+                ])
+            })
+            .collect();
+
+        Ok(vec![Value::array(consumers)])
+    }
+}
+
+/// The claims made by one XCLAIM or XAUTOCLAIM, and what to propagate for them.
+struct Claims<'a> {
+    key: &'a [u8],
+    group: &'a [u8],
+    consumer: &'a [u8],
+    now: u64,
+    claimed: Vec<StreamId>,
+    deleted: Vec<StreamId>,
+    propagate: Vec<Command>,
+}
+
+impl<'a> Claims<'a> {
+    fn new(key: &'a [u8], group: &'a [u8], consumer: &'a [u8], now: u64) -> Self {
+        Self {
+            key,
+            group,
+            consumer,
+            now,
+            claimed: vec![],
+            deleted: vec![],
+            propagate: vec![],
+        }
+    }
+
+    fn create_consumer(&mut self, stream: &mut Stream) {
+        let group = stream.group_mut(self.group).unwrap();
+
+        if group.create_consumer(self.consumer, self.now) {
+            self.propagate.push(Command::XgroupCreateconsumer {
+                size: 0,
+                key: self.key.to_vec(),
+                group: self.group.to_vec(),
+                consumer: self.consumer.to_vec(),
+            });
+        }
+    }
+
+    /// Hands a pending entry to the consumer, or drops it if its stream entry is gone.
+    fn claim(&mut self, stream: &mut Stream, id: StreamId, delivery_time: u64, count: u64) {
+        let exists = stream.entry(id).is_some();
+        let group = stream.group_mut(self.group).unwrap();
+
+        if !exists {
+            group.remove_pending(id);
+            self.deleted.push(id);
+            return;
+        }
+
+        let pending = PendingEntry {
+            consumer: self.consumer.to_vec(),
+            delivery_time,
+            delivery_count: count,
+        };
+
+        self.propagate
+            .push(xclaim_command(self.key, self.group, id, &pending));
+        group.set_pending(id, pending);
+        self.claimed.push(id);
+    }
+
+    /// Updates the consumer, queues what to propagate, and returns the claimed entries (or
+    /// their IDs with `justid`) and the IDs of deleted entries as replies.
+    fn finish(
+        mut self,
+        stream: &mut Stream,
+        justid: bool,
+        propagate_as: &mut Option<Vec<Command>>,
+    ) -> (Value, Value) {
+        let group = stream.group_mut(self.group).unwrap();
+        group.touch(self.consumer, self.now, !self.claimed.is_empty());
+
+        if !self.deleted.is_empty() {
+            self.propagate.push(Command::Xack {
+                size: 0,
+                key: self.key.to_vec(),
+                group: self.group.to_vec(),
+                ids: self.deleted.clone(),
+            });
+        }
+
+        *propagate_as = Some(self.propagate);
+
+        let claimed = match justid {
+            true => ids_value(&self.claimed),
+            false => entries_value(
+                self.claimed
+                    .iter()
+                    .filter_map(|id| Some((*id, stream.entry(*id)?.clone())))
+                    .collect(),
+            ),
+        };
+
+        (claimed, ids_value(&self.deleted))
+    }
+}
+
+/// Delivers the entries after the group's last ID to `consumer`, moving the group past them.
+fn read_undelivered(
+    stream: &mut Stream,
+    group: &[u8],
+    consumer: &[u8],
+    count: Option<usize>,
+    noack: bool,
+    now: u64,
+) -> Vec<(StreamId, Fields)> {
+    let entries = match stream.group(group).unwrap().last_id.next() {
+        Some(start) => stream.range(start, StreamId::MAX, false, count),
+        None => vec![],
+    };
+
+    for (id, _) in &entries {
+        stream.advance_group(group, *id);
+
+        if !noack {
+            let pending = PendingEntry {
+                consumer: consumer.to_vec(),
+                delivery_time: now,
+                delivery_count: 1,
+            };
+
+            stream.group_mut(group).unwrap().set_pending(*id, pending);
+        }
+    }
+
+    entries
+}
+
+/// Delivers the consumer's pending entries after `start` again. Entries deleted from the stream
+/// come back without fields and are left as they were.
+fn read_pending(
+    stream: &mut Stream,
+    group: &[u8],
+    consumer: &[u8],
+    start: StreamId,
+    count: Option<usize>,
+    now: u64,
+) -> Vec<(StreamId, Option<Fields>)> {
+    let Some(start) = start.next() else {
+        return vec![];
+    };
+
+    let pending: Vec<_> = stream
+        .group(group)
+        .unwrap()
+        .consumer_pending_from(consumer, start)
+        .take(count.unwrap_or(usize::MAX))
+        .map(|(id, entry)| (id, entry.clone()))
+        .collect();
+
+    let mut entries = Vec::with_capacity(pending.len());
+
+    for (id, mut entry) in pending {
+        let fields = stream.entry(id).cloned();
+
+        if fields.is_some() {
+            entry.delivery_time = now;
+            entry.delivery_count += 1;
+            stream.group_mut(group).unwrap().set_pending(id, entry);
+        }
+
+        entries.push((id, fields));
+    }
+
+    entries
+}
+
+/// The XCLAIM that gives a replica the same pending entry.
+fn xclaim_command(key: &[u8], group: &[u8], id: StreamId, pending: &PendingEntry) -> Command {
+    Command::Xclaim {
+        size: 0,
+        key: key.to_vec(),
+        group: group.to_vec(),
+        consumer: pending.consumer.clone(),
+        min_idle_time: 0,
+        ids: vec![id],
+        options: XclaimOptions {
+            time: Some(pending.delivery_time),
+            retry_count: Some(pending.delivery_count),
+            force: true,
+            justid: true,
+            ..XclaimOptions::default()
+        },
+    }
+}
+
+/// Replies with the number of pending entries, the smallest and largest of their IDs, and how
+/// many each consumer has.
+fn pending_summary(group: &ConsumerGroup) -> Value {
+    let first = group.pending_from(StreamId::MIN).next();
+    let last = group.pending_from(StreamId::MIN).next_back();
+
+    let (Some((first, _)), Some((last, _))) = (first, last) else {
+        return Value::array(vec![
+            Value::integer(0),
+            Value::NullBulkString,
+            Value::NullBulkString,
+            Value::NullArray,
+        ]);
+    };
+
+    let consumers = group
+        .consumers()
+        .filter(|(_, consumer)| consumer.pending_len() > 0)
+        .map(|(name, consumer)| {
+            Value::array(vec![
+                Value::bulk_string_from_bytes(name.clone()),
+                Value::bulk_string(&consumer.pending_len().to_string()),
+            ])
+        })
+        .collect();
+
+    Value::array(vec![
+        Value::integer(group.pending_len() as i64),
+        Value::bulk_string(&first.to_string()),
+        Value::bulk_string(&last.to_string()),
+        Value::array(consumers),
+    ])
+}
+
+fn resolve_id(stream: &Stream, id: XreadId) -> StreamId {
+    match id {
+        XreadId::After(id) => id,
+        XreadId::Last => stream.last_id(),
+    }
+}
+
+/// How long ago an entry was last delivered, in milliseconds.
+fn idle(entry: &PendingEntry, now: u64) -> u64 {
+    now.saturating_sub(entry.delivery_time)
+}
+
+fn ids_value(ids: &[StreamId]) -> Value {
+    Value::array(
+        ids.iter()
+            .map(|id| Value::bulk_string(&id.to_string()))
+            .collect(),
+    )
+}
+
+fn integer_or_null(value: Option<u64>) -> Value {
+    match value {
+        Some(value) => Value::integer(value as i64),
+        None => Value::NullBulkString,
+    }
+}
+
+/// The stream at `key` for an XGROUP subcommand, which fails if there's none.
+fn existing_stream<'a>(
+    database: &'a mut Database,
+    key: &[u8],
+) -> Result<&'a mut Stream, CommandError> {
+    database.stream(key)?.ok_or_else(no_key_for_xgroup)
+}
+
+fn no_key_for_xgroup() -> CommandError {
+    CommandError::Other(
+        "ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want \
+         to use the MKSTREAM option to create an empty stream automatically."
+            .to_string(),
+    )
+}
+
+fn existing_stream_for_info<'a>(
+    database: &'a mut Database,
+    key: &[u8],
+) -> Result<&'a mut Stream, CommandError> {
+    database
+        .stream(key)?
+        .ok_or_else(|| CommandError::Other("ERR no such key".to_string()))
+}
+
+/// The stream at `key`, which must have `group`.
+fn group_stream<'a>(
+    database: &'a mut Database,
+    key: &[u8],
+    group: &[u8],
+) -> Result<&'a mut Stream, CommandError> {
+    database
+        .stream(key)?
+        .filter(|stream| stream.group(group).is_some())
+        .ok_or_else(|| {
+            CommandError::Other(format!(
+                "NOGROUP No such key '{}' or consumer group '{}'",
+                String::from_utf8_lossy(key),
+                String::from_utf8_lossy(group)
+            ))
+        })
+}
+
+fn no_group_for_key(key: &[u8], group: &[u8]) -> CommandError {
+    CommandError::Other(format!(
+        "NOGROUP No such consumer group '{}' for key name '{}'",
+        String::from_utf8_lossy(group),
+        String::from_utf8_lossy(key)
+    ))
+}
@@ -1,21 +1,78 @@
-use std::collections::HashMap;
-use std::time::{Duration, Instant};
+use super::{sorted_set::SortedSet, stream::Stream};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::SystemTime;
 
+/// The reply to a command that expects a different type than the one stored at its key.
+#[derive(Debug, thiserror::Error)]
+#[error("WRONGTYPE Operation against a key holding the wrong kind of value")]
+pub struct WrongType;
+
+/// The fields and values of a hash.
+pub type Hash = HashMap<Vec<u8>, Vec<u8>>;
+
+/// The members of a set.
+pub type Set = HashSet<Vec<u8>>;
+
+/// Members paired with their scores.
+pub type ScoredMembers = Vec<(Vec<u8>, f64)>;
+
+/// A value stored at a key.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Data {
+    String(Vec<u8>),
+    List(VecDeque<Vec<u8>>),
+    Set(Set),
+    SortedSet(SortedSet),
+    Hash(Hash),
+    Stream(Stream),
+}
+
+impl Data {
+    /// The name reported by TYPE.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Data::String(_) => "string",
+            Data::List(_) => "list",
+            Data::Set(_) => "set",
+            Data::SortedSet(_) => "zset",
+            Data::Hash(_) => "hash",
+            Data::Stream(_) => "stream",
+        }
+    }
+
+    /// Whether this is an empty aggregate, which Redis never keeps around. Streams are kept
+    /// when empty, as they still hold their last ID.
+    fn is_empty(&self) -> bool {
+        match self {
+            Data::String(_) => false,
+            Data::List(list) => list.is_empty(),
+            Data::Set(set) => set.is_empty(),
+            Data::SortedSet(set) => set.is_empty(),
+            Data::Hash(hash) => hash.is_empty(),
+            Data::Stream(_) => false,
+        }
+    }
+}
+
+/// A stored value. It's shared with any snapshot taken while it was stored, and only copied
+/// when it's changed while a snapshot still holds it.
+#[derive(Clone)]
 struct Entry {
-    value: Vec<u8>,
-    expiry: Option<Duration>,
-    created_at: Instant,
+    data: Arc<Data>,
+    expires_at: Option<SystemTime>,
 }
 
 impl Entry {
     fn is_expired(&self) -> bool {
-        match self.expiry {
-            Some(expiry) => self.created_at + expiry < Instant::now(),
-            None => false,
-        }
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= SystemTime::now())
     }
 }
 
+/// The keyspace. Cloning it is how snapshots are taken for background saves: that copies the
+/// key table, but the values are shared with the snapshot until either side changes them.
+#[derive(Clone)]
 pub struct Database {
     data: HashMap<Vec<u8>, Entry>,
 }
@@ -27,24 +84,351 @@ impl Database {
         }
     }
 
-    pub fn get(&mut self, key: Vec<u8>) -> Option<Vec<u8>> {
-        match self.data.get(&key) {
-            Some(entry) if entry.is_expired() => {
-                self.data.remove(&key);
-                None
+    /// Returns the string stored at `key`.
+    pub fn get(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>, WrongType> {
+        match self.live(key) {
+            Some(Data::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the string stored at `key`, to change it in place.
+    pub fn string(&mut self, key: &[u8]) -> Result<Option<&mut Vec<u8>>, WrongType> {
+        match self.get_mut(key) {
+            Some(Data::String(value)) => Ok(Some(value)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores a string at `key`, replacing any previous value regardless of its type.
+    pub fn set(&mut self, key: Vec<u8>, value: Vec<u8>, expires_at: Option<SystemTime>) {
+        self.insert(key, Data::String(value), expires_at);
+    }
+
+    /// Stores a string at `key` like `set`, but keeps the time to live the key already had.
+    pub fn set_keep_ttl(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        let expires_at = self.expires_at(&key).flatten();
+        self.set(key, value, expires_at);
+    }
+
+    pub fn insert(&mut self, key: Vec<u8>, data: Data, expires_at: Option<SystemTime>) {
+        let data = Arc::new(data);
+        self.data.insert(key, Entry { data, expires_at });
+    }
+
+    /// When `key` expires: `None` if there's no such key, `Some(None)` if it never does.
+    pub fn expires_at(&mut self, key: &[u8]) -> Option<Option<SystemTime>> {
+        self.live(key)?;
+        self.data.get(key).map(|entry| entry.expires_at)
+    }
+
+    /// Changes when `key` expires, or makes it persistent with `None`. Returns whether the key
+    /// exists.
+    pub fn set_expires_at(&mut self, key: &[u8], expires_at: Option<SystemTime>) -> bool {
+        self.live(key);
+
+        match self.data.get_mut(key) {
+            Some(entry) => {
+                entry.expires_at = expires_at;
+                true
             }
-            Some(entry) => Some(entry.value.clone()),
-            None => None,
+            None => false,
+        }
+    }
+
+    /// The type of the value stored at `key`, if any.
+    pub fn type_name(&mut self, key: &[u8]) -> Option<&'static str> {
+        self.live(key).map(|data| data.type_name())
+    }
+
+    /// Returns the list stored at `key`.
+    pub fn list(&mut self, key: &[u8]) -> Result<Option<&mut VecDeque<Vec<u8>>>, WrongType> {
+        match self.get_mut(key) {
+            Some(Data::List(list)) => Ok(Some(list)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the list stored at `key`, creating an empty one if the key doesn't exist.
+    ///
+    /// Callers must leave something in a new list, or call [`remove_if_empty`].
+    ///
+    /// [`remove_if_empty`]: Self::remove_if_empty
+    pub fn list_or_insert(&mut self, key: &[u8]) -> Result<&mut VecDeque<Vec<u8>>, WrongType> {
+        if self.live(key).is_none() {
+            self.insert(key.to_vec(), Data::List(VecDeque::new()), None);
+        }
+
+        match self.get_mut(key) {
+            Some(Data::List(list)) => Ok(list),
+            _ => Err(WrongType),
+        }
+    }
+
+    /// Returns the set stored at `key`.
+    pub fn members(&mut self, key: &[u8]) -> Result<Option<&mut Set>, WrongType> {
+        match self.get_mut(key) {
+            Some(Data::Set(set)) => Ok(Some(set)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the set stored at `key`, creating an empty one if the key doesn't exist.
+    ///
+    /// Callers must leave something in a new set, or call [`remove_if_empty`].
+    ///
+    /// [`remove_if_empty`]: Self::remove_if_empty
+    pub fn members_or_insert(&mut self, key: &[u8]) -> Result<&mut Set, WrongType> {
+        if self.live(key).is_none() {
+            self.insert(key.to_vec(), Data::Set(Set::new()), None);
+        }
+
+        match self.get_mut(key) {
+            Some(Data::Set(set)) => Ok(set),
+            _ => Err(WrongType),
+        }
+    }
+
+    /// Returns the sorted set stored at `key`.
+    pub fn sorted_set(&mut self, key: &[u8]) -> Result<Option<&mut SortedSet>, WrongType> {
+        match self.get_mut(key) {
+            Some(Data::SortedSet(set)) => Ok(Some(set)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the sorted set stored at `key`, creating an empty one if the key doesn't exist.
+    ///
+    /// Callers must leave something in a new sorted set, or call [`remove_if_empty`].
+    ///
+    /// [`remove_if_empty`]: Self::remove_if_empty
+    pub fn sorted_set_or_insert(&mut self, key: &[u8]) -> Result<&mut SortedSet, WrongType> {
+        if self.live(key).is_none() {
+            self.insert(key.to_vec(), Data::SortedSet(SortedSet::new()), None);
+        }
+
+        match self.get_mut(key) {
+            Some(Data::SortedSet(set)) => Ok(set),
+            _ => Err(WrongType),
         }
     }
 
-    pub fn set(&mut self, key: Vec<u8>, value: Vec<u8>, expiry: Option<Duration>) {
-        let entry = Entry {
-            value,
-            expiry,
-            created_at: Instant::now(),
-        };
+    /// Returns the members and scores of the sorted set stored at `key`, or of the set stored
+    /// there with every score as 1, as the commands that combine sorted sets accept both.
+    pub fn scores(&mut self, key: &[u8]) -> Result<Option<ScoredMembers>, WrongType> {
+        match self.live(key) {
+            Some(Data::SortedSet(set)) => Ok(Some(
+                set.iter()
+                    .map(|(member, score)| (member.to_vec(), score))
+                    .collect(),
+            )),
+            Some(Data::Set(set)) => Ok(Some(
+                set.iter().map(|member| (member.clone(), 1.0)).collect(),
+            )),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the hash stored at `key`.
+    pub fn hash(&mut self, key: &[u8]) -> Result<Option<&mut Hash>, WrongType> {
+        match self.get_mut(key) {
+            Some(Data::Hash(hash)) => Ok(Some(hash)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the hash stored at `key`, creating an empty one if the key doesn't exist.
+    ///
+    /// Callers must leave something in a new hash, or call [`remove_if_empty`].
+    ///
+    /// [`remove_if_empty`]: Self::remove_if_empty
+    pub fn hash_or_insert(&mut self, key: &[u8]) -> Result<&mut Hash, WrongType> {
+        if self.live(key).is_none() {
+            self.insert(key.to_vec(), Data::Hash(Hash::new()), None);
+        }
+
+        match self.get_mut(key) {
+            Some(Data::Hash(hash)) => Ok(hash),
+            _ => Err(WrongType),
+        }
+    }
+
+    /// Returns the stream stored at `key`.
+    pub fn stream(&mut self, key: &[u8]) -> Result<Option<&mut Stream>, WrongType> {
+        match self.get_mut(key) {
+            Some(Data::Stream(stream)) => Ok(Some(stream)),
+            Some(_) => Err(WrongType),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the stream stored at `key`, creating an empty one if the key doesn't exist.
+    pub fn stream_or_insert(&mut self, key: &[u8]) -> Result<&mut Stream, WrongType> {
+        if self.live(key).is_none() {
+            self.insert(key.to_vec(), Data::Stream(Stream::new()), None);
+        }
+
+        match self.get_mut(key) {
+            Some(Data::Stream(stream)) => Ok(stream),
+            _ => Err(WrongType),
+        }
+    }
+
+    /// Deletes `key`, returning whether it existed.
+    pub fn remove(&mut self, key: &[u8]) -> bool {
+        self.live(key).is_some() && self.data.remove(key).is_some()
+    }
+
+    /// Deletes `key` if it holds an empty aggregate, as left behind by pops and removals.
+    pub fn remove_if_empty(&mut self, key: &[u8]) {
+        if self
+            .data
+            .get(key)
+            .is_some_and(|entry| entry.data.is_empty())
+        {
+            self.data.remove(key);
+        }
+    }
+
+    /// Iterates over every key that hasn't expired yet, with its absolute expiry time.
+    pub fn entries(&self) -> impl Iterator<Item = (&[u8], &Data, Option<SystemTime>)> {
+        self.data
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired())
+            .map(|(key, entry)| (key.as_slice(), entry.data.as_ref(), entry.expires_at))
+    }
+
+    /// Returns the live value at `key`, deleting it first if it has expired.
+    fn live(&mut self, key: &[u8]) -> Option<&Data> {
+        if self.data.get(key).is_some_and(Entry::is_expired) {
+            self.data.remove(key);
+        }
+
+        self.data.get(key).map(|entry| entry.data.as_ref())
+    }
+
+    /// Returns the live value at `key` to change it, copying it first if a snapshot shares it.
+    fn get_mut(&mut self, key: &[u8]) -> Option<&mut Data> {
+        self.live(key)?;
+        self.data
+            .get_mut(key)
+            .map(|entry| Arc::make_mut(&mut entry.data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrong_type() {
+        let mut database = Database::new();
+        database.set(key("string"), key("value"), None);
+        database
+            .list_or_insert(&key("list"))
+            .unwrap()
+            .push_back(key("a"));
+
+        assert!(database.list(&key("string")).is_err());
+        assert!(database.list_or_insert(&key("string")).is_err());
+        assert!(database.get(&key("list")).is_err());
+        assert!(database.hash(&key("list")).is_err());
+        assert!(database.hash_or_insert(&key("string")).is_err());
+        assert!(database.members(&key("list")).is_err());
+        assert!(database.sorted_set(&key("list")).is_err());
+        assert_eq!(database.type_name(&key("list")), Some("list"));
+        assert_eq!(database.type_name(&key("missing")), None);
+    }
+
+    #[test]
+    fn remove_if_empty() {
+        let mut database = Database::new();
+        database.list_or_insert(&key("list")).unwrap();
+        database.set(key("string"), vec![], None);
+
+        database.remove_if_empty(&key("list"));
+        database.remove_if_empty(&key("string"));
+
+        assert_eq!(database.type_name(&key("list")), None);
+        assert_eq!(database.type_name(&key("string")), Some("string"));
+    }
+
+    #[test]
+    fn expired_keys_are_gone() {
+        let mut database = Database::new();
+        database.set(key("gone"), key("value"), Some(SystemTime::now()));
+
+        assert_eq!(database.get(&key("gone")).unwrap(), None);
+        assert_eq!(database.entries().count(), 0);
+    }
+
+    #[test]
+    fn set_keep_ttl() {
+        let mut database = Database::new();
+        database.set(key("a"), key("1"), Some(in_a_minute()));
+        database.set_keep_ttl(key("a"), key("2"));
+        database.set_keep_ttl(key("b"), key("3"));
+
+        let mut entries: Vec<_> = database
+            .entries()
+            .map(|(key, _, expires_at)| (key.to_vec(), expires_at.is_some()))
+            .collect();
+        entries.sort();
+
+        assert_eq!(entries, vec![(key("a"), true), (key("b"), false)]);
+        assert_eq!(database.get(&key("a")).unwrap(), Some(key("2")));
+    }
+
+    #[test]
+    fn change_expiry() {
+        let mut database = Database::new();
+        database.set(key("a"), key("1"), None);
+        assert_eq!(database.expires_at(&key("a")), Some(None));
+        assert_eq!(database.expires_at(&key("b")), None);
+
+        let deadline = in_a_minute();
+        assert!(database.set_expires_at(&key("a"), Some(deadline)));
+        assert!(!database.set_expires_at(&key("b"), Some(deadline)));
+        assert_eq!(database.expires_at(&key("a")), Some(Some(deadline)));
+
+        assert!(database.set_expires_at(&key("a"), Some(SystemTime::UNIX_EPOCH)));
+        assert_eq!(database.expires_at(&key("a")), None);
+    }
+
+    #[test]
+    fn snapshots_share_values_until_changed() {
+        let mut database = Database::new();
+        database.set(key("a"), key("1"), None);
+        database
+            .list_or_insert(&key("list"))
+            .unwrap()
+            .push_back(key("x"));
+
+        let mut snapshot = database.clone();
+        database.set_keep_ttl(key("a"), key("2"));
+        database
+            .list(&key("list"))
+            .unwrap()
+            .unwrap()
+            .push_back(key("y"));
+
+        assert_eq!(snapshot.get(&key("a")).unwrap(), Some(key("1")));
+        assert_eq!(snapshot.list(&key("list")).unwrap().unwrap().len(), 1);
+        assert_eq!(database.list(&key("list")).unwrap().unwrap().len(), 2);
+    }
+
+    fn in_a_minute() -> SystemTime {
+        SystemTime::now() + std::time::Duration::from_secs(60)
+    }
 
-        self.data.insert(key, entry);
+    fn key(key: &str) -> Vec<u8> {
+        key.as_bytes().to_vec()
     }
 }
@@ -0,0 +1,151 @@
+use super::{CommandResult, Server};
+use crate::protocol::{ExpireFlags, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+impl Server {
+    /// Sets when a key expires, unless its flags rule it out, in which case nothing is
+    /// propagated. A deadline already in the past deletes the key.
+    pub(super) fn expire(
+        &mut self,
+        key: Vec<u8>,
+        deadline: SystemTime,
+        flags: ExpireFlags,
+    ) -> CommandResult {
+        let allowed = self
+            .database
+            .expires_at(&key)
+            .is_some_and(|current| flags_allow(flags, current, deadline));
+
+        if !allowed {
+            self.propagate_as = Some(vec![]);
+            return Ok(vec![Value::integer(0)]);
+        }
+
+        if deadline <= SystemTime::now() {
+            self.database.remove(&key);
+        } else {
+            self.database.set_expires_at(&key, Some(deadline));
+        }
+
+        Ok(vec![Value::integer(1)])
+    }
+
+    /// The time to live of a key in seconds, rounded, or in milliseconds. -2 means there's no
+    /// such key and -1 that it never expires.
+    pub(super) fn ttl(&mut self, key: Vec<u8>, millis: bool) -> CommandResult {
+        let ttl = match self.database.expires_at(&key) {
+            Some(Some(deadline)) => {
+                let ttl = deadline
+                    .duration_since(SystemTime::now())
+                    .unwrap_or_default()
+                    .as_millis() as i64;
+
+                if millis {
+                    ttl
+                } else {
+                    (ttl + 500) / 1000
+                }
+            }
+            Some(None) => -1,
+            None => -2,
+        };
+
+        Ok(vec![Value::integer(ttl)])
+    }
+
+    /// When a key expires as a Unix time in seconds or milliseconds, with -2 and -1 as in TTL.
+    pub(super) fn expiretime(&mut self, key: Vec<u8>, millis: bool) -> CommandResult {
+        let time = match self.database.expires_at(&key) {
+            Some(Some(deadline)) => {
+                let time = deadline
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as i64;
+
+                if millis {
+                    time
+                } else {
+                    time / 1000
+                }
+            }
+            Some(None) => -1,
+            None => -2,
+        };
+
+        Ok(vec![Value::integer(time)])
+    }
+
+    pub(super) fn persist(&mut self, key: Vec<u8>) -> CommandResult {
+        let persisted = matches!(self.database.expires_at(&key), Some(Some(_)))
+            && self.database.set_expires_at(&key, None);
+
+        if !persisted {
+            self.propagate_as = Some(vec![]);
+        }
+
+        Ok(vec![Value::integer(persisted as i64)])
+    }
+}
+
+/// Whether EXPIRE's flags let it replace a key's current expiry, where `None` means the key
+/// never expires.
+fn flags_allow(flags: ExpireFlags, current: Option<SystemTime>, deadline: SystemTime) -> bool {
+    match current {
+        Some(current) => {
+            !(flags.nx || (flags.gt && deadline <= current) || (flags.lt && deadline >= current))
+        }
+        None => !(flags.xx || flags.gt),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn expire_flags() {
+        let now = SystemTime::now();
+        let later = now + Duration::from_secs(60);
+        let flags = |nx, xx, gt, lt| ExpireFlags { nx, xx, gt, lt };
+
+        assert!(flags_allow(ExpireFlags::default(), Some(later), now));
+        assert!(flags_allow(flags(true, false, false, false), None, now));
+        assert!(!flags_allow(
+            flags(true, false, false, false),
+            Some(later),
+            now
+        ));
+        assert!(!flags_allow(flags(false, true, false, false), None, now));
+        assert!(flags_allow(
+            flags(false, true, false, false),
+            Some(now),
+            later
+        ));
+
+        // a key without an expiry never expires, which is later than any deadline
+        assert!(!flags_allow(flags(false, false, true, false), None, later));
+        assert!(flags_allow(flags(false, false, false, true), None, later));
+
+        assert!(flags_allow(
+            flags(false, false, true, false),
+            Some(now),
+            later
+        ));
+        assert!(!flags_allow(
+            flags(false, false, true, false),
+            Some(later),
+            later
+        ));
+        assert!(flags_allow(
+            flags(false, false, false, true),
+            Some(later),
+            now
+        ));
+        assert!(!flags_allow(
+            flags(false, false, false, true),
+            Some(now),
+            later
+        ));
+    }
+}
@@ -0,0 +1,228 @@
+use super::{number, pattern, CommandError, CommandResult, Server};
+use crate::protocol::{Command, Value};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+/// How many fields HSCAN returns when the client doesn't give a COUNT.
+const DEFAULT_SCAN_COUNT: usize = 10;
+
+impl Server {
+    pub(super) fn hset(&mut self, key: Vec<u8>, pairs: Vec<(Vec<u8>, Vec<u8>)>) -> CommandResult {
+        let hash = self.database.hash_or_insert(&key)?;
+        let added = pairs
+            .into_iter()
+            .filter(|(field, value)| hash.insert(field.clone(), value.clone()).is_none())
+            .count();
+
+        Ok(vec![Value::integer(added as i64)])
+    }
+
+    pub(super) fn hget(&mut self, key: Vec<u8>, field: Vec<u8>) -> CommandResult {
+        let value = self
+            .database
+            .hash(&key)?
+            .and_then(|hash| hash.get(&field).cloned());
+
+        Ok(vec![bulk_or_null(value)])
+    }
+
+    pub(super) fn hmget(&mut self, key: Vec<u8>, fields: Vec<Vec<u8>>) -> CommandResult {
+        let hash = self.database.hash(&key)?;
+
+        let values = fields
+            .iter()
+            .map(|field| bulk_or_null(hash.as_ref().and_then(|hash| hash.get(field).cloned())))
+            .collect();
+
+        Ok(vec![Value::array(values)])
+    }
+
+    pub(super) fn hdel(&mut self, key: Vec<u8>, fields: Vec<Vec<u8>>) -> CommandResult {
+        let Some(hash) = self.database.hash(&key)? else {
+            return Ok(vec![Value::integer(0)]);
+        };
+
+        let removed = fields
+            .iter()
+            .filter(|field| hash.remove(*field).is_some())
+            .count();
+
+        self.database.remove_if_empty(&key);
+        Ok(vec![Value::integer(removed as i64)])
+    }
+
+    pub(super) fn hgetall(&mut self, key: Vec<u8>) -> CommandResult {
+        let values = match self.database.hash(&key)? {
+            Some(hash) => hash
+                .iter()
+                .flat_map(|(field, value)| [field.clone(), value.clone()])
+                .map(Value::bulk_string_from_bytes)
+                .collect(),
+            None => vec![],
+        };
+
+        Ok(vec![Value::array(values)])
+    }
+
+    pub(super) fn hincrby(
+        &mut self,
+        key: Vec<u8>,
+        field: Vec<u8>,
+        increment: i64,
+    ) -> CommandResult {
+        let hash = self.database.hash_or_insert(&key)?;
+
+        let result = match hash.get(&field) {
+            Some(value) => number::parse_integer(value)
+                .ok_or_else(|| CommandError::Other("ERR hash value is not an integer".to_string())),
+            None => Ok(0),
+        }
+        .and_then(|current| {
+            current.checked_add(increment).ok_or_else(|| {
+                CommandError::Other("ERR increment or decrement would overflow".to_string())
+            })
+        });
+
+        // a missing field can't fail, so there's never an empty hash left behind
+        let value = result?;
+        hash.insert(field, value.to_string().into_bytes());
+        Ok(vec![Value::integer(value)])
+    }
+
+    /// Increments a field by a float, propagated as an HSET of the result so replicas don't
+    /// depend on their own float arithmetic.
+    pub(super) fn hincrbyfloat(
+        &mut self,
+        key: Vec<u8>,
+        field: Vec<u8>,
+        increment: f64,
+    ) -> CommandResult {
+        let hash = self.database.hash_or_insert(&key)?;
+
+        let result = match hash.get(&field) {
+            Some(value) => number::parse_float(value)
+                .ok_or_else(|| CommandError::Other("ERR hash value is not a float".to_string())),
+            None => Ok(0.0),
+        }
+        .and_then(|current| {
+            Some(current + increment)
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| {
+                    CommandError::Other("ERR increment would produce NaN or Infinity".to_string())
+                })
+        });
+
+        let value = number::format_float(result?).into_bytes();
+        hash.insert(field.clone(), value.clone());
+
+        self.propagate_as = Some(vec![Command::Hset {
+            size: 0,
+            key,
+            pairs: vec![(field, value.clone())],
+        }]);
+
+        Ok(vec![Value::bulk_string_from_bytes(value)])
+    }
+
+    pub(super) fn hexists(&mut self, key: Vec<u8>, field: Vec<u8>) -> CommandResult {
+        let exists = self
+            .database
+            .hash(&key)?
+            .is_some_and(|hash| hash.contains_key(&field));
+
+        Ok(vec![Value::integer(exists as i64)])
+    }
+
+    pub(super) fn hlen(&mut self, key: Vec<u8>) -> CommandResult {
+        let len = self.database.hash(&key)?.map_or(0, |hash| hash.len());
+        Ok(vec![Value::integer(len as i64)])
+    }
+
+    pub(super) fn hkeys(&mut self, key: Vec<u8>) -> CommandResult {
+        let fields = match self.database.hash(&key)? {
+            Some(hash) => hash
+                .keys()
+                .cloned()
+                .map(Value::bulk_string_from_bytes)
+                .collect(),
+            None => vec![],
+        };
+
+        Ok(vec![Value::array(fields)])
+    }
+
+    pub(super) fn hvals(&mut self, key: Vec<u8>) -> CommandResult {
+        let values = match self.database.hash(&key)? {
+            Some(hash) => hash
+                .values()
+                .cloned()
+                .map(Value::bulk_string_from_bytes)
+                .collect(),
+            None => vec![],
+        };
+
+        Ok(vec![Value::array(values)])
+    }
+
+    /// Iterates over a hash in the order of its fields' hashes, using the hash of the next field
+    /// to return as the cursor. Fields that stay in the hash for a whole iteration are returned
+    /// exactly once, whatever gets added or removed in between.
+    pub(super) fn hscan(
+        &mut self,
+        key: Vec<u8>,
+        cursor: u64,
+        pattern: Option<Vec<u8>>,
+        count: Option<usize>,
+        novalues: bool,
+    ) -> CommandResult {
+        let mut fields: Vec<_> = match self.database.hash(&key)? {
+            Some(hash) => hash
+                .iter()
+                .map(|(field, value)| (scan_position(field), field, value))
+                .filter(|(position, _, _)| *position >= cursor)
+                .collect(),
+            None => vec![],
+        };
+
+        fields.sort_unstable_by_key(|(position, _, _)| *position);
+
+        let count = count.unwrap_or(DEFAULT_SCAN_COUNT);
+        let next_cursor = fields.get(count).map_or(0, |(position, _, _)| *position);
+
+        let mut values = vec![];
+
+        for (_, field, value) in fields.into_iter().take(count) {
+            if pattern
+                .as_ref()
+                .is_some_and(|p| !pattern::matches(p, field))
+            {
+                continue;
+            }
+
+            values.push(Value::bulk_string_from_bytes(field.clone()));
+
+            if !novalues {
+                values.push(Value::bulk_string_from_bytes(value.clone()));
+            }
+        }
+
+        let next_cursor = Value::bulk_string(&next_cursor.to_string());
+        Ok(vec![Value::array(vec![next_cursor, Value::array(values)])])
+    }
+}
+
+/// Where a field falls in a scan. Never zero, as a zero cursor ends the iteration.
+fn scan_position(field: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    field.hash(&mut hasher);
+    hasher.finish().max(1)
+}
+
+fn bulk_or_null(value: Option<Vec<u8>>) -> Value {
+    match value {
+        Some(value) => Value::bulk_string_from_bytes(value),
+        None => Value::NullBulkString,
+    }
+}
@@ -0,0 +1,259 @@
+use super::{CommandError, CommandResult, Server};
+use crate::protocol::{ListEnd, Value};
+use std::collections::VecDeque;
+
+impl Server {
+    pub(super) fn push(
+        &mut self,
+        key: Vec<u8>,
+        elements: Vec<Vec<u8>>,
+        end: ListEnd,
+    ) -> CommandResult {
+        let list = self.database.list_or_insert(&key)?;
+
+        for element in elements {
+            match end {
+                ListEnd::Left => list.push_front(element),
+                ListEnd::Right => list.push_back(element),
+            }
+        }
+
+        Ok(vec![Value::integer(list.len() as i64)])
+    }
+
+    pub(super) fn pop(
+        &mut self,
+        key: Vec<u8>,
+        count: Option<usize>,
+        end: ListEnd,
+    ) -> CommandResult {
+        let Some(list) = self.database.list(&key)? else {
+            return Ok(match count {
+                Some(_) => vec![Value::NullArray],
+                None => vec![Value::NullBulkString],
+            });
+        };
+
+        let response = match count {
+            Some(count) => {
+                let popped = (0..count).map_while(|_| pop_end(list, end));
+                Value::array(popped.map(Value::bulk_string_from_bytes).collect())
+            }
+            None => match pop_end(list, end) {
+                Some(element) => Value::bulk_string_from_bytes(element),
+                None => Value::NullBulkString,
+            },
+        };
+
+        self.database.remove_if_empty(&key);
+        Ok(vec![response])
+    }
+
+    pub(super) fn lrange(&mut self, key: Vec<u8>, start: i64, stop: i64) -> CommandResult {
+        let elements = match self.database.list(&key)? {
+            Some(list) => match range(list.len(), start, stop) {
+                Some((start, stop)) => list
+                    .range(start..=stop)
+                    .cloned()
+                    .map(Value::bulk_string_from_bytes)
+                    .collect(),
+                None => vec![],
+            },
+            None => vec![],
+        };
+
+        Ok(vec![Value::array(elements)])
+    }
+
+    pub(super) fn llen(&mut self, key: Vec<u8>) -> CommandResult {
+        let len = self.database.list(&key)?.map_or(0, |list| list.len());
+        Ok(vec![Value::integer(len as i64)])
+    }
+
+    pub(super) fn lindex(&mut self, key: Vec<u8>, index: i64) -> CommandResult {
+        let element = self
+            .database
+            .list(&key)?
+            .and_then(|list| Some(list[position(list.len(), index)?].clone()));
+
+        Ok(vec![match element {
+            Some(element) => Value::bulk_string_from_bytes(element),
+            None => Value::NullBulkString,
+        }])
+    }
+
+    pub(super) fn lset(&mut self, key: Vec<u8>, index: i64, element: Vec<u8>) -> CommandResult {
+        let list = self
+            .database
+            .list(&key)?
+            .ok_or_else(|| CommandError::Other("ERR no such key".to_string()))?;
+
+        let index = position(list.len(), index)
+            .ok_or_else(|| CommandError::Other("ERR index out of range".to_string()))?;
+
+        list[index] = element;
+        Ok(vec![Value::ok()])
+    }
+
+    pub(super) fn lrem(&mut self, key: Vec<u8>, count: i64, element: Vec<u8>) -> CommandResult {
+        let Some(list) = self.database.list(&key)? else {
+            return Ok(vec![Value::integer(0)]);
+        };
+
+        // a negative count removes matches starting from the tail
+        let limit = match count {
+            0 => usize::MAX,
+            count => count.unsigned_abs() as usize,
+        };
+
+        let mut removed = 0;
+        let mut kept = VecDeque::with_capacity(list.len());
+
+        if count < 0 {
+            while let Some(item) = list.pop_back() {
+                if removed < limit && item == element {
+                    removed += 1;
+                } else {
+                    kept.push_front(item);
+                }
+            }
+        } else {
+            while let Some(item) = list.pop_front() {
+                if removed < limit && item == element {
+                    removed += 1;
+                } else {
+                    kept.push_back(item);
+                }
+            }
+        }
+
+        *list = kept;
+        self.database.remove_if_empty(&key);
+        Ok(vec![Value::integer(removed as i64)])
+    }
+
+    pub(super) fn ltrim(&mut self, key: Vec<u8>, start: i64, stop: i64) -> CommandResult {
+        if let Some(list) = self.database.list(&key)? {
+            match range(list.len(), start, stop) {
+                Some((start, stop)) => {
+                    list.truncate(stop + 1);
+                    list.drain(..start);
+                }
+                None => list.clear(),
+            }
+
+            self.database.remove_if_empty(&key);
+        }
+
+        Ok(vec![Value::ok()])
+    }
+
+    pub(super) fn linsert(
+        &mut self,
+        key: Vec<u8>,
+        before: bool,
+        pivot: Vec<u8>,
+        element: Vec<u8>,
+    ) -> CommandResult {
+        let Some(list) = self.database.list(&key)? else {
+            return Ok(vec![Value::integer(0)]);
+        };
+
+        let len = match list.iter().position(|item| *item == pivot) {
+            Some(index) => {
+                let index = if before { index } else { index + 1 };
+                list.insert(index, element);
+                list.len() as i64
+            }
+            None => -1,
+        };
+
+        Ok(vec![Value::integer(len)])
+    }
+
+    pub(super) fn lmove(
+        &mut self,
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        from: ListEnd,
+        to: ListEnd,
+    ) -> CommandResult {
+        // check the destination first so a type error leaves the source untouched
+        self.database.list(&destination)?;
+
+        let element = match self.database.list(&source)? {
+            Some(list) => pop_end(list, from),
+            None => None,
+        };
+
+        let Some(element) = element else {
+            return Ok(vec![Value::NullBulkString]);
+        };
+
+        self.database.remove_if_empty(&source);
+
+        let list = self.database.list_or_insert(&destination)?;
+        match to {
+            ListEnd::Left => list.push_front(element.clone()),
+            ListEnd::Right => list.push_back(element.clone()),
+        }
+
+        Ok(vec![Value::bulk_string_from_bytes(element)])
+    }
+}
+
+pub(super) fn pop_end(list: &mut VecDeque<Vec<u8>>, end: ListEnd) -> Option<Vec<u8>> {
+    match end {
+        ListEnd::Left => list.pop_front(),
+        ListEnd::Right => list.pop_back(),
+    }
+}
+
+/// Resolves an index that may count from the end of the list, where -1 is the last element.
+fn position(len: usize, index: i64) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+/// Resolves an inclusive range the way LRANGE and LTRIM do: negative indexes count from the end
+/// and out of range indexes are clamped. Returns `None` if the range is empty.
+pub(super) fn range(len: usize, start: i64, stop: i64) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        len + stop
+    } else {
+        stop.min(len - 1)
+    };
+
+    (start <= stop && start < len).then_some((start as usize, stop as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges() {
+        assert_eq!(range(5, 0, -1), Some((0, 4)));
+        assert_eq!(range(5, -3, -2), Some((2, 3)));
+        assert_eq!(range(5, -100, 100), Some((0, 4)));
+        assert_eq!(range(5, 3, 1), None);
+        assert_eq!(range(5, 5, 10), None);
+        assert_eq!(range(5, 0, -6), None);
+        assert_eq!(range(0, 0, -1), None);
+    }
+
+    #[test]
+    fn positions() {
+        assert_eq!(position(3, 0), Some(0));
+        assert_eq!(position(3, -1), Some(2));
+        assert_eq!(position(3, 3), None);
+        assert_eq!(position(3, -4), None);
+    }
+}
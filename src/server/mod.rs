@@ -1,20 +1,95 @@
+mod aof;
+mod blocking;
+mod consumer_group;
+mod consumer_groups;
 mod database;
+mod expire;
+mod hashes;
+mod lists;
+mod number;
+mod pattern;
+mod random;
+mod rdb;
 mod replication;
+mod sets;
+mod sorted_set;
+mod sorted_sets;
+mod stream;
+mod streams;
+mod strings;
 
 use crate::{
-    protocol::{Command, Value},
+    protocol::{Command, ListEnd, Value},
     server::replication::ReplicationManager,
 };
-use database::Database;
-use std::{collections::HashMap, net::SocketAddr, time::Duration};
-use tokio::sync::{mpsc, oneshot};
+use database::{Database, WrongType};
+use std::{
+    io,
+    net::SocketAddr,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use stream::StreamIdError;
+use tokio::{
+    sync::mpsc,
+    time::{interval, sleep_until, MissedTickBehavior},
+};
+
+use self::{
+    aof::Aof, blocking::BlockedClient, rdb::RdbError, replication::Resync, sets::SetOperation,
+};
+
+pub use aof::FsyncPolicy;
+
+/// An error reply to a command that couldn't run. Commands that fail aren't propagated.
+#[derive(Debug, thiserror::Error)]
+enum CommandError {
+    #[error(transparent)]
+    WrongType(#[from] WrongType),
+
+    #[error(transparent)]
+    StreamId(#[from] StreamIdError),
+
+    #[error("{0}")]
+    Other(String),
+}
 
-use self::replication::ReplicationError;
+type CommandResult = Result<Vec<Value>, CommandError>;
 
 #[derive(Clone)]
 pub struct ServerOptions {
     pub port: u16,
     pub replica_of: Option<String>,
+    pub dir: String,
+    pub dbfilename: String,
+    pub appendonly: bool,
+    pub appendfilename: String,
+    pub appendfsync: FsyncPolicy,
+    pub auto_aof_rewrite_percentage: u64,
+    pub auto_aof_rewrite_min_size: u64,
+    pub repl_backlog_size: usize,
+    pub replica_read_only: bool,
+    pub min_replicas_to_write: usize,
+    pub min_replicas_max_lag: Duration,
+}
+
+impl ServerOptions {
+    pub fn rdb_path(&self) -> PathBuf {
+        PathBuf::from(&self.dir).join(&self.dbfilename)
+    }
+
+    pub fn aof_path(&self) -> PathBuf {
+        PathBuf::from(&self.dir).join(&self.appendfilename)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoadError {
+    #[error("failed to load RDB snapshot: {0}")]
+    Rdb(#[from] RdbError),
+
+    #[error("failed to load AOF: {0}")]
+    Aof(#[from] io::Error),
 }
 
 #[derive(Clone)]
@@ -36,17 +111,87 @@ impl ServerHandle {
         self.sender.send(envelope).map_err(|_| ServerSendError)
     }
 
-    pub fn add_replica(
+    /// Answers a replica's PSYNC and starts streaming write commands to it.
+    pub fn sync_replica(
         &self,
         address: SocketAddr,
+        listening_port: u16,
+        master_replid: Option<Vec<u8>>,
+        master_repl_offset: Option<u64>,
         values_sender: mpsc::UnboundedSender<Vec<Value>>,
     ) -> Result<(), ServerSendError> {
-        let message = ServerMessage::AddReplica {
+        let message = ServerMessage::SyncReplica {
             address,
+            listening_port,
+            master_replid,
+            master_repl_offset,
             values_sender,
         };
         self.sender.send(message).map_err(|_| ServerSendError)
     }
+
+    /// Applies a command received through the replication stream from this server's master.
+    /// `bytes` is the command exactly as the master sent it, passed on to this server's own
+    /// replicas.
+    ///
+    /// Like every message from a master link, it's dropped unless `generation` still identifies
+    /// the current link.
+    pub fn send_from_master(
+        &self,
+        generation: u64,
+        command: Command,
+        bytes: Vec<u8>,
+        reply_to: mpsc::UnboundedSender<Vec<Value>>,
+    ) -> Result<(), ServerSendError> {
+        let envelope = ServerMessage::ProcessMasterCommand {
+            generation,
+            command,
+            bytes,
+            reply_to,
+        };
+        self.sender.send(envelope).map_err(|_| ServerSendError)
+    }
+
+    /// Passes `bytes` from the master's replication stream on to this server's replicas without
+    /// running them, as for commands this server can't parse.
+    pub fn forward_from_master(
+        &self,
+        generation: u64,
+        bytes: Vec<u8>,
+    ) -> Result<(), ServerSendError> {
+        let message = ServerMessage::ForwardFromMaster { generation, bytes };
+        self.sender.send(message).map_err(|_| ServerSendError)
+    }
+
+    /// Records a replica's REPLCONF ACK; there's no reply.
+    pub fn replica_ack(&self, address: SocketAddr, offset: u64) -> Result<(), ServerSendError> {
+        let message = ServerMessage::ReplicaAck { address, offset };
+        self.sender.send(message).map_err(|_| ServerSendError)
+    }
+
+    /// Reports a completed sync with the master, which is reachable through `master`.
+    pub fn master_link_up(
+        &self,
+        generation: u64,
+        sync: Resync,
+        master: mpsc::UnboundedSender<Vec<Value>>,
+    ) -> Result<(), ServerSendError> {
+        let message = ServerMessage::MasterLinkUp {
+            generation,
+            sync,
+            master,
+        };
+        self.sender.send(message).map_err(|_| ServerSendError)
+    }
+
+    pub fn master_link_down(&self, generation: u64) -> Result<(), ServerSendError> {
+        let message = ServerMessage::MasterLinkDown { generation };
+        self.sender.send(message).map_err(|_| ServerSendError)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
 }
 
 enum ServerMessage {
@@ -54,59 +199,167 @@ enum ServerMessage {
         command: Command,
         reply_to: mpsc::UnboundedSender<Vec<Value>>,
     },
-    AddReplica {
+    ProcessMasterCommand {
+        generation: u64,
+        command: Command,
+        bytes: Vec<u8>,
+        reply_to: mpsc::UnboundedSender<Vec<Value>>,
+    },
+    ForwardFromMaster {
+        generation: u64,
+        bytes: Vec<u8>,
+    },
+    SyncReplica {
         address: SocketAddr,
+        listening_port: u16,
+        master_replid: Option<Vec<u8>>,
+        master_repl_offset: Option<u64>,
         values_sender: mpsc::UnboundedSender<Vec<Value>>,
     },
+    ReplicaAck {
+        address: SocketAddr,
+        offset: u64,
+    },
+    BackgroundSaveCompleted {
+        result: io::Result<()>,
+    },
+    MasterLinkUp {
+        generation: u64,
+        sync: Resync,
+        master: mpsc::UnboundedSender<Vec<Value>>,
+    },
+    MasterLinkDown {
+        generation: u64,
+    },
+    AofRewriteCompleted {
+        temp_path: PathBuf,
+        result: io::Result<()>,
+    },
 }
 
-pub fn start(options: ServerOptions) -> ServerHandle {
+impl ServerMessage {
+    /// The generation of the master link that sent this message, if a master link did.
+    fn link_generation(&self) -> Option<u64> {
+        match self {
+            ServerMessage::ProcessMasterCommand { generation, .. }
+            | ServerMessage::ForwardFromMaster { generation, .. }
+            | ServerMessage::MasterLinkUp { generation, .. }
+            | ServerMessage::MasterLinkDown { generation } => Some(*generation),
+            _ => None,
+        }
+    }
+}
+
+pub async fn start(options: ServerOptions) -> Result<ServerHandle, LoadError> {
     let (tx, rx) = mpsc::unbounded_channel::<ServerMessage>();
-    let server = ServerHandle { sender: tx };
+    let handle = ServerHandle { sender: tx };
+
+    let mut server = Server::new(options.clone(), handle.clone());
+    server.load().await?;
+
+    if let Some(master) = options.replica_of {
+        server
+            .replication
+            .follow(master, options.port, handle.clone());
+    }
 
-    let repl_init = replication::start(options.clone(), server.clone());
-    tokio::spawn(async move { command_loop(options, repl_init, rx).await });
+    tokio::spawn(async move { command_loop(server, rx).await });
 
-    server
+    Ok(handle)
 }
 
-async fn command_loop(
-    options: ServerOptions,
-    repl_init: oneshot::Receiver<Result<(), ReplicationError>>,
-    mut receiver: mpsc::UnboundedReceiver<ServerMessage>,
-) {
-    let mut server = Server::new(options);
+/// How often [`Server::cron`] runs.
+const CRON_INTERVAL: Duration = Duration::from_secs(1);
 
-    match repl_init.await.unwrap() {
-        Ok(()) => {
-            println!("replica initialization completed successfully; now processing commands")
-        }
-        Err(err) => {
-            println!("failed to start replication: {}", err);
-            return;
-        }
-    }
+async fn command_loop(mut server: Server, mut receiver: mpsc::UnboundedReceiver<ServerMessage>) {
+    let mut cron = interval(CRON_INTERVAL);
+    cron.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
     loop {
-        match receiver.recv().await {
+        let deadline = server.next_deadline();
+
+        let message = tokio::select! {
+            message = receiver.recv() => message,
+            _ = cron.tick() => {
+                server.cron();
+                continue;
+            }
+            _ = sleep_until(deadline.unwrap_or_else(Instant::now).into()), if deadline.is_some() => {
+                server.wake_blocked_clients(Instant::now());
+                continue;
+            }
+        };
+
+        if message
+            .as_ref()
+            .is_some_and(|message| !server.is_current(message))
+        {
+            println!("ignoring message from a previous master link");
+            continue;
+        }
+
+        match message {
             None => {
                 println!("server channel closed; exiting task");
                 break;
             }
             Some(ServerMessage::ProcessCommand { command, reply_to }) => {
-                let response = server.handle(command.clone());
+                let response = server.handle(command, &reply_to);
+
+                // blocked clients get their reply later
+                if response.is_empty() {
+                    continue;
+                }
 
                 if reply_to.send(response).is_err() {
                     println!("failed to send response to client; ignoring");
-                } else {
-                    server.replication.replicate(&command);
                 }
             }
-            Some(ServerMessage::AddReplica {
+            Some(ServerMessage::ProcessMasterCommand {
+                command,
+                bytes,
+                reply_to,
+                ..
+            }) => {
+                let response = server.handle_from_master(command, &bytes, &reply_to);
+
+                if !response.is_empty() && reply_to.send(response).is_err() {
+                    println!("failed to send response to master; ignoring");
+                }
+            }
+            Some(ServerMessage::ForwardFromMaster { bytes, .. }) => {
+                server.replication.feed(&bytes);
+            }
+            Some(ServerMessage::SyncReplica {
                 address,
+                listening_port,
+                master_replid,
+                master_repl_offset,
                 values_sender,
             }) => {
-                server.replication.add(address, values_sender);
+                server.sync_replica(
+                    address,
+                    listening_port,
+                    master_replid,
+                    master_repl_offset,
+                    values_sender,
+                );
+            }
+            Some(ServerMessage::ReplicaAck { address, offset }) => {
+                server.replication.ack(address, offset);
+            }
+            Some(ServerMessage::BackgroundSaveCompleted { result }) => {
+                server.background_save_completed(result);
+            }
+            Some(ServerMessage::MasterLinkUp { sync, master, .. }) => {
+                server.master_link_up(sync, master);
+            }
+            Some(ServerMessage::MasterLinkDown { .. }) => {
+                println!("master link is down");
+                server.replication.set_master_link(None);
+            }
+            Some(ServerMessage::AofRewriteCompleted { temp_path, result }) => {
+                server.aof_rewrite_completed(temp_path, result);
             }
         }
     }
@@ -115,42 +368,466 @@ async fn command_loop(
 struct Server {
     options: ServerOptions,
     database: Database,
+    handle: ServerHandle,
     replication: ReplicationManager,
-    offset: usize,
+    aof: Option<Aof>,
+    last_save: SystemTime,
+    background_save_in_progress: bool,
+    blocked: Vec<BlockedClient>,
+    /// Set by a command that must reach the AOF and replicas as different commands, or as none
+    /// at all, than the one that was run. See [`Server::execute`].
+    propagate_as: Option<Vec<Command>>,
 }
 
 impl Server {
-    pub fn new(options: ServerOptions) -> Self {
+    pub fn new(options: ServerOptions, handle: ServerHandle) -> Self {
         Self {
-            options,
             database: Database::new(),
-            replication: ReplicationManager::new(),
-            offset: 0,
+            handle,
+            replication: ReplicationManager::new(options.repl_backlog_size),
+            aof: None,
+            last_save: SystemTime::now(),
+            background_save_in_progress: false,
+            blocked: Vec::new(),
+            propagate_as: None,
+            options,
+        }
+    }
+
+    /// Restores the dataset from the AOF when it's enabled, or from the RDB snapshot otherwise.
+    async fn load(&mut self) -> Result<(), LoadError> {
+        if !self.options.appendonly {
+            self.database = rdb::load(&self.options.rdb_path())?;
+            return Ok(());
         }
+
+        let path = self.options.aof_path();
+        let (reply_to, _) = mpsc::unbounded_channel();
+        let count = aof::replay(&path, |command| {
+            self.execute(command, &reply_to);
+        })
+        .await?;
+
+        println!("replayed {} commands from {}", count, path.display());
+
+        self.aof = Some(Aof::open(&path, self.options.appendfsync)?);
+        Ok(())
     }
 
-    fn handle(&mut self, command: Command) -> Vec<Value> {
-        let (size, response) = match command {
-            Command::Ping { size, message } => (size, self.ping(message)),
-            Command::Echo { size, message } => (size, self.echo(message)),
-            Command::Get { size, key, .. } => (size, self.get(key)),
+    /// Runs a command sent by a client and returns its reply. An empty reply means the client is
+    /// blocked and will be answered through `reply_to` later on.
+    fn handle(
+        &mut self,
+        command: Command,
+        reply_to: &mpsc::UnboundedSender<Vec<Value>>,
+    ) -> Vec<Value> {
+        if command.is_write() {
+            if self.options.replica_of.is_some() && self.options.replica_read_only {
+                return vec![Value::simple_error(
+                    "READONLY You can't write against a read only replica.",
+                )];
+            }
+
+            if self.options.replica_of.is_none() && !self.enough_good_replicas() {
+                return vec![Value::simple_error(
+                    "NOREPLICAS Not enough good replicas to write.",
+                )];
+            }
+        }
+
+        self.execute(command, reply_to)
+    }
+
+    /// Whether enough replicas acknowledged their offset recently for this master to accept
+    /// writes, as configured by min-replicas-to-write and min-replicas-max-lag.
+    fn enough_good_replicas(&self) -> bool {
+        let required = self.options.min_replicas_to_write;
+        required == 0 || self.good_replicas() >= required
+    }
+
+    fn good_replicas(&self) -> usize {
+        let max_lag = self.options.min_replicas_max_lag;
+        self.replication.good_replicas(max_lag)
+    }
+
+    /// Periodic housekeeping, run every [`CRON_INTERVAL`].
+    fn cron(&mut self) {
+        if self.options.replica_of.is_some() {
+            self.replication.send_ack();
+        }
+    }
+
+    /// Runs a command from the master's replication stream, then passes the master's `bytes`
+    /// on to this server's replicas, which moves the replication offset forward.
+    fn handle_from_master(
+        &mut self,
+        command: Command,
+        bytes: &[u8],
+        reply_to: &mpsc::UnboundedSender<Vec<Value>>,
+    ) -> Vec<Value> {
+        let response = self.execute(command, reply_to);
+        self.replication.feed(bytes);
+        response
+    }
+
+    /// Runs a command and propagates it, or whatever the command put in `propagate_as` instead.
+    /// Commands that fail aren't propagated.
+    fn execute(
+        &mut self,
+        command: Command,
+        reply_to: &mpsc::UnboundedSender<Vec<Value>>,
+    ) -> Vec<Value> {
+        self.propagate_as = None;
+
+        let result = match command.clone() {
+            Command::Ping { message, .. } => Ok(self.ping(message)),
+            Command::Echo { message, .. } => Ok(self.echo(message)),
+            Command::Get { key, .. } => self.get(key),
             Command::Set {
-                size,
                 key,
                 value,
+                condition,
                 expiry,
+                get,
+                ..
+            } => self.set(key, value, condition, expiry, get),
+            Command::Incr { key, .. } => self.incr_by(key, 1),
+            Command::Decr { key, .. } => self.decr_by(key, 1),
+            Command::Incrby { key, increment, .. } => self.incr_by(key, increment),
+            Command::Decrby { key, decrement, .. } => self.decr_by(key, decrement),
+            Command::Incrbyfloat { key, increment, .. } => self.incrbyfloat(key, increment),
+            Command::Append { key, value, .. } => self.append(key, value),
+            Command::Strlen { key, .. } => self.strlen(key),
+            Command::Getrange {
+                key, start, end, ..
+            } => self.getrange(key, start, end),
+            Command::Setrange {
+                key, offset, value, ..
+            } => self.setrange(key, offset, value),
+            Command::Getdel { key, .. } => self.getdel(key),
+            Command::Getex { key, expiry, .. } => self.getex(key, expiry),
+            Command::Getset { key, value, .. } => self.getset(key, value),
+            Command::Mget { keys, .. } => self.mget(keys),
+            Command::Mset { pairs, .. } => self.mset(pairs),
+            Command::Msetnx { pairs, .. } => self.msetnx(pairs),
+            Command::Setnx { key, value, .. } => self.setnx(key, value),
+            Command::Expire {
+                key,
+                deadline,
+                flags,
+                ..
+            } => self.expire(key, deadline, flags),
+            Command::Ttl { key, .. } => self.ttl(key, false),
+            Command::Pttl { key, .. } => self.ttl(key, true),
+            Command::Expiretime { key, .. } => self.expiretime(key, false),
+            Command::Pexpiretime { key, .. } => self.expiretime(key, true),
+            Command::Persist { key, .. } => self.persist(key),
+            Command::Info { .. } => Ok(self.info()),
+            Command::Replconf { key, value, .. } => Ok(self.replconf(&key, &value)),
+            Command::Psync {
+                master_replid,
+                master_repl_offset,
+                ..
+            } => Ok(self.psync(master_replid, master_repl_offset)),
+            Command::Wait {
+                replicas, timeout, ..
+            } => Ok(self.wait(replicas, timeout, reply_to)),
+            Command::Save { .. } => Ok(self.save()),
+            Command::Bgsave { .. } => Ok(self.bgsave()),
+            Command::Lastsave { .. } => Ok(self.lastsave()),
+            Command::Bgrewriteaof { .. } => Ok(self.bgrewriteaof()),
+            Command::Role { .. } => Ok(self.role()),
+            Command::Replicaof { master, .. } => Ok(self.replicaof(master)),
+            Command::Type { key, .. } => Ok(self.type_name(key)),
+            Command::Lpush { key, elements, .. } => self.push(key, elements, ListEnd::Left),
+            Command::Rpush { key, elements, .. } => self.push(key, elements, ListEnd::Right),
+            Command::Lpop { key, count, .. } => self.pop(key, count, ListEnd::Left),
+            Command::Rpop { key, count, .. } => self.pop(key, count, ListEnd::Right),
+            Command::Lrange {
+                key, start, stop, ..
+            } => self.lrange(key, start, stop),
+            Command::Llen { key, .. } => self.llen(key),
+            Command::Lindex { key, index, .. } => self.lindex(key, index),
+            Command::Lset {
+                key,
+                index,
+                element,
+                ..
+            } => self.lset(key, index, element),
+            Command::Lrem {
+                key,
+                count,
+                element,
+                ..
+            } => self.lrem(key, count, element),
+            Command::Ltrim {
+                key, start, stop, ..
+            } => self.ltrim(key, start, stop),
+            Command::Linsert {
+                key,
+                before,
+                pivot,
+                element,
+                ..
+            } => self.linsert(key, before, pivot, element),
+            Command::Lmove {
+                source,
+                destination,
+                from,
+                to,
+                ..
+            } => self.lmove(source, destination, from, to),
+            Command::Blpop { keys, timeout, .. } => {
+                self.blocking_pop(keys, ListEnd::Left, timeout, reply_to)
+            }
+            Command::Brpop { keys, timeout, .. } => {
+                self.blocking_pop(keys, ListEnd::Right, timeout, reply_to)
+            }
+            Command::Blmove {
+                source,
+                destination,
+                from,
+                to,
+                timeout,
+                ..
+            } => self.blmove(source, destination, from, to, timeout, reply_to),
+            Command::Hset { key, pairs, .. } => self.hset(key, pairs),
+            Command::Hget { key, field, .. } => self.hget(key, field),
+            Command::Hmget { key, fields, .. } => self.hmget(key, fields),
+            Command::Hdel { key, fields, .. } => self.hdel(key, fields),
+            Command::Hgetall { key, .. } => self.hgetall(key),
+            Command::Hincrby {
+                key,
+                field,
+                increment,
                 ..
-            } => (size, self.set(key, value, expiry)),
-            Command::Info { size, .. } => (size, self.info()),
-            Command::Replconf {
-                size, key, value, ..
-            } => (size, self.replconf(&key, &value)),
-            Command::Psync { size, .. } => (size, self.psync()),
-            Command::Wait { size, .. } => (size, self.wait()),
+            } => self.hincrby(key, field, increment),
+            Command::Hincrbyfloat {
+                key,
+                field,
+                increment,
+                ..
+            } => self.hincrbyfloat(key, field, increment),
+            Command::Hexists { key, field, .. } => self.hexists(key, field),
+            Command::Hlen { key, .. } => self.hlen(key),
+            Command::Hkeys { key, .. } => self.hkeys(key),
+            Command::Hvals { key, .. } => self.hvals(key),
+            Command::Hscan {
+                key,
+                cursor,
+                pattern,
+                count,
+                novalues,
+                ..
+            } => self.hscan(key, cursor, pattern, count, novalues),
+            Command::Sadd { key, members, .. } => self.sadd(key, members),
+            Command::Srem { key, members, .. } => self.srem(key, members),
+            Command::Smembers { key, .. } => self.smembers(key),
+            Command::Sismember { key, member, .. } => self.sismember(key, member),
+            Command::Smismember { key, members, .. } => self.smismember(key, members),
+            Command::Scard { key, .. } => self.scard(key),
+            Command::Spop { key, count, .. } => self.spop(key, count),
+            Command::Srandmember { key, count, .. } => self.srandmember(key, count),
+            Command::Sinter { keys, .. } => self.set_operation(keys, SetOperation::Intersection),
+            Command::Sunion { keys, .. } => self.set_operation(keys, SetOperation::Union),
+            Command::Sdiff { keys, .. } => self.set_operation(keys, SetOperation::Difference),
+            Command::Sinterstore {
+                destination, keys, ..
+            } => self.set_operation_store(destination, keys, SetOperation::Intersection),
+            Command::Sunionstore {
+                destination, keys, ..
+            } => self.set_operation_store(destination, keys, SetOperation::Union),
+            Command::Sdiffstore {
+                destination, keys, ..
+            } => self.set_operation_store(destination, keys, SetOperation::Difference),
+            Command::Zadd {
+                key,
+                flags,
+                elements,
+                ..
+            } => self.zadd(key, flags, elements),
+            Command::Zrange {
+                key,
+                by,
+                rev,
+                limit,
+                withscores,
+                ..
+            } => self.zrange(key, by, rev, limit, withscores),
+            Command::Zrank { key, member, .. } => self.zrank(key, member),
+            Command::Zscore { key, member, .. } => self.zscore(key, member),
+            Command::Zcard { key, .. } => self.zcard(key),
+            Command::Zincrby {
+                key,
+                increment,
+                member,
+                ..
+            } => self.zincrby(key, increment, member),
+            Command::Zrem { key, members, .. } => self.zrem(key, members),
+            Command::Zcount { key, min, max, .. } => self.zcount(key, min, max),
+            Command::Zpopmin { key, count, .. } => self.zpopmin(key, count),
+            Command::Zunionstore {
+                destination,
+                keys,
+                weights,
+                aggregate,
+                ..
+            } => self.zstore(destination, keys, weights, aggregate, SetOperation::Union),
+            Command::Zinterstore {
+                destination,
+                keys,
+                weights,
+                aggregate,
+                ..
+            } => self.zstore(
+                destination,
+                keys,
+                weights,
+                aggregate,
+                SetOperation::Intersection,
+            ),
+            Command::Xadd {
+                key,
+                nomkstream,
+                trim,
+                id,
+                fields,
+                ..
+            } => self.xadd(key, nomkstream, trim, id, fields),
+            Command::Xrange {
+                key,
+                start,
+                end,
+                count,
+                ..
+            } => self.xrange(key, start, end, count, false),
+            Command::Xrevrange {
+                key,
+                end,
+                start,
+                count,
+                ..
+            } => self.xrange(key, start, end, count, true),
+            Command::Xlen { key, .. } => self.xlen(key),
+            Command::Xdel { key, ids, .. } => self.xdel(key, ids),
+            Command::Xtrim { key, trim, .. } => self.xtrim(key, trim),
+            Command::Xsetid {
+                key,
+                last_id,
+                entries_added,
+                max_deleted_id,
+                ..
+            } => self.xsetid(key, last_id, entries_added, max_deleted_id),
+            Command::Xread {
+                count,
+                block,
+                streams,
+                ..
+            } => self.xread(count, block, streams, reply_to),
+            Command::XgroupCreate {
+                key,
+                group,
+                id,
+                mkstream,
+                entries_read,
+                ..
+            } => self.xgroup_create(key, group, id, mkstream, entries_read),
+            Command::XgroupSetid {
+                key,
+                group,
+                id,
+                entries_read,
+                ..
+            } => self.xgroup_setid(key, group, id, entries_read),
+            Command::XgroupDestroy { key, group, .. } => self.xgroup_destroy(key, group),
+            Command::XgroupCreateconsumer {
+                key,
+                group,
+                consumer,
+                ..
+            } => self.xgroup_createconsumer(key, group, consumer),
+            Command::XgroupDelconsumer {
+                key,
+                group,
+                consumer,
+                ..
+            } => self.xgroup_delconsumer(key, group, consumer),
+            Command::Xreadgroup {
+                group,
+                consumer,
+                count,
+                block,
+                noack,
+                streams,
+                ..
+            } => self.xreadgroup(group, consumer, count, block, noack, streams, reply_to),
+            Command::Xack {
+                key, group, ids, ..
+            } => self.xack(key, group, ids),
+            Command::Xpending {
+                key, group, range, ..
+            } => self.xpending(key, group, range),
+            Command::Xclaim {
+                key,
+                group,
+                consumer,
+                min_idle_time,
+                ids,
+                options,
+                ..
+            } => self.xclaim(key, group, consumer, min_idle_time, ids, options),
+            Command::Xautoclaim {
+                key,
+                group,
+                consumer,
+                min_idle_time,
+                start,
+                count,
+                justid,
+                ..
+            } => self.xautoclaim(key, group, consumer, min_idle_time, start, count, justid),
+            Command::XinfoStream { key, .. } => self.xinfo_stream(key),
+            Command::XinfoGroups { key, .. } => self.xinfo_groups(key),
+            Command::XinfoConsumers { key, group, .. } => self.xinfo_consumers(key, group),
         };
 
-        self.offset += size;
-        response
+        match result {
+            Ok(response) => {
+                match self.propagate_as.take() {
+                    Some(commands) => commands.iter().for_each(|c| self.propagate(c)),
+                    None => self.propagate(&command),
+                }
+
+                if command.is_write() && !self.blocked.is_empty() {
+                    self.serve_blocked_clients();
+                }
+
+                response
+            }
+            Err(err) => vec![Value::simple_error(&err.to_string())],
+        }
+    }
+
+    /// Feeds a write command to the AOF and, on a master, to every connected replica. Replicas
+    /// pass on their master's stream instead, see [`Server::handle_from_master`].
+    fn propagate(&mut self, command: &Command) {
+        if let Some(aof) = &mut self.aof {
+            if let Err(err) = aof.append(command) {
+                println!("failed to append command to AOF: {}", err);
+            }
+
+            let percentage = self.options.auto_aof_rewrite_percentage;
+            let min_size = self.options.auto_aof_rewrite_min_size;
+
+            if aof.should_rewrite(percentage, min_size) {
+                println!("AOF grew by more than {}% since last rewrite", percentage);
+                self.start_aof_rewrite();
+            }
+        }
+
+        if self.options.replica_of.is_none() {
+            self.replication.replicate(command);
+        }
     }
 
     fn ping(&self, message: Option<Vec<u8>>) -> Vec<Value> {
@@ -166,67 +843,650 @@ impl Server {
         vec![Value::bulk_string_from_bytes(message)]
     }
 
-    fn get(&mut self, key: Vec<u8>) -> Vec<Value> {
-        match self.database.get(key) {
-            Some(value) => vec![Value::bulk_string_from_bytes(value)],
-            None => vec![Value::NullBulkString],
-        }
-    }
-
-    fn set(&mut self, key: Vec<u8>, value: Vec<u8>, expiry: Option<Duration>) -> Vec<Value> {
-        self.database.set(key, value, expiry);
-        vec![Value::ok()]
+    fn type_name(&mut self, key: Vec<u8>) -> Vec<Value> {
+        let name = self.database.type_name(&key).unwrap_or("none");
+        vec![Value::simple_string(name)]
     }
 
     fn info(&self) -> Vec<Value> {
-        let mut info = HashMap::new();
+        let mut info = vec![];
+        let replication = &self.replication;
 
-        if self.options.replica_of.is_some() {
-            info.insert("role".to_string(), "slave".to_string());
-        } else {
-            info.insert("role".to_string(), "master".to_string());
-            info.insert(
-                "master_replid".to_string(),
-                "8371b4fb1155b71f4a04d3e1bc3e18c4a990aeeb".to_string(),
+        match &self.options.replica_of {
+            Some(master) => {
+                let (host, port) = master.rsplit_once(':').unwrap_or((master, ""));
+                let link_status = if replication.master_link_up() {
+                    "up"
+                } else {
+                    "down"
+                };
+
+                info.push(("role".to_string(), "slave".to_string()));
+                info.push(("master_host".to_string(), host.to_string()));
+                info.push(("master_port".to_string(), port.to_string()));
+                info.push(("master_link_status".to_string(), link_status.to_string()));
+                info.push((
+                    "slave_repl_offset".to_string(),
+                    replication.offset().to_string(),
+                ));
+                info.push((
+                    "slave_read_only".to_string(),
+                    (self.options.replica_read_only as u8).to_string(),
+                ));
+            }
+            None => info.push(("role".to_string(), "master".to_string())),
+        }
+
+        let replicas: Vec<_> = replication.replicas().collect();
+        info.push(("connected_slaves".to_string(), replicas.len().to_string()));
+
+        if self.options.min_replicas_to_write > 0 {
+            info.push((
+                "min_slaves_good_slaves".to_string(),
+                self.good_replicas().to_string(),
+            ));
+        }
+
+        for (index, replica) in replicas.iter().enumerate() {
+            let description = format!(
+                "ip={},port={},state=online,offset={},lag={}",
+                replica.address().ip(),
+                replica.listening_port(),
+                replica.ack_offset(),
+                replica.lag().as_secs()
             );
-            info.insert("master_repl_offset".to_string(), "0".to_string());
+            info.push((format!("slave{}", index), description));
         }
 
-        let result = info
-            .iter()
-            .map(|(key, value)| format!("{}:{}", key, value))
-            .collect::<Vec<_>>()
-            .join("\r\n");
+        let second_repl_offset = replication
+            .second_repl_offset()
+            .map(|offset| offset as i64)
+            .unwrap_or(-1);
+        let backlog = replication.backlog();
+
+        info.push((
+            "master_replid".to_string(),
+            replication.replid().to_string(),
+        ));
+        info.push((
+            "master_replid2".to_string(),
+            replication.replid2().to_string(),
+        ));
+        info.push((
+            "master_repl_offset".to_string(),
+            replication.offset().to_string(),
+        ));
+        info.push((
+            "second_repl_offset".to_string(),
+            second_repl_offset.to_string(),
+        ));
+        info.push(("repl_backlog_active".to_string(), "1".to_string()));
+        info.push((
+            "repl_backlog_size".to_string(),
+            backlog.capacity().to_string(),
+        ));
+        info.push((
+            "repl_backlog_first_byte_offset".to_string(),
+            backlog.first_offset().to_string(),
+        ));
+        info.push((
+            "repl_backlog_histlen".to_string(),
+            backlog.histlen().to_string(),
+        ));
+
+        let mut result = "# Replication\r\n".to_string();
+
+        for (key, value) in info {
+            result.push_str(&format!("{}:{}\r\n", key, value));
+        }
 
         vec![Value::bulk_string_from_bytes(result.into_bytes())]
     }
 
+    /// Describes this server's replication role: a master lists its replicas, a replica its
+    /// master and the state of the link.
+    fn role(&self) -> Vec<Value> {
+        let offset = self.replication.offset() as i64;
+
+        let role = match &self.options.replica_of {
+            Some(master) => {
+                let (host, port) = master.rsplit_once(':').unwrap_or((master, ""));
+                let state = if self.replication.master_link_up() {
+                    "connected"
+                } else {
+                    "connect"
+                };
+
+                vec![
+                    Value::bulk_string("slave"),
+                    Value::bulk_string(host),
+                    Value::integer(port.parse().unwrap_or(0)),
+                    Value::bulk_string(state),
+                    Value::integer(offset),
+                ]
+            }
+            None => {
+                let replicas = self
+                    .replication
+                    .replicas()
+                    .map(|replica| {
+                        Value::array(vec![
+                            Value::bulk_string(&replica.address().ip().to_string()),
+                            Value::bulk_string(&replica.listening_port().to_string()),
+                            Value::bulk_string(&replica.ack_offset().to_string()),
+                        ])
+                    })
+                    .collect();
+
+                vec![
+                    Value::bulk_string("master"),
+                    Value::integer(offset),
+                    Value::array(replicas),
+                ]
+            }
+        };
+
+        vec![Value::array(role)]
+    }
+
+    /// Turns this server into a replica of `master`, or into a master when it's `None`.
+    ///
+    /// A new replica drops its dataset once the full resynchronization with its master
+    /// completes. A promoted replica keeps its dataset and starts a new replication history.
+    fn replicaof(&mut self, master: Option<(String, u16)>) -> Vec<Value> {
+        match master {
+            None => {
+                if self.options.replica_of.take().is_some() {
+                    self.replication.unfollow();
+                    self.replication.shift_replid(random::hex_string(40));
+                    println!("promoted to master");
+                }
+
+                vec![Value::ok()]
+            }
+            Some((host, port)) => {
+                let master = format!("{}:{}", host, port);
+
+                if self.options.replica_of.as_ref() == Some(&master) {
+                    return vec![Value::simple_string(
+                        "OK Already connected to specified master",
+                    )];
+                }
+
+                println!("becoming a replica of {}", master);
+                self.replication.disconnect_replicas();
+                self.replication
+                    .follow(master.clone(), self.options.port, self.handle.clone());
+                self.options.replica_of = Some(master);
+
+                vec![Value::ok()]
+            }
+        }
+    }
+
+    /// Whether `message` should still be processed: anything from a master link that REPLICAOF
+    /// has since closed or replaced is stale.
+    fn is_current(&self, message: &ServerMessage) -> bool {
+        message.link_generation().map_or(true, |generation| {
+            generation == self.replication.link_generation()
+        })
+    }
+
+    fn master_link_up(&mut self, sync: Resync, master: mpsc::UnboundedSender<Vec<Value>>) {
+        match sync {
+            Resync::Full {
+                replid,
+                offset,
+                database,
+            } => {
+                println!("full resync with master {} at offset {}", replid, offset);
+                self.database = database;
+                self.replication.reset(replid, offset);
+
+                // their history no longer matches ours
+                self.replication.disconnect_replicas();
+            }
+            Resync::Partial { replid } => {
+                println!("partial resync with master {}", replid);
+
+                if replid != self.replication.replid() {
+                    self.replication.shift_replid(replid);
+                    self.replication.disconnect_replicas();
+                }
+            }
+        }
+
+        self.replication.set_master_link(Some(master));
+    }
+
     fn replconf(&self, key: &[u8], _value: &[u8]) -> Vec<Value> {
         if String::from_utf8_lossy(key).to_uppercase() == "GETACK" {
-            let offset = format!("{}", self.offset);
+            let offset = format!("{}", self.replication.offset());
             vec![Value::command_str("REPLCONF", &["ACK", &offset])]
         } else {
             vec![Value::ok()]
         }
     }
 
-    fn psync(&self) -> Vec<Value> {
-        let rdb = vec![
-            82, 69, 68, 73, 83, 48, 48, 49, 49, 250, 9, 114, 101, 100, 105, 115, 45, 118, 101, 114,
-            5, 55, 46, 50, 46, 48, 250, 10, 114, 101, 100, 105, 115, 45, 98, 105, 116, 115, 192,
-            64, 250, 5, 99, 116, 105, 109, 101, 194, 109, 8, 188, 101, 250, 8, 117, 115, 101, 100,
-            45, 109, 101, 109, 194, 176, 196, 16, 0, 250, 8, 97, 111, 102, 45, 98, 97, 115, 101,
-            192, 0, 255, 240, 110, 59, 254, 192, 255, 90, 162,
-        ];
+    /// Answers a replica's PSYNC and starts streaming to it. The reply and the registration
+    /// happen in the same step, so the replica can't miss anything processed in between.
+    fn sync_replica(
+        &mut self,
+        address: SocketAddr,
+        listening_port: u16,
+        replid: Option<Vec<u8>>,
+        offset: Option<u64>,
+        values_sender: mpsc::UnboundedSender<Vec<Value>>,
+    ) {
+        if self.options.replica_of.is_some() && !self.replication.master_link_up() {
+            let error =
+                Value::simple_error("NOMASTERLINK Can't SYNC while not connected with my master");
+            let _ = values_sender.send(vec![error]);
+            return;
+        }
+
+        let response = self.psync(replid, offset);
+
+        if values_sender.send(response).is_err() {
+            println!("failed to send PSYNC response to replica; ignoring");
+        } else {
+            self.replication.add(address, listening_port, values_sender);
+        }
+    }
+
+    fn psync(&self, replid: Option<Vec<u8>>, offset: Option<u64>) -> Vec<Value> {
+        let master_replid = self.replication.replid();
+
+        if let (Some(replid), Some(offset)) = (replid, offset) {
+            if let Some(backlog) = self.replication.continue_from(&replid, offset) {
+                println!("partial resync from offset {}", offset);
+                let reply = format!("CONTINUE {}", master_replid);
+                return vec![Value::simple_string(&reply), Value::raw(backlog)];
+            }
+        }
+
+        let rdb = rdb::write(&self.database);
+        let reply = format!("FULLRESYNC {} {}", master_replid, self.replication.offset());
 
         vec![
-            Value::simple_string("FULLRESYNC 8371b4fb1155b71f4a04d3e1bc3e18c4a990aeeb 0"),
+            Value::simple_string(&reply),
             Value::BulkBytes((rdb.len(), rdb)),
         ]
     }
 
-    fn wait(&self) -> Vec<Value> {
-        let connected = self.replication.connected_replicas();
-        vec![Value::integer(connected)]
+    /// Blocks the client until `replicas` replicas acknowledge every write propagated so far,
+    /// or `timeout` milliseconds pass (0 waits forever).
+    fn wait(
+        &mut self,
+        replicas: u32,
+        timeout: u64,
+        reply_to: &mpsc::UnboundedSender<Vec<Value>>,
+    ) -> Vec<Value> {
+        if self.options.replica_of.is_some() {
+            return vec![Value::simple_error(
+                "ERR WAIT cannot be used with replica instances.",
+            )];
+        }
+
+        let offset = self.replication.offset();
+        let acked = self.replication.acked_replicas(offset);
+
+        if acked >= replicas as usize {
+            return vec![Value::integer(acked as i64)];
+        }
+
+        let deadline = (timeout > 0).then(|| Instant::now() + Duration::from_millis(timeout));
+        self.replication
+            .block(reply_to.clone(), replicas as usize, offset, deadline);
+        self.replication.request_acks();
+
+        vec![]
+    }
+
+    /// The earliest time a WAIT or a blocking pop times out.
+    fn next_deadline(&self) -> Option<Instant> {
+        let waiters = self.replication.next_deadline();
+        let blocked = self.next_unblock_deadline();
+        waiters.into_iter().chain(blocked).min()
+    }
+
+    fn wake_blocked_clients(&mut self, now: Instant) {
+        self.replication.wake_waiters(now);
+        self.unblock_timed_out_clients(now);
+    }
+
+    fn save(&mut self) -> Vec<Value> {
+        if self.background_save_in_progress {
+            return vec![Value::simple_error(
+                "ERR Background save already in progress",
+            )];
+        }
+
+        match rdb::save(&self.database, &self.options.rdb_path()) {
+            Ok(()) => {
+                self.last_save = SystemTime::now();
+                vec![Value::ok()]
+            }
+            Err(err) => {
+                println!("failed to save RDB snapshot: {}", err);
+                vec![Value::simple_error("ERR failed to save RDB snapshot")]
+            }
+        }
+    }
+
+    fn bgsave(&mut self) -> Vec<Value> {
+        if self.background_save_in_progress {
+            return vec![Value::simple_error(
+                "ERR Background save already in progress",
+            )];
+        }
+
+        // copies the key table only: values stay shared until a later command changes them
+        let snapshot = self.database.clone();
+        let path = self.options.rdb_path();
+        let sender = self.handle.sender.clone();
+        self.background_save_in_progress = true;
+
+        tokio::task::spawn_blocking(move || {
+            let result = rdb::save(&snapshot, &path);
+            let _ = sender.send(ServerMessage::BackgroundSaveCompleted { result });
+        });
+
+        vec![Value::simple_string("Background saving started")]
+    }
+
+    fn background_save_completed(&mut self, result: io::Result<()>) {
+        self.background_save_in_progress = false;
+
+        match result {
+            Ok(()) => {
+                println!("background save completed");
+                self.last_save = SystemTime::now();
+            }
+            Err(err) => println!("background save failed: {}", err),
+        }
+    }
+
+    fn lastsave(&self) -> Vec<Value> {
+        let seconds = self
+            .last_save
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        vec![Value::integer(seconds as i64)]
+    }
+
+    fn bgrewriteaof(&mut self) -> Vec<Value> {
+        match &self.aof {
+            None => vec![Value::simple_error("ERR Append only file is disabled")],
+            Some(aof) if aof.rewrite_in_progress() => vec![Value::simple_error(
+                "ERR Background append only file rewriting already in progress",
+            )],
+            Some(_) => {
+                self.start_aof_rewrite();
+                vec![Value::simple_string(
+                    "Background append only file rewriting started",
+                )]
+            }
+        }
+    }
+
+    /// Rewrites the AOF from a snapshot of the dataset in a background task, while new write
+    /// commands keep being appended to the current file and buffered for the new one.
+    fn start_aof_rewrite(&mut self) {
+        let Some(aof) = &mut self.aof else {
+            return;
+        };
+
+        aof.start_rewrite();
+
+        // copies the key table only: values stay shared until a later command changes them
+        let snapshot = self.database.clone();
+        let aof_path = self.options.aof_path();
+        let temp_path =
+            aof_path.with_file_name(format!("temp-rewriteaof-{}.aof", std::process::id()));
+        let sender = self.handle.sender.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let result = aof::rewrite(&snapshot, &temp_path);
+            let _ = sender.send(ServerMessage::AofRewriteCompleted { temp_path, result });
+        });
+    }
+
+    fn aof_rewrite_completed(&mut self, temp_path: PathBuf, result: io::Result<()>) {
+        let Some(aof) = &mut self.aof else {
+            return;
+        };
+
+        let result = result.and_then(|_| aof.finish_rewrite(&temp_path));
+
+        match result {
+            Ok(()) => println!("background AOF rewrite completed"),
+            Err(err) => {
+                println!("background AOF rewrite failed: {}", err);
+                aof.abort_rewrite();
+                let _ = std::fs::remove_file(&temp_path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::XreadId;
+
+    #[test]
+    fn full_resync_replaces_the_dataset() {
+        let mut server = replica();
+        server.database.set(key("stale"), key("1"), None);
+
+        let deadline = SystemTime::now() + Duration::from_secs(60);
+        let mut database = Database::new();
+        database.set(key("a"), key("2"), Some(deadline));
+
+        let replid = random::hex_string(40);
+        let sync = Resync::Full {
+            replid: replid.clone(),
+            offset: 42,
+            database,
+        };
+
+        let (master, _master_receiver) = mpsc::unbounded_channel();
+        server.master_link_up(sync, master);
+
+        assert_eq!(server.database.type_name(&key("stale")), None);
+        assert_eq!(server.database.get(&key("a")).unwrap(), Some(key("2")));
+        assert_eq!(server.database.expires_at(&key("a")), Some(Some(deadline)));
+        assert_eq!(server.replication.replid(), replid);
+        assert_eq!(server.replication.offset(), 42);
+    }
+
+    #[test]
+    fn partial_resync_keeps_the_dataset() {
+        let mut server = replica();
+        server.database.set(key("a"), key("1"), None);
+
+        let replid = server.replication.replid().to_string();
+        let sync = Resync::Partial {
+            replid: replid.clone(),
+        };
+
+        let (master, _master_receiver) = mpsc::unbounded_channel();
+        server.master_link_up(sync, master);
+
+        assert_eq!(server.database.get(&key("a")).unwrap(), Some(key("1")));
+        assert_eq!(server.replication.replid(), replid);
+        assert!(server.replication.master_link_up());
+    }
+
+    #[test]
+    fn read_only_replica_rejects_client_writes() {
+        let mut server = replica();
+        let (reply_to, _receiver) = mpsc::unbounded_channel();
+
+        let reply = server.handle(set("a", "1"), &reply_to);
+        assert_eq!(
+            reply,
+            vec![Value::simple_error(
+                "READONLY You can't write against a read only replica."
+            )]
+        );
+
+        let reply = server.handle(get("a"), &reply_to);
+        assert_eq!(reply, vec![Value::NullBulkString]);
+
+        let bytes = set("a", "1").to_value().to_bytes();
+        server.handle_from_master(set("a", "1"), &bytes, &reply_to);
+
+        let reply = server.handle(get("a"), &reply_to);
+        assert_eq!(reply, vec![Value::bulk_string("1")]);
+        assert_eq!(server.replication.offset(), bytes.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn messages_from_a_previous_master_link_are_dropped() {
+        let mut server = replica();
+        let generation = server.replication.link_generation();
+        let link_down = ServerMessage::MasterLinkDown { generation };
+        assert!(server.is_current(&link_down));
+
+        server.replicaof(None);
+        assert!(!server.is_current(&link_down));
+        assert!(!server.is_current(&ServerMessage::ForwardFromMaster {
+            generation,
+            bytes: vec![],
+        }));
+
+        server.replicaof(Some(("127.0.0.1".to_string(), 6381)));
+        let generation = server.replication.link_generation();
+        assert!(server.is_current(&ServerMessage::MasterLinkDown { generation }));
+        assert!(!server.is_current(&link_down));
+
+        let ack = ServerMessage::ReplicaAck {
+            address: "127.0.0.1:6382".parse().unwrap(),
+            offset: 0,
+        };
+        assert!(server.is_current(&ack));
+    }
+
+    #[test]
+    fn srandmember_rejects_huge_negative_counts() {
+        let mut server = replica();
+        let (reply_to, _receiver) = mpsc::unbounded_channel();
+        server
+            .database
+            .members_or_insert(&key("s"))
+            .unwrap()
+            .insert(key("a"));
+
+        let srandmember = |count| Command::Srandmember {
+            size: 0,
+            key: key("s"),
+            count: Some(count),
+        };
+
+        let reply = server.handle(srandmember(i64::MIN), &reply_to);
+        assert_eq!(
+            reply,
+            vec![Value::simple_error("ERR value is out of range")]
+        );
+
+        let reply = server.handle(srandmember(-3), &reply_to);
+        let member = || Value::bulk_string("a");
+        assert_eq!(
+            reply,
+            vec![Value::array(vec![member(), member(), member()])]
+        );
+    }
+
+    #[tokio::test]
+    async fn rewrite_and_replay_empty_stream_with_group() {
+        let mut server = master();
+        let (reply_to, _receiver) = mpsc::unbounded_channel();
+
+        let xgroup_create = Command::XgroupCreate {
+            size: 0,
+            key: key("s"),
+            group: key("g"),
+            id: XreadId::Last,
+            mkstream: true,
+            entries_read: None,
+        };
+        assert_eq!(server.handle(xgroup_create, &reply_to), vec![Value::ok()]);
+
+        let path = std::env::temp_dir().join(format!("aof-empty-{}.aof", std::process::id()));
+        aof::rewrite(&server.database, &path).unwrap();
+
+        let mut replayed = master();
+        aof::replay(&path, |command| {
+            let reply = replayed.execute(command, &reply_to);
+            assert!(!matches!(reply.as_slice(), [Value::SimpleError(_)]));
+        })
+        .await
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let stream = replayed.database.stream(&key("s")).unwrap().unwrap();
+        assert_eq!(stream.len(), 0);
+        assert_eq!(
+            stream
+                .groups()
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>(),
+            vec![key("g")]
+        );
+    }
+
+    fn master() -> Server {
+        let mut server = replica();
+        server.options.replica_of = None;
+        server
+    }
+
+    /// A server following a master that isn't there, which is enough to run commands against.
+    fn replica() -> Server {
+        let (sender, _) = mpsc::unbounded_channel();
+
+        let options = ServerOptions {
+            port: 6380,
+            replica_of: Some("127.0.0.1:6379".to_string()),
+            dir: std::env::temp_dir().display().to_string(),
+            dbfilename: "dump.rdb".to_string(),
+            appendonly: false,
+            appendfilename: "appendonly.aof".to_string(),
+            appendfsync: FsyncPolicy::EverySec,
+            auto_aof_rewrite_percentage: 100,
+            auto_aof_rewrite_min_size: 64 * 1024 * 1024,
+            repl_backlog_size: 1024 * 1024,
+            replica_read_only: true,
+            min_replicas_to_write: 0,
+            min_replicas_max_lag: Duration::from_secs(10),
+        };
+
+        Server::new(options, ServerHandle { sender })
+    }
+
+    fn set(key: &str, value: &str) -> Command {
+        Command::Set {
+            size: 0,
+            key: key.as_bytes().to_vec(),
+            value: value.as_bytes().to_vec(),
+            condition: None,
+            expiry: None,
+            get: false,
+        }
+    }
+
+    fn get(key: &str) -> Command {
+        Command::Get {
+            size: 0,
+            key: key.as_bytes().to_vec(),
+        }
+    }
+
+    fn key(key: &str) -> Vec<u8> {
+        key.as_bytes().to_vec()
     }
 }
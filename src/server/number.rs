@@ -0,0 +1,65 @@
+//! Numbers stored as strings, as used by the commands that increment values in place.
+
+/// Parses a stored value as a 64-bit signed integer. Like Redis, this rejects anything but
+/// plain decimal digits with an optional minus sign.
+pub fn parse_integer(bytes: &[u8]) -> Option<i64> {
+    if bytes.first() == Some(&b'+') {
+        return None;
+    }
+
+    std::str::from_utf8(bytes).ok()?.parse().ok()
+}
+
+/// Parses a stored value as a finite floating point number.
+pub fn parse_float(bytes: &[u8]) -> Option<f64> {
+    let value: f64 = std::str::from_utf8(bytes).ok()?.trim_start().parse().ok()?;
+    value.is_finite().then_some(value)
+}
+
+/// Formats a floating point number laid out like C's `%.17g`, as Redis replies with them:
+/// exponent notation such as `1e+300` below 1e-4 and from 1e17 on, plain digits otherwise. The
+/// digits are the fewest that parse back to the same value, which is never more than 17.
+pub fn format_float(value: f64) -> String {
+    let scientific = format!("{:e}", value);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+
+    if (-4..17).contains(&exponent) {
+        return value.to_string();
+    }
+
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers() {
+        assert_eq!(parse_integer("-42".as_bytes()), Some(-42));
+        assert_eq!(parse_integer("+42".as_bytes()), None);
+        assert_eq!(parse_integer("4.2".as_bytes()), None);
+        assert_eq!(parse_integer("99999999999999999999".as_bytes()), None);
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(parse_float("1.5".as_bytes()), Some(1.5));
+        assert_eq!(parse_float("3".as_bytes()), Some(3.0));
+        assert_eq!(parse_float("inf".as_bytes()), None);
+        assert_eq!(parse_float("abc".as_bytes()), None);
+
+        assert_eq!(format_float(3.0), "3");
+        assert_eq!(format_float(10.5 + 0.1), "10.6");
+        assert_eq!(format_float(-0.25), "-0.25");
+        assert_eq!(format_float(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(format_float(0.0001), "0.0001");
+        assert_eq!(format_float(1e16), "10000000000000000");
+        assert_eq!(format_float(1e17), "1e+17");
+        assert_eq!(format_float(1e300), "1e+300");
+        assert_eq!(format_float(-1.5e-7), "-1.5e-07");
+        assert_eq!(format_float(1.2345e-300), "1.2345e-300");
+    }
+}
@@ -0,0 +1,85 @@
+/// Whether `text` matches the glob-style `pattern` used by MATCH options: `*` matches any
+/// sequence, `?` any single byte, `[...]` a set or range of bytes (`[^...]` negates it) and `\`
+/// escapes the next byte.
+pub fn matches(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(b'*') => {
+            let rest = &pattern[1..];
+            (0..=text.len()).any(|skip| matches(rest, &text[skip..]))
+        }
+        Some(b'?') => !text.is_empty() && matches(&pattern[1..], &text[1..]),
+        Some(b'[') => match text.first() {
+            Some(byte) => {
+                let (matched, rest) = match_class(&pattern[1..], *byte);
+                matched && matches(rest, &text[1..])
+            }
+            None => false,
+        },
+        Some(b'\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && matches(&pattern[2..], &text[1..])
+        }
+        Some(byte) => text.first() == Some(byte) && matches(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Matches `byte` against the body of a `[...]` class, returning whether it matched and the
+/// pattern after the closing bracket.
+fn match_class(pattern: &[u8], byte: u8) -> (bool, &[u8]) {
+    let negated = pattern.first() == Some(&b'^');
+    let mut pos = usize::from(negated);
+    let mut matched = false;
+
+    while let Some(&current) = pattern.get(pos) {
+        match current {
+            b']' => break,
+            b'\\' if pos + 1 < pattern.len() => {
+                pos += 1;
+                matched |= pattern[pos] == byte;
+            }
+            _ if pattern.get(pos + 1) == Some(&b'-') && pos + 2 < pattern.len() => {
+                let (start, end) = (current, pattern[pos + 2]);
+                let (start, end) = (start.min(end), start.max(end));
+                matched |= (start..=end).contains(&byte);
+                pos += 2;
+            }
+            _ => matched |= current == byte,
+        }
+
+        pos += 1;
+    }
+
+    let rest = pattern.get(pos + 1..).unwrap_or_default();
+    (matched != negated, rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards() {
+        assert!(matches(b"*", b""));
+        assert!(matches(b"user:*", b"user:42"));
+        assert!(matches(b"*:name", b"user:name"));
+        assert!(!matches(b"user:*", b"session:1"));
+        assert!(matches(b"h?llo", b"hello"));
+        assert!(!matches(b"h?llo", b"hllo"));
+    }
+
+    #[test]
+    fn classes() {
+        assert!(matches(b"h[ae]llo", b"hallo"));
+        assert!(!matches(b"h[ae]llo", b"hillo"));
+        assert!(matches(b"h[^e]llo", b"hallo"));
+        assert!(!matches(b"h[^e]llo", b"hello"));
+        assert!(matches(b"h[a-c]llo", b"hbllo"));
+        assert!(matches(b"h[c-a]llo", b"hbllo"));
+    }
+
+    #[test]
+    fn escapes() {
+        assert!(matches(b"a\\*b", b"a*b"));
+        assert!(!matches(b"a\\*b", b"axb"));
+    }
+}
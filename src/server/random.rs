@@ -0,0 +1,44 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// Returns a random 64-bit number.
+///
+/// Every [`RandomState`] is keyed differently from the previous one on the same thread, starting
+/// from a seed taken from the OS, so hashing nothing with a fresh one is a cheap source of
+/// randomness. It's not meant for cryptographic use.
+pub fn next_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// Returns `length` random lowercase hex digits, as used for replication IDs.
+pub fn hex_string(length: usize) -> String {
+    let mut hex = String::with_capacity(length + 16);
+
+    while hex.len() < length {
+        hex.push_str(&format!("{:016x}", next_u64()));
+    }
+
+    hex.truncate(length);
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_string_length() {
+        let hex = hex_string(40);
+        assert_eq!(hex.len(), 40);
+        assert!(hex
+            .chars()
+            .all(|c| c.is_ascii_hexdigit() && !c.is_uppercase()));
+    }
+
+    #[test]
+    fn hex_strings_differ() {
+        assert_ne!(hex_string(40), hex_string(40));
+    }
+}
@@ -0,0 +1,51 @@
+/// Reflected form of the Jones polynomial used by Redis for RDB checksums.
+const POLY: u64 = 0x95ac_9329_ac4b_c9b5;
+
+const TABLE: [u64; 256] = build_table();
+
+const fn build_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u64;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ POLY
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
+/// Updates a running CRC-64 checksum with `data`. Start with `0`.
+pub fn crc64(crc: u64, data: &[u8]) -> u64 {
+    data.iter().fold(crc, |crc, byte| {
+        TABLE[((crc ^ *byte as u64) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_value() {
+        assert_eq!(crc64(0, "123456789".as_bytes()), 0xe9c6_d914_c4b8_d9ca);
+    }
+
+    #[test]
+    fn incremental() {
+        let crc = crc64(0, "12345".as_bytes());
+        assert_eq!(crc64(crc, "6789".as_bytes()), 0xe9c6_d914_c4b8_d9ca);
+    }
+}
@@ -0,0 +1,44 @@
+use super::listpack::read_int_le;
+
+/// Decodes every element of an intset, the sorted array of integers Redis uses for small sets
+/// of integers. Elements are returned in their decimal string form.
+///
+/// Returns `None` if the data is malformed.
+pub fn entries(data: &[u8]) -> Option<Vec<Vec<u8>>> {
+    // 4 bytes of element size, then 4 bytes of element count
+    let size = u32::from_le_bytes(data.get(0..4)?.try_into().ok()?) as usize;
+    let count = u32::from_le_bytes(data.get(4..8)?.try_into().ok()?) as usize;
+
+    if !matches!(size, 2 | 4 | 8) {
+        return None;
+    }
+
+    let elements = data.get(8..8 + size.checked_mul(count)?)?;
+
+    let entries = elements
+        .chunks(size)
+        .map(|bytes| read_int_le(bytes).to_string().into_bytes())
+        .collect();
+
+    Some(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn int16_elements() {
+        let data = [2, 0, 0, 0, 3, 0, 0, 0, 0xfb, 0xff, 7, 0, 0xe8, 0x03];
+
+        let entries = entries(&data).unwrap();
+        let entries: Vec<_> = entries.iter().map(|e| String::from_utf8_lossy(e)).collect();
+        assert_eq!(entries, vec!["-5", "7", "1000"]);
+    }
+
+    #[test]
+    fn truncated() {
+        let data = [4, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0];
+        assert_eq!(entries(&data), None);
+    }
+}
@@ -0,0 +1,244 @@
+/// Decodes every element of a listpack, the compact encoding Redis 7 uses for small
+/// aggregates. Integers are returned in their decimal string form.
+///
+/// Returns `None` if the data is malformed.
+pub fn entries(data: &[u8]) -> Option<Vec<Vec<u8>>> {
+    // 4 bytes of total size and 2 bytes of element count
+    let count = u16::from_le_bytes([*data.get(4)?, *data.get(5)?]) as usize;
+    let mut entries = Vec::with_capacity(count);
+    let mut pos = 6;
+
+    loop {
+        let encoding = *data.get(pos)?;
+
+        let (entry, length) = match encoding {
+            0xff => return Some(entries),
+            0x00..=0x7f => (int_entry(encoding as i64), 1),
+            0x80..=0xbf => {
+                let length = (encoding & 0x3f) as usize;
+                (data.get(pos + 1..pos + 1 + length)?.to_vec(), 1 + length)
+            }
+            0xc0..=0xdf => {
+                let value = ((encoding as i64 & 0x1f) << 8) | *data.get(pos + 1)? as i64;
+                (int_entry(sign_extend(value, 13)), 2)
+            }
+            0xe0..=0xef => {
+                let length = ((encoding as usize & 0x0f) << 8) | *data.get(pos + 1)? as usize;
+                (data.get(pos + 2..pos + 2 + length)?.to_vec(), 2 + length)
+            }
+            0xf0 => {
+                let length = u32::from_le_bytes(data.get(pos + 1..pos + 5)?.try_into().ok()?);
+                let length = length as usize;
+                (data.get(pos + 5..pos + 5 + length)?.to_vec(), 5 + length)
+            }
+            0xf1..=0xf4 => {
+                let size = match encoding {
+                    0xf1 => 2,
+                    0xf2 => 3,
+                    0xf3 => 4,
+                    _ => 8,
+                };
+                let value = read_int_le(data.get(pos + 1..pos + 1 + size)?);
+                (int_entry(value), 1 + size)
+            }
+            _ => return None,
+        };
+
+        entries.push(entry);
+        pos += length + backlen_size(length);
+    }
+}
+
+/// Encodes elements into a listpack. Like Redis, elements that are integers in canonical
+/// decimal form are stored in binary.
+pub fn encode(elements: &[Vec<u8>]) -> Vec<u8> {
+    // the total size and element count are filled in at the end
+    let mut data = vec![0; 6];
+
+    for element in elements {
+        let start = data.len();
+
+        match canonical_integer(element) {
+            Some(value) => encode_integer(&mut data, value),
+            None => encode_string(&mut data, element),
+        }
+
+        let length = data.len() - start;
+        encode_backlen(&mut data, length);
+    }
+
+    data.push(0xff);
+
+    let total = data.len() as u32;
+    let count = u16::try_from(elements.len()).unwrap_or(u16::MAX);
+    data[0..4].copy_from_slice(&total.to_le_bytes());
+    data[4..6].copy_from_slice(&count.to_le_bytes());
+    data
+}
+
+fn canonical_integer(bytes: &[u8]) -> Option<i64> {
+    let text = std::str::from_utf8(bytes).ok()?;
+    let value: i64 = text.parse().ok()?;
+    (value.to_string() == text).then_some(value)
+}
+
+fn encode_integer(data: &mut Vec<u8>, value: i64) {
+    let bytes = value.to_le_bytes();
+
+    match value {
+        0..=127 => data.push(value as u8),
+        -4096..=4095 => {
+            let value = value as u16 & 0x1fff;
+            data.extend([0xc0 | (value >> 8) as u8, value as u8]);
+        }
+        -32768..=32767 => {
+            data.push(0xf1);
+            data.extend(&bytes[..2]);
+        }
+        -8388608..=8388607 => {
+            data.push(0xf2);
+            data.extend(&bytes[..3]);
+        }
+        -2147483648..=2147483647 => {
+            data.push(0xf3);
+            data.extend(&bytes[..4]);
+        }
+        _ => {
+            data.push(0xf4);
+            data.extend(bytes);
+        }
+    }
+}
+
+fn encode_string(data: &mut Vec<u8>, bytes: &[u8]) {
+    let length = bytes.len();
+
+    if length < 64 {
+        data.push(0x80 | length as u8);
+    } else if length < 4096 {
+        data.extend([0xe0 | (length >> 8) as u8, length as u8]);
+    } else {
+        data.push(0xf0);
+        data.extend((length as u32).to_le_bytes());
+    }
+
+    data.extend(bytes);
+}
+
+/// Writes an entry's length in the variable size form read by [`backlen_size`], most
+/// significant 7 bits first, with the high bit set on all but the first byte.
+fn encode_backlen(data: &mut Vec<u8>, length: usize) {
+    let size = backlen_size(length);
+
+    for index in (0..size).rev() {
+        let bits = ((length >> (7 * index)) & 0x7f) as u8;
+        data.push(if index == size - 1 { bits } else { bits | 0x80 });
+    }
+}
+
+/// Size of the field after each entry that stores the entry's length, used to walk the
+/// listpack backwards.
+fn backlen_size(length: usize) -> usize {
+    match length {
+        0..=127 => 1,
+        128..=16382 => 2,
+        16383..=2097150 => 3,
+        2097151..=268435454 => 4,
+        _ => 5,
+    }
+}
+
+/// Reads a little-endian two's complement integer of up to 8 bytes.
+pub(super) fn read_int_le(bytes: &[u8]) -> i64 {
+    let mut value = 0u64;
+
+    for (index, byte) in bytes.iter().enumerate() {
+        value |= (*byte as u64) << (8 * index);
+    }
+
+    sign_extend(value as i64, bytes.len() as u32 * 8)
+}
+
+fn sign_extend(value: i64, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    (value << shift) >> shift
+}
+
+fn int_entry(value: i64) -> Vec<u8> {
+    value.to_string().into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_and_integers() {
+        let mut data = vec![0, 0, 0, 0, 5, 0];
+        // "foo"
+        data.extend([0x83, b'f', b'o', b'o', 4]);
+        // 7-bit uint 100
+        data.extend([100, 1]);
+        // 13-bit int -1000
+        data.extend([0xdc, 0x18, 2]);
+        // int16 1000
+        data.extend([0xf1, 0xe8, 0x03, 3]);
+        // int24 -100000
+        data.extend([0xf2, 0x60, 0x79, 0xfe, 4]);
+        data.push(0xff);
+
+        let entries = entries(&data).unwrap();
+        let entries: Vec<_> = entries.iter().map(|e| String::from_utf8_lossy(e)).collect();
+        assert_eq!(entries, vec!["foo", "100", "-1000", "1000", "-100000"]);
+    }
+
+    #[test]
+    fn long_string() {
+        let mut data = vec![0, 0, 0, 0, 1, 0];
+        data.extend([0xe0, 200]);
+        data.extend([b'x'; 200]);
+        data.extend([0x81, 0x01]);
+        data.push(0xff);
+
+        assert_eq!(entries(&data), Some(vec![vec![b'x'; 200]]));
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        let elements: Vec<Vec<u8>> = [
+            "foo",
+            "0",
+            "127",
+            "-1",
+            "4095",
+            "-4096",
+            "30000",
+            "-8000000",
+            "2000000000",
+            "-9000000000000",
+            "007",
+            "+5",
+            "",
+        ]
+        .iter()
+        .map(|element| element.as_bytes().to_vec())
+        .chain([vec![b'x'; 200], vec![b'y'; 5000]])
+        .collect();
+
+        let data = encode(&elements);
+        assert_eq!(
+            data.len(),
+            u32::from_le_bytes(data[..4].try_into().unwrap()) as usize
+        );
+        assert_eq!(entries(&data), Some(elements));
+
+        // small integers take a single byte plus the backlen
+        assert_eq!(encode(&[b"5".to_vec()]), [9, 0, 0, 0, 1, 0, 5, 1, 0xff]);
+    }
+
+    #[test]
+    fn truncated() {
+        let data = [0, 0, 0, 0, 1, 0, 0x83, b'f'];
+        assert_eq!(entries(&data), None);
+    }
+}
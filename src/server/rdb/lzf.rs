@@ -0,0 +1,78 @@
+/// Decompresses an LZF block into exactly `length` bytes.
+///
+/// Returns `None` if the input is malformed or doesn't expand to `length` bytes.
+pub fn decompress(input: &[u8], length: usize) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(length);
+    let mut pos = 0;
+
+    while pos < input.len() {
+        let ctrl = input[pos] as usize;
+        pos += 1;
+
+        if ctrl < 32 {
+            // literal run of ctrl + 1 bytes
+            let run = input.get(pos..pos + ctrl + 1)?;
+            output.extend_from_slice(run);
+            pos += ctrl + 1;
+        } else {
+            // back reference into the already decompressed output
+            let mut len = ctrl >> 5;
+
+            if len == 7 {
+                len += *input.get(pos)? as usize;
+                pos += 1;
+            }
+
+            let distance = ((ctrl & 0x1f) << 8) + *input.get(pos)? as usize + 1;
+            pos += 1;
+
+            let start = output.len().checked_sub(distance)?;
+
+            for i in 0..len + 2 {
+                output.push(output[start + i]);
+            }
+        }
+
+        if output.len() > length {
+            return None;
+        }
+    }
+
+    if output.len() == length {
+        Some(output)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_only() {
+        let input = [2, b'a', b'b', b'c'];
+        assert_eq!(decompress(&input, 3), Some("abc".as_bytes().to_vec()));
+    }
+
+    #[test]
+    fn back_reference() {
+        let input = [0, b'a', 0xe0, 0, 0];
+        assert_eq!(
+            decompress(&input, 10),
+            Some("aaaaaaaaaa".as_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn wrong_length() {
+        let input = [2, b'a', b'b', b'c'];
+        assert_eq!(decompress(&input, 4), None);
+    }
+
+    #[test]
+    fn invalid_reference() {
+        let input = [0xe0, 0, 0];
+        assert_eq!(decompress(&input, 9), None);
+    }
+}
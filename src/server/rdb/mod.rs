@@ -0,0 +1,98 @@
+mod crc64;
+mod intset;
+mod listpack;
+mod lzf;
+mod reader;
+mod writer;
+mod ziplist;
+
+use crate::server::database::Database;
+use std::{fs, io, io::Write, path::Path};
+
+pub use reader::read;
+pub use writer::write;
+
+const OPCODE_FUNCTION2: u8 = 0xf5;
+const OPCODE_MODULE_AUX: u8 = 0xf7;
+const OPCODE_IDLE: u8 = 0xf8;
+const OPCODE_FREQ: u8 = 0xf9;
+const OPCODE_AUX: u8 = 0xfa;
+const OPCODE_RESIZEDB: u8 = 0xfb;
+const OPCODE_EXPIRETIME_MS: u8 = 0xfc;
+const OPCODE_EXPIRETIME: u8 = 0xfd;
+const OPCODE_SELECTDB: u8 = 0xfe;
+const OPCODE_EOF: u8 = 0xff;
+
+const TYPE_STRING: u8 = 0;
+const TYPE_LIST: u8 = 1;
+const TYPE_SET: u8 = 2;
+const TYPE_ZSET: u8 = 3;
+const TYPE_HASH: u8 = 4;
+const TYPE_ZSET_2: u8 = 5;
+const TYPE_LIST_ZIPLIST: u8 = 10;
+const TYPE_SET_INTSET: u8 = 11;
+const TYPE_ZSET_ZIPLIST: u8 = 12;
+const TYPE_HASH_ZIPLIST: u8 = 13;
+const TYPE_LIST_QUICKLIST: u8 = 14;
+const TYPE_STREAM_LISTPACKS: u8 = 15;
+const TYPE_HASH_LISTPACK: u8 = 16;
+const TYPE_ZSET_LISTPACK: u8 = 17;
+const TYPE_LIST_QUICKLIST_2: u8 = 18;
+const TYPE_STREAM_LISTPACKS_2: u8 = 19;
+const TYPE_SET_LISTPACK: u8 = 20;
+const TYPE_STREAM_LISTPACKS_3: u8 = 21;
+
+const QUICKLIST_NODE_PLAIN: usize = 1;
+const QUICKLIST_NODE_PACKED: usize = 2;
+
+/// Flags of an entry in a stream listpack node.
+const STREAM_ITEM_FLAG_DELETED: u64 = 1;
+const STREAM_ITEM_FLAG_SAMEFIELDS: u64 = 2;
+
+const ENCODING_INT8: u8 = 0;
+const ENCODING_INT16: u8 = 1;
+const ENCODING_INT32: u8 = 2;
+const ENCODING_LZF: u8 = 3;
+
+/// A possible error loading an RDB snapshot.
+#[derive(Debug, thiserror::Error)]
+pub enum RdbError {
+    /// The data can't be interpreted as a valid RDB snapshot
+    #[error("invalid RDB data: {0}")]
+    Invalid(String),
+
+    /// An I/O error ocurred while reading the snapshot file
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Loads the RDB file at `path`, or returns an empty database if it doesn't exist.
+pub fn load(path: &Path) -> Result<Database, RdbError> {
+    match fs::read(path) {
+        Ok(data) => {
+            let database = read(&data)?;
+            println!("loaded RDB snapshot from {}", path.display());
+            Ok(database)
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            println!("no RDB snapshot at {}; starting empty", path.display());
+            Ok(Database::new())
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Writes a snapshot of `database` to `path`.
+///
+/// The data goes to a temporary file in the same directory first, which is then renamed over
+/// `path`, so a crash never leaves a partially written snapshot behind.
+pub fn save(database: &Database, path: &Path) -> io::Result<()> {
+    let data = write(database);
+    let temp_path = path.with_file_name(format!("temp-{}.rdb", std::process::id()));
+
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(&data)?;
+    file.sync_all()?;
+
+    fs::rename(&temp_path, path)
+}
@@ -0,0 +1,790 @@
+use super::*;
+use crate::{
+    protocol::StreamId,
+    server::{
+        consumer_group::{ConsumerGroup, PendingEntry},
+        database::{Data, Database},
+        sorted_set::SortedSet,
+        stream::{Fields, Stream},
+    },
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The newest RDB format version this reader understands.
+const MAX_VERSION: u32 = 12;
+
+/// Parses a complete RDB snapshot into a new [`Database`].
+///
+/// Only keys in database 0 are loaded; keys whose expiry is already in the past are skipped.
+pub fn read(data: &[u8]) -> Result<Database, RdbError> {
+    RdbReader { data, pos: 0 }.read_database()
+}
+
+enum Length {
+    Plain(usize),
+    Encoded(u8),
+}
+
+struct RdbReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> RdbReader<'a> {
+    fn read_database(&mut self) -> Result<Database, RdbError> {
+        let version = self.read_header()?;
+        let now = SystemTime::now();
+        let mut database = Database::new();
+        let mut db_index = 0;
+        let mut expires_at = None;
+
+        loop {
+            match self.read_u8()? {
+                OPCODE_AUX => {
+                    self.read_string()?;
+                    self.read_string()?;
+                }
+                OPCODE_SELECTDB => db_index = self.read_length()?,
+                OPCODE_RESIZEDB => {
+                    self.read_length()?;
+                    self.read_length()?;
+                }
+                OPCODE_EXPIRETIME => {
+                    let seconds = u32::from_le_bytes(self.read_array()?);
+                    expires_at = Some(UNIX_EPOCH + Duration::from_secs(seconds.into()));
+                }
+                OPCODE_EXPIRETIME_MS => {
+                    let millis = u64::from_le_bytes(self.read_array()?);
+                    expires_at = Some(UNIX_EPOCH + Duration::from_millis(millis));
+                }
+                OPCODE_FREQ => {
+                    self.read_u8()?;
+                }
+                OPCODE_IDLE => {
+                    self.read_length()?;
+                }
+                OPCODE_EOF => {
+                    self.verify_checksum(version)?;
+                    return Ok(database);
+                }
+                opcode @ (OPCODE_FUNCTION2 | OPCODE_MODULE_AUX) => {
+                    return Err(invalid(format!("unsupported opcode: {:#x}", opcode)));
+                }
+                value_type => {
+                    let key = self.read_string()?;
+                    let value = self.read_value(value_type)?;
+
+                    let expires_at = expires_at.take();
+
+                    if expires_at.is_some_and(|expires_at| expires_at <= now) {
+                        continue;
+                    }
+
+                    if db_index == 0 {
+                        database.insert(key, value, expires_at);
+                    }
+                }
+            }
+        }
+    }
+
+    fn read_header(&mut self) -> Result<u32, RdbError> {
+        let header = self.read_bytes(9)?;
+
+        if &header[..5] != "REDIS".as_bytes() {
+            return Err(invalid("missing REDIS signature".to_string()));
+        }
+
+        let version = std::str::from_utf8(&header[5..])
+            .ok()
+            .and_then(|version| version.parse().ok())
+            .ok_or_else(|| invalid("malformed version number".to_string()))?;
+
+        if version == 0 || version > MAX_VERSION {
+            return Err(invalid(format!("unsupported version: {}", version)));
+        }
+
+        Ok(version)
+    }
+
+    fn verify_checksum(&mut self, version: u32) -> Result<(), RdbError> {
+        if version < 5 {
+            return Ok(());
+        }
+
+        let computed = crc64::crc64(0, &self.data[..self.pos]);
+        let expected = u64::from_le_bytes(self.read_array()?);
+
+        if expected != 0 && expected != computed {
+            return Err(invalid("checksum mismatch".to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn read_value(&mut self, value_type: u8) -> Result<Data, RdbError> {
+        match value_type {
+            TYPE_STRING => Ok(Data::String(self.read_string()?)),
+            TYPE_LIST => {
+                let length = self.read_length()?;
+                let list = (0..length)
+                    .map(|_| self.read_string())
+                    .collect::<Result<_, _>>()?;
+                Ok(Data::List(list))
+            }
+            TYPE_LIST_ZIPLIST => {
+                let ziplist = self.read_string()?;
+                Ok(Data::List(decode(&ziplist, ziplist::entries)?.into()))
+            }
+            TYPE_LIST_QUICKLIST | TYPE_LIST_QUICKLIST_2 => {
+                let mut list = VecDeque::new();
+
+                for _ in 0..self.read_length()? {
+                    let container = match value_type {
+                        TYPE_LIST_QUICKLIST_2 => self.read_length()?,
+                        _ => QUICKLIST_NODE_PACKED,
+                    };
+
+                    let node = self.read_string()?;
+
+                    match (container, value_type) {
+                        (QUICKLIST_NODE_PLAIN, _) => list.push_back(node),
+                        (QUICKLIST_NODE_PACKED, TYPE_LIST_QUICKLIST) => {
+                            list.extend(decode(&node, ziplist::entries)?)
+                        }
+                        (QUICKLIST_NODE_PACKED, _) => {
+                            list.extend(decode(&node, listpack::entries)?)
+                        }
+                        _ => {
+                            let message = format!("unknown quicklist container: {}", container);
+                            return Err(invalid(message));
+                        }
+                    }
+                }
+
+                Ok(Data::List(list))
+            }
+            TYPE_SET => {
+                let length = self.read_length()?;
+                let set = (0..length)
+                    .map(|_| self.read_string())
+                    .collect::<Result<_, _>>()?;
+                Ok(Data::Set(set))
+            }
+            TYPE_SET_INTSET | TYPE_SET_LISTPACK => {
+                let encoded = self.read_string()?;
+                let entries = match value_type {
+                    TYPE_SET_INTSET => decode(&encoded, intset::entries)?,
+                    _ => decode(&encoded, listpack::entries)?,
+                };
+
+                Ok(Data::Set(entries.into_iter().collect()))
+            }
+            TYPE_ZSET | TYPE_ZSET_2 => {
+                let mut set = SortedSet::new();
+
+                for _ in 0..self.read_length()? {
+                    let member = self.read_string()?;
+                    let score = match value_type {
+                        TYPE_ZSET_2 => f64::from_le_bytes(self.read_array()?),
+                        _ => self.read_text_score()?,
+                    };
+
+                    set.insert(member, score);
+                }
+
+                Ok(Data::SortedSet(set))
+            }
+            TYPE_ZSET_ZIPLIST | TYPE_ZSET_LISTPACK => {
+                let encoded = self.read_string()?;
+                let entries = match value_type {
+                    TYPE_ZSET_ZIPLIST => decode(&encoded, ziplist::entries)?,
+                    _ => decode(&encoded, listpack::entries)?,
+                };
+
+                let mut set = SortedSet::new();
+
+                for (member, score) in pairs(entries)? {
+                    set.insert(member, parse_score(&score)?);
+                }
+
+                Ok(Data::SortedSet(set))
+            }
+            TYPE_STREAM_LISTPACKS | TYPE_STREAM_LISTPACKS_2 | TYPE_STREAM_LISTPACKS_3 => {
+                Ok(Data::Stream(self.read_stream(value_type)?))
+            }
+            TYPE_HASH => {
+                let length = self.read_length()?;
+                let hash = (0..length)
+                    .map(|_| Ok((self.read_string()?, self.read_string()?)))
+                    .collect::<Result<_, RdbError>>()?;
+                Ok(Data::Hash(hash))
+            }
+            TYPE_HASH_ZIPLIST | TYPE_HASH_LISTPACK => {
+                let encoded = self.read_string()?;
+                let entries = match value_type {
+                    TYPE_HASH_ZIPLIST => decode(&encoded, ziplist::entries)?,
+                    _ => decode(&encoded, listpack::entries)?,
+                };
+
+                Ok(Data::Hash(pairs(entries)?.collect()))
+            }
+            _ => Err(invalid(format!("unsupported value type: {}", value_type))),
+        }
+    }
+
+    /// Reads a stream: listpack nodes of entries keyed by their first ID, then the stream's
+    /// last ID and counters, then its consumer groups.
+    fn read_stream(&mut self, value_type: u8) -> Result<Stream, RdbError> {
+        let mut stream = Stream::new();
+
+        for _ in 0..self.read_length()? {
+            let master_id = self.read_string()?;
+            let master_id = <[u8; 16]>::try_from(master_id.as_slice())
+                .map_err(|_| invalid("invalid stream node key".to_string()))?;
+            let encoded = self.read_string()?;
+
+            for (id, fields) in
+                stream_node(stream_id(master_id), decode(&encoded, listpack::entries)?)?
+            {
+                if id <= stream.last_id() {
+                    return Err(invalid("stream entries out of order".to_string()));
+                }
+
+                stream.add(id, fields);
+            }
+        }
+
+        self.read_length()?;
+        let last_id = self.read_stream_id()?;
+
+        let (max_deleted_id, entries_added) = if value_type == TYPE_STREAM_LISTPACKS {
+            (StreamId::MIN, stream.len() as u64)
+        } else {
+            // the first ID is recomputed from the entries
+            self.read_stream_id()?;
+            (self.read_stream_id()?, self.read_length()? as u64)
+        };
+
+        stream.set_id(last_id, entries_added, max_deleted_id);
+
+        for _ in 0..self.read_length()? {
+            let (name, group) = self.read_consumer_group(value_type)?;
+            stream.create_group(name, group);
+        }
+
+        Ok(stream)
+    }
+
+    /// Reads a consumer group: its name and last ID, then its pending entries with their
+    /// delivery times and counts, then its consumers with the IDs of the entries they own.
+    fn read_consumer_group(
+        &mut self,
+        value_type: u8,
+    ) -> Result<(Vec<u8>, ConsumerGroup), RdbError> {
+        let name = self.read_string()?;
+        let last_id = self.read_stream_id()?;
+
+        // an unknown count is stored as -1
+        let entries_read = match value_type {
+            TYPE_STREAM_LISTPACKS => None,
+            _ => Some(self.read_length()? as u64).filter(|read| *read != u64::MAX),
+        };
+
+        let mut group = ConsumerGroup::new(last_id, entries_read);
+        let mut deliveries = HashMap::new();
+
+        for _ in 0..self.read_length()? {
+            let id = stream_id(self.read_array()?);
+            let delivery_time = u64::from_le_bytes(self.read_array()?);
+            deliveries.insert(id, (delivery_time, self.read_length()? as u64));
+        }
+
+        for _ in 0..self.read_length()? {
+            let consumer = self.read_string()?;
+            let seen_time = u64::from_le_bytes(self.read_array()?);
+
+            // older versions don't have the active time, which Redis then takes as the seen time
+            let active_time = match value_type {
+                TYPE_STREAM_LISTPACKS_3 => i64::from_le_bytes(self.read_array()?),
+                _ => seen_time as i64,
+            };
+
+            group.create_consumer(&consumer, seen_time);
+            group.consumer_mut(&consumer).unwrap().active_time = u64::try_from(active_time).ok();
+
+            for _ in 0..self.read_length()? {
+                let id = stream_id(self.read_array()?);
+                let (delivery_time, delivery_count) = deliveries
+                    .remove(&id)
+                    .ok_or_else(|| invalid(format!("consumer owns unknown entry {}", id)))?;
+
+                let pending = PendingEntry {
+                    consumer: consumer.clone(),
+                    delivery_time,
+                    delivery_count,
+                };
+                group.set_pending(id, pending);
+            }
+        }
+
+        Ok((name, group))
+    }
+
+    /// Reads a stream ID stored as two lengths.
+    fn read_stream_id(&mut self) -> Result<StreamId, RdbError> {
+        Ok(StreamId {
+            ms: self.read_length()? as u64,
+            seq: self.read_length()? as u64,
+        })
+    }
+
+    /// Reads a score in the old text encoding: a length byte, with 253, 254 and 255 standing for
+    /// NaN, +inf and -inf.
+    fn read_text_score(&mut self) -> Result<f64, RdbError> {
+        match self.read_u8()? {
+            253 => Ok(f64::NAN),
+            254 => Ok(f64::INFINITY),
+            255 => Ok(f64::NEG_INFINITY),
+            length => parse_score(self.read_bytes(length as usize)?),
+        }
+    }
+
+    fn read_string(&mut self) -> Result<Vec<u8>, RdbError> {
+        match self.read_length_or_encoding()? {
+            Length::Plain(length) => Ok(self.read_bytes(length)?.to_vec()),
+            Length::Encoded(ENCODING_INT8) => {
+                let value = i8::from_le_bytes(self.read_array()?);
+                Ok(value.to_string().into_bytes())
+            }
+            Length::Encoded(ENCODING_INT16) => {
+                let value = i16::from_le_bytes(self.read_array()?);
+                Ok(value.to_string().into_bytes())
+            }
+            Length::Encoded(ENCODING_INT32) => {
+                let value = i32::from_le_bytes(self.read_array()?);
+                Ok(value.to_string().into_bytes())
+            }
+            Length::Encoded(ENCODING_LZF) => {
+                let compressed_length = self.read_length()?;
+                let length = self.read_length()?;
+                let compressed = self.read_bytes(compressed_length)?;
+
+                lzf::decompress(compressed, length)
+                    .ok_or_else(|| invalid("corrupt LZF compressed string".to_string()))
+            }
+            Length::Encoded(encoding) => {
+                Err(invalid(format!("unknown string encoding: {}", encoding)))
+            }
+        }
+    }
+
+    fn read_length(&mut self) -> Result<usize, RdbError> {
+        match self.read_length_or_encoding()? {
+            Length::Plain(length) => Ok(length),
+            Length::Encoded(_) => Err(invalid("expected a plain length".to_string())),
+        }
+    }
+
+    fn read_length_or_encoding(&mut self) -> Result<Length, RdbError> {
+        let first = self.read_u8()?;
+
+        let length = match first >> 6 {
+            0 => (first & 0x3f) as u64,
+            1 => (((first & 0x3f) as u64) << 8) | self.read_u8()? as u64,
+            2 if first == 0x80 => u32::from_be_bytes(self.read_array()?) as u64,
+            2 if first == 0x81 => u64::from_be_bytes(self.read_array()?),
+            3 => return Ok(Length::Encoded(first & 0x3f)),
+            _ => return Err(invalid(format!("invalid length prefix: {:#x}", first))),
+        };
+
+        let length = usize::try_from(length)
+            .map_err(|_| invalid(format!("length too large: {}", length)))?;
+
+        Ok(Length::Plain(length))
+    }
+
+    fn read_u8(&mut self) -> Result<u8, RdbError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], RdbError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.read_bytes(N)?);
+        Ok(array)
+    }
+
+    fn read_bytes(&mut self, length: usize) -> Result<&'a [u8], RdbError> {
+        let end = self
+            .pos
+            .checked_add(length)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| invalid("unexpected end of data".to_string()))?;
+
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+}
+
+/// Decodes the elements of a compact encoding such as a listpack.
+fn decode(
+    data: &[u8],
+    entries: fn(&[u8]) -> Option<Vec<Vec<u8>>>,
+) -> Result<Vec<Vec<u8>>, RdbError> {
+    entries(data).ok_or_else(|| invalid("corrupt compact encoding".to_string()))
+}
+
+fn parse_score(bytes: &[u8]) -> Result<f64, RdbError> {
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(|score| score.parse().ok())
+        .ok_or_else(|| invalid("malformed sorted set score".to_string()))
+}
+
+/// A stream ID stored as 16 big-endian bytes.
+fn stream_id(bytes: [u8; 16]) -> StreamId {
+    let (ms, seq) = bytes.split_at(8);
+
+    StreamId {
+        ms: u64::from_be_bytes(ms.try_into().unwrap()),
+        seq: u64::from_be_bytes(seq.try_into().unwrap()),
+    }
+}
+
+/// Decodes the entries of a stream listpack node. A node starts with a master entry holding
+/// the entry counts and a list of fields later entries may share, and entry IDs are stored as
+/// differences from the node's master ID.
+fn stream_node(
+    master_id: StreamId,
+    elements: Vec<Vec<u8>>,
+) -> Result<Vec<(StreamId, Fields)>, RdbError> {
+    let mut elements = elements.into_iter();
+    let elements = &mut elements;
+
+    let count = next_integer(elements)? + next_integer(elements)?;
+    let master_fields = (0..next_integer(elements)?)
+        .map(|_| next_element(elements))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // the master entry's terminator
+    next_element(elements)?;
+
+    let mut entries = vec![];
+
+    for _ in 0..count {
+        let flags = next_integer(elements)?;
+        let id = StreamId {
+            ms: master_id.ms.wrapping_add(next_integer(elements)?),
+            seq: master_id.seq.wrapping_add(next_integer(elements)?),
+        };
+
+        let fields = if flags & STREAM_ITEM_FLAG_SAMEFIELDS != 0 {
+            master_fields
+                .iter()
+                .map(|field| Ok((field.clone(), next_element(elements)?)))
+                .collect::<Result<Fields, RdbError>>()?
+        } else {
+            (0..next_integer(elements)?)
+                .map(|_| Ok((next_element(elements)?, next_element(elements)?)))
+                .collect::<Result<Fields, RdbError>>()?
+        };
+
+        // the entry's element count, for walking the node backwards
+        next_element(elements)?;
+
+        if flags & STREAM_ITEM_FLAG_DELETED == 0 {
+            entries.push((id, fields));
+        }
+    }
+
+    Ok(entries)
+}
+
+fn next_element(elements: &mut impl Iterator<Item = Vec<u8>>) -> Result<Vec<u8>, RdbError> {
+    elements
+        .next()
+        .ok_or_else(|| invalid("truncated stream node".to_string()))
+}
+
+/// Reads an integer element, where negative values wrap around like the deltas Redis stores.
+fn next_integer(elements: &mut impl Iterator<Item = Vec<u8>>) -> Result<u64, RdbError> {
+    std::str::from_utf8(&next_element(elements)?)
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .map(|value| value as u64)
+        .ok_or_else(|| invalid("malformed stream node integer".to_string()))
+}
+
+/// Groups the flat elements of a compact encoding into field-value pairs.
+fn pairs(entries: Vec<Vec<u8>>) -> Result<impl Iterator<Item = (Vec<u8>, Vec<u8>)>, RdbError> {
+    if entries.len() % 2 != 0 {
+        return Err(invalid("odd number of elements in a hash".to_string()));
+    }
+
+    let mut entries = entries.into_iter();
+    Ok(std::iter::from_fn(move || {
+        Some((entries.next()?, entries.next()?))
+    }))
+}
+
+fn invalid(message: String) -> RdbError {
+    RdbError::Invalid(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty snapshot as produced by redis-server 7.2.
+    const EMPTY_RDB: [u8; 88] = [
+        82, 69, 68, 73, 83, 48, 48, 49, 49, 250, 9, 114, 101, 100, 105, 115, 45, 118, 101, 114, 5,
+        55, 46, 50, 46, 48, 250, 10, 114, 101, 100, 105, 115, 45, 98, 105, 116, 115, 192, 64, 250,
+        5, 99, 116, 105, 109, 101, 194, 109, 8, 188, 101, 250, 8, 117, 115, 101, 100, 45, 109, 101,
+        109, 194, 176, 196, 16, 0, 250, 8, 97, 111, 102, 45, 98, 97, 115, 101, 192, 0, 255, 240,
+        110, 59, 254, 192, 255, 90, 162,
+    ];
+
+    #[test]
+    fn read_empty_redis_snapshot() {
+        let mut database = read(&EMPTY_RDB).unwrap();
+        assert_eq!(database.get("key".as_bytes()).unwrap(), None);
+    }
+
+    #[test]
+    fn read_string_values() {
+        let mut body = header();
+        body.extend([0xfe, 0, 0xfb, 3, 0]);
+        body.extend([0, 3, b'f', b'o', b'o', 3, b'b', b'a', b'r']);
+        body.extend([0, 3, b'i', b'n', b't', 0xc1, 0x39, 0x30]);
+        body.extend([0, 3, b'l', b'z', b'f', 0xc3, 5, 10, 0, b'a', 0xe0, 0, 0]);
+
+        let mut database = read(&with_checksum(body)).unwrap();
+        assert_eq!(
+            database.get(&key("foo")).unwrap(),
+            Some("bar".as_bytes().to_vec())
+        );
+        assert_eq!(
+            database.get(&key("int")).unwrap(),
+            Some("12345".as_bytes().to_vec())
+        );
+        assert_eq!(
+            database.get(&key("lzf")).unwrap(),
+            Some("aaaaaaaaaa".as_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn read_expiries() {
+        let future = SystemTime::now() + Duration::from_secs(3600);
+        let future = future.duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+
+        let mut body = header();
+        body.extend([0xfe, 0]);
+        body.push(0xfc);
+        body.extend(future.to_le_bytes());
+        body.extend([0, 4, b'l', b'i', b'v', b'e', 1, b'1']);
+        body.push(0xfc);
+        body.extend(1000u64.to_le_bytes());
+        body.extend([0, 4, b'g', b'o', b'n', b'e', 1, b'2']);
+        body.push(0xfd);
+        body.extend(1u32.to_le_bytes());
+        body.extend([0, 3, b'o', b'l', b'd', 1, b'3']);
+
+        let mut database = read(&with_checksum(body)).unwrap();
+        assert_eq!(
+            database.get(&key("live")).unwrap(),
+            Some("1".as_bytes().to_vec())
+        );
+        assert_eq!(database.get(&key("gone")).unwrap(), None);
+        assert_eq!(database.get(&key("old")).unwrap(), None);
+    }
+
+    #[test]
+    fn read_lists() {
+        let mut body = header();
+        body.extend([0xfe, 0]);
+        body.extend([1, 4, b'l', b'i', b's', b't', 2, 1, b'a', 1, b'b']);
+
+        // a quicklist with a plain node and a listpack node holding "c" and 7
+        body.extend([18, 2, b'q', b'l', 2, 1, 1, b'x', 2, 12]);
+        body.extend([12, 0, 0, 0, 2, 0, 0x81, b'c', 2, 7, 1, 0xff]);
+
+        let mut database = read(&with_checksum(body)).unwrap();
+        let list = database.list(&key("list")).unwrap().unwrap();
+        assert_eq!(list, &[key("a"), key("b")]);
+
+        let list = database.list(&key("ql")).unwrap().unwrap();
+        assert_eq!(list, &[key("x"), key("c"), key("7")]);
+    }
+
+    #[test]
+    fn read_hashes() {
+        let mut body = header();
+        body.extend([0xfe, 0]);
+        body.extend([4, 1, b'h', 1, 1, b'f', 1, b'v']);
+
+        // a listpack holding "a" => 1
+        body.extend([16, 2, b'l', b'p', 12]);
+        body.extend([12, 0, 0, 0, 2, 0, 0x81, b'a', 2, 1, 1, 0xff]);
+
+        let mut database = read(&with_checksum(body)).unwrap();
+        let hash = database.hash(&key("h")).unwrap().unwrap();
+        assert_eq!(hash.get(&key("f")), Some(&key("v")));
+
+        let hash = database.hash(&key("lp")).unwrap().unwrap();
+        assert_eq!(hash.len(), 1);
+        assert_eq!(hash.get(&key("a")), Some(&key("1")));
+    }
+
+    #[test]
+    fn read_sets() {
+        let mut body = header();
+        body.extend([0xfe, 0]);
+        body.extend([2, 1, b's', 2, 1, b'a', 1, b'b']);
+
+        // an intset holding 1 and 2
+        body.extend([11, 2, b'i', b's', 12]);
+        body.extend([2, 0, 0, 0, 2, 0, 0, 0, 1, 0, 2, 0]);
+
+        let mut database = read(&with_checksum(body)).unwrap();
+        let set = database.members(&key("s")).unwrap().unwrap();
+        assert!(set.contains(&key("a")) && set.contains(&key("b")));
+
+        let set = database.members(&key("is")).unwrap().unwrap();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&key("2")));
+    }
+
+    #[test]
+    fn read_sorted_sets() {
+        let mut body = header();
+        body.extend([0xfe, 0]);
+        body.extend([5, 1, b'z', 2, 1, b'a']);
+        body.extend(1.5f64.to_le_bytes());
+        body.extend([1, b'b']);
+        body.extend((-2.0f64).to_le_bytes());
+
+        // the old encoding with text scores
+        body.extend([3, 2, b'z', b'1', 1, 1, b'c', 3, b'0', b'.', b'5']);
+
+        let mut database = read(&with_checksum(body)).unwrap();
+        let set = database.sorted_set(&key("z")).unwrap().unwrap();
+        assert_eq!(set.score(b"a"), Some(1.5));
+        assert_eq!(set.rank(b"b"), Some(0));
+
+        let set = database.sorted_set(&key("z1")).unwrap().unwrap();
+        assert_eq!(set.score(b"c"), Some(0.5));
+    }
+
+    #[test]
+    fn read_streams() {
+        let elements: Vec<Vec<u8>> = [
+            // master entry: 2 entries, 1 deleted, fields [f]
+            "2", "1", "1", "f", "0", //
+            // 1-5 with the master fields
+            "2", "0", "0", "a", "4", //
+            // 2-0 with its own fields
+            "0", "1", "-5", "2", "f", "b", "g", "c", "8", //
+            // 2-1, deleted
+            "3", "1", "-4", "z", "4",
+        ]
+        .iter()
+        .map(|element| element.as_bytes().to_vec())
+        .collect();
+        let node = listpack::encode(&elements);
+
+        let mut body = header();
+        body.extend([0xfe, 0]);
+        body.extend([TYPE_STREAM_LISTPACKS, 1, b's', 1, 16]);
+        body.extend(1u64.to_be_bytes());
+        body.extend(5u64.to_be_bytes());
+        body.extend([0x40 | (node.len() >> 8) as u8, node.len() as u8]);
+        body.extend(node);
+        // length, last ID 2-1 and no consumer groups
+        body.extend([2, 2, 1, 0]);
+
+        let mut database = read(&with_checksum(body)).unwrap();
+        let stream = database.stream(&key("s")).unwrap().unwrap();
+        let entries = stream.range(StreamId::MIN, StreamId::MAX, false, None);
+
+        assert_eq!(stream.last_id(), StreamId { ms: 2, seq: 1 });
+        assert_eq!(
+            entries,
+            vec![
+                (StreamId { ms: 1, seq: 5 }, vec![(key("f"), key("a"))]),
+                (
+                    StreamId { ms: 2, seq: 0 },
+                    vec![(key("f"), key("b")), (key("g"), key("c"))]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_skips_other_databases() {
+        let mut body = header();
+        body.extend([0xfe, 1, 0, 1, b'a', 1, b'1']);
+        body.extend([0xfe, 0, 0, 1, b'b', 1, b'2']);
+
+        let mut database = read(&with_checksum(body)).unwrap();
+        assert_eq!(database.get(&key("a")).unwrap(), None);
+        assert_eq!(
+            database.get(&key("b")).unwrap(),
+            Some("2".as_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn read_checksum_mismatch() {
+        let mut data = EMPTY_RDB.to_vec();
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+
+        match read(&data) {
+            Err(RdbError::Invalid(message)) => assert_eq!(message, "checksum mismatch"),
+            value => panic!("expected checksum mismatch, got {:?}", value.is_ok()),
+        }
+    }
+
+    #[test]
+    fn read_disabled_checksum() {
+        let mut data = header();
+        data.extend([0xff, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(read(&data).is_ok());
+    }
+
+    #[test]
+    fn read_truncated() {
+        match read(&EMPTY_RDB[..40]) {
+            Err(RdbError::Invalid(message)) => assert_eq!(message, "unexpected end of data"),
+            value => panic!("expected truncation error, got {:?}", value.is_ok()),
+        }
+    }
+
+    #[test]
+    fn read_invalid_signature() {
+        match read("RODIS0011".as_bytes()) {
+            Err(RdbError::Invalid(message)) => assert_eq!(message, "missing REDIS signature"),
+            value => panic!("expected signature error, got {:?}", value.is_ok()),
+        }
+    }
+
+    fn header() -> Vec<u8> {
+        "REDIS0011".as_bytes().to_vec()
+    }
+
+    fn with_checksum(mut body: Vec<u8>) -> Vec<u8> {
+        body.push(0xff);
+        let checksum = crc64::crc64(0, &body);
+        body.extend(checksum.to_le_bytes());
+        body
+    }
+
+    fn key(key: &str) -> Vec<u8> {
+        key.as_bytes().to_vec()
+    }
+}
@@ -0,0 +1,412 @@
+use super::*;
+use crate::{
+    protocol::StreamId,
+    server::{
+        consumer_group::ConsumerGroup,
+        database::{Data, Database},
+        stream::Fields,
+    },
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The RDB format version written by [`write`].
+const VERSION: &str = "0011";
+
+/// The most entries written to a stream listpack node, the default of Redis'
+/// `stream-node-max-entries`.
+const STREAM_NODE_MAX_ENTRIES: usize = 100;
+
+/// Serializes every live key in `database` into a complete RDB snapshot.
+pub fn write(database: &Database) -> Vec<u8> {
+    let mut writer = RdbWriter { data: Vec::new() };
+    writer.write_database(database);
+    writer.data
+}
+
+struct RdbWriter {
+    data: Vec<u8>,
+}
+
+impl RdbWriter {
+    fn write_database(&mut self, database: &Database) {
+        self.data.extend("REDIS".as_bytes());
+        self.data.extend(VERSION.as_bytes());
+
+        let ctime = unix_millis(SystemTime::now()) / 1000;
+        self.write_aux("redis-ver", "7.2.0".as_bytes());
+        self.write_aux("redis-bits", "64".as_bytes());
+        self.write_aux("ctime", ctime.to_string().as_bytes());
+
+        let entries: Vec<_> = database.entries().collect();
+        let expires = entries.iter().filter(|entry| entry.2.is_some()).count();
+
+        self.data.push(OPCODE_SELECTDB);
+        self.write_length(0);
+        self.data.push(OPCODE_RESIZEDB);
+        self.write_length(entries.len());
+        self.write_length(expires);
+
+        for (key, data, expires_at) in entries {
+            if let Some(expires_at) = expires_at {
+                self.data.push(OPCODE_EXPIRETIME_MS);
+                self.data.extend(unix_millis(expires_at).to_le_bytes());
+            }
+
+            self.write_entry(key, data);
+        }
+
+        self.data.push(OPCODE_EOF);
+        let checksum = crc64::crc64(0, &self.data);
+        self.data.extend(checksum.to_le_bytes());
+    }
+
+    fn write_entry(&mut self, key: &[u8], data: &Data) {
+        match data {
+            Data::String(value) => {
+                self.data.push(TYPE_STRING);
+                self.write_string(key);
+                self.write_string(value);
+            }
+            Data::List(list) => {
+                self.data.push(TYPE_LIST);
+                self.write_string(key);
+                self.write_length(list.len());
+                list.iter().for_each(|element| self.write_string(element));
+            }
+            Data::Set(set) => {
+                self.data.push(TYPE_SET);
+                self.write_string(key);
+                self.write_length(set.len());
+                set.iter().for_each(|member| self.write_string(member));
+            }
+            Data::SortedSet(set) => {
+                self.data.push(TYPE_ZSET_2);
+                self.write_string(key);
+                self.write_length(set.len());
+
+                for (member, score) in set.iter() {
+                    self.write_string(member);
+                    self.data.extend(score.to_le_bytes());
+                }
+            }
+            Data::Hash(hash) => {
+                self.data.push(TYPE_HASH);
+                self.write_string(key);
+                self.write_length(hash.len());
+
+                for (field, value) in hash {
+                    self.write_string(field);
+                    self.write_string(value);
+                }
+            }
+            Data::Stream(stream) => {
+                self.data.push(TYPE_STREAM_LISTPACKS_3);
+                self.write_string(key);
+
+                let entries: Vec<_> = stream.iter().collect();
+                let nodes = entries.chunks(STREAM_NODE_MAX_ENTRIES);
+                self.write_length(nodes.len());
+
+                for node in nodes {
+                    let master_id = *node[0].0;
+                    self.write_string(&raw_stream_id(master_id));
+                    self.write_string(&listpack::encode(&stream_node(master_id, node)));
+                }
+
+                let first_id = entries.first().map_or(StreamId::MIN, |(id, _)| **id);
+
+                self.write_length(stream.len());
+                self.write_stream_id(stream.last_id());
+                self.write_stream_id(first_id);
+                self.write_stream_id(stream.max_deleted_id());
+                self.write_length(stream.entries_added() as usize);
+
+                self.write_length(stream.groups().count());
+
+                for (name, group) in stream.groups() {
+                    self.write_consumer_group(name, group);
+                }
+            }
+        }
+    }
+
+    /// Writes a consumer group in the layout [`read`](super::read) expects: the pending entries
+    /// with their delivery times and counts, then each consumer with the IDs it owns.
+    fn write_consumer_group(&mut self, name: &[u8], group: &ConsumerGroup) {
+        self.write_string(name);
+        self.write_stream_id(group.last_id);
+        self.write_length(group.entries_read.unwrap_or(u64::MAX) as usize);
+
+        self.write_length(group.pending_len());
+
+        for (id, pending) in group.pending_from(StreamId::MIN) {
+            self.data.extend(raw_stream_id(*id));
+            self.data.extend(pending.delivery_time.to_le_bytes());
+            self.write_length(pending.delivery_count as usize);
+        }
+
+        self.write_length(group.consumers().count());
+
+        for (name, consumer) in group.consumers() {
+            self.write_string(name);
+            self.data.extend(consumer.seen_time.to_le_bytes());
+
+            let active_time = consumer.active_time.map_or(-1, |time| time as i64);
+            self.data.extend(active_time.to_le_bytes());
+
+            self.write_length(consumer.pending_len());
+            consumer
+                .pending()
+                .for_each(|id| self.data.extend(raw_stream_id(id)));
+        }
+    }
+
+    fn write_stream_id(&mut self, id: StreamId) {
+        self.write_length(id.ms as usize);
+        self.write_length(id.seq as usize);
+    }
+
+    fn write_aux(&mut self, key: &str, value: &[u8]) {
+        self.data.push(OPCODE_AUX);
+        self.write_string(key.as_bytes());
+        self.write_string(value);
+    }
+
+    fn write_string(&mut self, bytes: &[u8]) {
+        self.write_length(bytes.len());
+        self.data.extend(bytes);
+    }
+
+    fn write_length(&mut self, length: usize) {
+        if length < 1 << 6 {
+            self.data.push(length as u8);
+        } else if length < 1 << 14 {
+            self.data.push(0x40 | (length >> 8) as u8);
+            self.data.push(length as u8);
+        } else if let Ok(length) = u32::try_from(length) {
+            self.data.push(0x80);
+            self.data.extend(length.to_be_bytes());
+        } else {
+            self.data.push(0x81);
+            self.data.extend((length as u64).to_be_bytes());
+        }
+    }
+}
+
+/// A stream ID as 16 big-endian bytes.
+fn raw_stream_id(id: StreamId) -> [u8; 16] {
+    let mut bytes = [0; 16];
+    bytes[..8].copy_from_slice(&id.ms.to_be_bytes());
+    bytes[8..].copy_from_slice(&id.seq.to_be_bytes());
+    bytes
+}
+
+/// Lays out the elements of a stream listpack node: the master entry with the counts and the
+/// first entry's fields, then each entry with its ID as a difference from `master_id`, and its
+/// values alone when it has the same fields as the master entry.
+fn stream_node(master_id: StreamId, entries: &[(&StreamId, &Fields)]) -> Vec<Vec<u8>> {
+    let integer = |value: u64| value.to_string().into_bytes();
+    let master_fields: Vec<_> = entries[0].1.iter().map(|(field, _)| field).collect();
+
+    let mut elements = vec![integer(entries.len() as u64), integer(0)];
+    elements.push(integer(master_fields.len() as u64));
+    elements.extend(master_fields.iter().map(|field| field.to_vec()));
+    elements.push(integer(0));
+
+    for (id, fields) in entries {
+        let same_fields = fields.len() == master_fields.len()
+            && fields
+                .iter()
+                .zip(&master_fields)
+                .all(|((field, _), master_field)| field == *master_field);
+
+        let flags = match same_fields {
+            true => STREAM_ITEM_FLAG_SAMEFIELDS,
+            false => 0,
+        };
+
+        elements.push(integer(flags));
+        elements.push(
+            ((id.ms.wrapping_sub(master_id.ms)) as i64)
+                .to_string()
+                .into_bytes(),
+        );
+        elements.push(
+            ((id.seq.wrapping_sub(master_id.seq)) as i64)
+                .to_string()
+                .into_bytes(),
+        );
+
+        if same_fields {
+            elements.extend(fields.iter().map(|(_, value)| value.clone()));
+            elements.push(integer(3 + fields.len() as u64));
+        } else {
+            elements.push(integer(fields.len() as u64));
+            elements.extend(
+                fields
+                    .iter()
+                    .flat_map(|(field, value)| [field.clone(), value.clone()]),
+            );
+            elements.push(integer(4 + 2 * fields.len() as u64));
+        }
+    }
+
+    elements
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::consumer_group::PendingEntry;
+    use std::time::Duration;
+
+    #[test]
+    fn write_empty_database() {
+        let data = write(&Database::new());
+
+        assert!(data.starts_with("REDIS0011".as_bytes()));
+        assert_eq!(data[data.len() - 9], OPCODE_EOF);
+        assert!(read(&data).is_ok());
+    }
+
+    #[test]
+    fn write_round_trip() {
+        let mut database = Database::new();
+        database.set(key("plain"), key("value"), None);
+        let soon = SystemTime::now() + Duration::from_secs(60);
+        database.set(key("expiring"), key("soon"), Some(soon));
+        database.set(key("long"), vec![b'x'; 20_000], None);
+
+        let mut loaded = read(&write(&database)).unwrap();
+        assert_eq!(loaded.get(&key("plain")).unwrap(), Some(key("value")));
+        assert_eq!(loaded.get(&key("expiring")).unwrap(), Some(key("soon")));
+        assert_eq!(loaded.get(&key("long")).unwrap(), Some(vec![b'x'; 20_000]));
+    }
+
+    #[test]
+    fn write_lists() {
+        let mut database = Database::new();
+        let list = database.list_or_insert(&key("list")).unwrap();
+        list.extend([key("a"), key("b"), key("c")]);
+
+        let mut loaded = read(&write(&database)).unwrap();
+        let list = loaded.list(&key("list")).unwrap().unwrap();
+        assert_eq!(list, &[key("a"), key("b"), key("c")]);
+    }
+
+    #[test]
+    fn write_hashes() {
+        let mut database = Database::new();
+        let hash = database.hash_or_insert(&key("hash")).unwrap();
+        hash.insert(key("a"), key("1"));
+        hash.insert(key("b"), key("2"));
+
+        let mut loaded = read(&write(&database)).unwrap();
+        let hash = loaded.hash(&key("hash")).unwrap().unwrap();
+        assert_eq!(hash.len(), 2);
+        assert_eq!(hash.get(&key("b")), Some(&key("2")));
+    }
+
+    #[test]
+    fn write_sets() {
+        let mut database = Database::new();
+        let set = database.members_or_insert(&key("set")).unwrap();
+        set.extend([key("a"), key("b")]);
+
+        let mut loaded = read(&write(&database)).unwrap();
+        let set = loaded.members(&key("set")).unwrap().unwrap();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&key("a")));
+    }
+
+    #[test]
+    fn write_sorted_sets() {
+        let mut database = Database::new();
+        let set = database.sorted_set_or_insert(&key("zset")).unwrap();
+        set.insert(key("a"), 2.0);
+        set.insert(key("b"), f64::NEG_INFINITY);
+
+        let mut loaded = read(&write(&database)).unwrap();
+        let set = loaded.sorted_set(&key("zset")).unwrap().unwrap();
+        assert_eq!(set.score(b"a"), Some(2.0));
+        assert_eq!(set.rank(b"b"), Some(0));
+    }
+
+    #[test]
+    fn write_streams() {
+        let mut database = Database::new();
+        let stream = database.stream_or_insert(&key("stream")).unwrap();
+
+        for ms in 1..=150 {
+            let field = if ms % 3 == 0 { "other" } else { "field" };
+            let id = StreamId { ms, seq: 150 - ms };
+            stream.add(id, vec![(key(field), ms.to_string().into_bytes())]);
+        }
+
+        stream.remove(StreamId { ms: 150, seq: 0 });
+
+        let mut loaded = read(&write(&database)).unwrap();
+        let loaded = loaded.stream(&key("stream")).unwrap().unwrap();
+        let stream = database.stream(&key("stream")).unwrap().unwrap();
+        assert_eq!(loaded, stream);
+        assert_eq!(loaded.len(), 149);
+        assert_eq!(loaded.last_id(), StreamId { ms: 150, seq: 0 });
+    }
+
+    #[test]
+    fn write_consumer_groups() {
+        let mut database = Database::new();
+        let stream = database.stream_or_insert(&key("stream")).unwrap();
+        let id = StreamId { ms: 1, seq: 0 };
+        stream.add(id, vec![(key("field"), key("value"))]);
+
+        let mut group = ConsumerGroup::new(id, None);
+        group.create_consumer(&key("idle"), 500);
+
+        let pending = PendingEntry {
+            consumer: key("busy"),
+            delivery_time: 1000,
+            delivery_count: 3,
+        };
+        group.set_pending(id, pending);
+        group.touch(&key("busy"), 1200, true);
+
+        stream.create_group(key("group"), group);
+        stream.create_group(key("empty"), ConsumerGroup::new(StreamId::MIN, Some(0)));
+
+        let mut loaded = read(&write(&database)).unwrap();
+        let loaded = loaded.stream(&key("stream")).unwrap().unwrap();
+        let stream = database.stream(&key("stream")).unwrap().unwrap();
+        assert_eq!(loaded, stream);
+        assert_eq!(loaded.groups().count(), 2);
+    }
+
+    #[test]
+    fn write_skips_expired_keys() {
+        let mut database = Database::new();
+        database.set(key("gone"), key("value"), Some(SystemTime::now()));
+
+        let mut loaded = read(&write(&database)).unwrap();
+        assert_eq!(loaded.get(&key("gone")).unwrap(), None);
+    }
+
+    #[test]
+    fn write_length_encodings() {
+        let mut writer = RdbWriter { data: Vec::new() };
+        writer.write_length(10);
+        writer.write_length(700);
+        writer.write_length(70_000);
+
+        assert_eq!(writer.data, vec![10, 0x42, 0xbc, 0x80, 0, 1, 0x11, 0x70]);
+    }
+
+    fn key(key: &str) -> Vec<u8> {
+        key.as_bytes().to_vec()
+    }
+}
@@ -0,0 +1,91 @@
+use super::listpack::read_int_le;
+
+/// Decodes every element of a ziplist, the compact encoding used by Redis before 7.0 for small
+/// aggregates. Integers are returned in their decimal string form.
+///
+/// Returns `None` if the data is malformed.
+pub fn entries(data: &[u8]) -> Option<Vec<Vec<u8>>> {
+    // 4 bytes of total size, 4 bytes of tail offset and 2 bytes of element count
+    let count = u16::from_le_bytes([*data.get(8)?, *data.get(9)?]) as usize;
+    let mut entries = Vec::with_capacity(count);
+    let mut pos = 10;
+
+    loop {
+        // the length of the previous entry, used to walk the ziplist backwards
+        match *data.get(pos)? {
+            0xff => return Some(entries),
+            0xfe => pos += 5,
+            _ => pos += 1,
+        }
+
+        let encoding = *data.get(pos)?;
+
+        let (entry, length) = match encoding >> 6 {
+            0 => {
+                let length = (encoding & 0x3f) as usize;
+                (data.get(pos + 1..pos + 1 + length)?.to_vec(), 1 + length)
+            }
+            1 => {
+                let length = ((encoding as usize & 0x3f) << 8) | *data.get(pos + 1)? as usize;
+                (data.get(pos + 2..pos + 2 + length)?.to_vec(), 2 + length)
+            }
+            2 => {
+                let length = u32::from_be_bytes(data.get(pos + 1..pos + 5)?.try_into().ok()?);
+                let length = length as usize;
+                (data.get(pos + 5..pos + 5 + length)?.to_vec(), 5 + length)
+            }
+            _ => {
+                let size = match encoding {
+                    0xc0 => 2,
+                    0xd0 => 4,
+                    0xe0 => 8,
+                    0xf0 => 3,
+                    0xfe => 1,
+                    0xf1..=0xfd => 0,
+                    _ => return None,
+                };
+
+                let value = match size {
+                    // 4-bit immediate, stored as value + 1
+                    0 => (encoding & 0x0f) as i64 - 1,
+                    size => read_int_le(data.get(pos + 1..pos + 1 + size)?),
+                };
+
+                (value.to_string().into_bytes(), 1 + size)
+            }
+        };
+
+        entries.push(entry);
+        pos += length;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_and_integers() {
+        let mut data = vec![0; 8];
+        data.extend([4, 0]);
+        // "foo"
+        data.extend([0, 0x03, b'f', b'o', b'o']);
+        // immediate 12
+        data.extend([5, 0xfd]);
+        // int8 -5
+        data.extend([2, 0xfe, 0xfb]);
+        // int16 1000
+        data.extend([3, 0xc0, 0xe8, 0x03]);
+        data.push(0xff);
+
+        let entries = entries(&data).unwrap();
+        let entries: Vec<_> = entries.iter().map(|e| String::from_utf8_lossy(e)).collect();
+        assert_eq!(entries, vec!["foo", "12", "-5", "1000"]);
+    }
+
+    #[test]
+    fn truncated() {
+        let data = [0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0x03, b'f'];
+        assert_eq!(entries(&data), None);
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+
+/// A bounded history of the bytes sent down the replication stream.
+///
+/// Offsets follow Redis conventions: the first byte ever propagated has offset 1, so the offset
+/// of the last byte is also the total number of bytes propagated (the master replication
+/// offset). A replica that processed everything up to offset `n` asks to continue from `n + 1`.
+pub struct Backlog {
+    buffer: VecDeque<u8>,
+    capacity: usize,
+    offset: u64,
+}
+
+impl Backlog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: VecDeque::new(),
+            capacity,
+            offset: 0,
+        }
+    }
+
+    pub fn append(&mut self, bytes: &[u8]) {
+        self.offset += bytes.len() as u64;
+
+        if bytes.len() >= self.capacity {
+            self.buffer.clear();
+            self.buffer.extend(&bytes[bytes.len() - self.capacity..]);
+            return;
+        }
+
+        let overflow = (self.buffer.len() + bytes.len()).saturating_sub(self.capacity);
+        self.buffer.drain(..overflow);
+        self.buffer.extend(bytes);
+    }
+
+    /// Drops every byte held and continues the stream from `offset`, as when a replica adopts
+    /// the master's stream after a full resynchronization.
+    pub fn reset(&mut self, offset: u64) {
+        self.buffer.clear();
+        self.offset = offset;
+    }
+
+    /// Number of bytes currently held.
+    pub fn histlen(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Offset of the last byte appended, i.e. the master replication offset.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Offset of the oldest byte still held in the backlog.
+    pub fn first_offset(&self) -> u64 {
+        self.offset - self.buffer.len() as u64 + 1
+    }
+
+    /// Returns every byte from `offset` onwards, or `None` if part of that range was already
+    /// discarded or hasn't been written yet.
+    pub fn since(&self, offset: u64) -> Option<Vec<u8>> {
+        if offset < self.first_offset() || offset > self.offset + 1 {
+            return None;
+        }
+
+        let skip = (offset - self.first_offset()) as usize;
+        Some(self.buffer.iter().skip(skip).copied().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn since_start() {
+        let mut backlog = Backlog::new(16);
+        backlog.append("hello".as_bytes());
+
+        assert_eq!(backlog.offset(), 5);
+        assert_eq!(backlog.first_offset(), 1);
+        assert_eq!(backlog.since(1), Some("hello".as_bytes().to_vec()));
+        assert_eq!(backlog.since(4), Some("lo".as_bytes().to_vec()));
+    }
+
+    #[test]
+    fn since_end_is_empty() {
+        let mut backlog = Backlog::new(16);
+        assert_eq!(backlog.since(1), Some(vec![]));
+
+        backlog.append("hello".as_bytes());
+        assert_eq!(backlog.since(6), Some(vec![]));
+        assert_eq!(backlog.since(7), None);
+    }
+
+    #[test]
+    fn discards_oldest_bytes() {
+        let mut backlog = Backlog::new(8);
+        backlog.append("hello".as_bytes());
+        backlog.append("world".as_bytes());
+
+        assert_eq!(backlog.offset(), 10);
+        assert_eq!(backlog.first_offset(), 3);
+        assert_eq!(backlog.since(2), None);
+        assert_eq!(backlog.since(3), Some("lloworld".as_bytes().to_vec()));
+    }
+
+    #[test]
+    fn reset_continues_from_offset() {
+        let mut backlog = Backlog::new(16);
+        backlog.append("hello".as_bytes());
+        backlog.reset(100);
+
+        assert_eq!(backlog.histlen(), 0);
+        assert_eq!(backlog.since(101), Some(vec![]));
+        assert_eq!(backlog.since(6), None);
+
+        backlog.append("world".as_bytes());
+        assert_eq!(backlog.offset(), 105);
+        assert_eq!(backlog.since(103), Some("rld".as_bytes().to_vec()));
+    }
+
+    #[test]
+    fn append_larger_than_capacity() {
+        let mut backlog = Backlog::new(4);
+        backlog.append("hello world".as_bytes());
+
+        assert_eq!(backlog.offset(), 11);
+        assert_eq!(backlog.since(8), Some("orld".as_bytes().to_vec()));
+    }
+}
@@ -0,0 +1,767 @@
+mod backlog;
+
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    protocol::{Command, CommandReadError, CommandReader, Value, ValueReader, ValueWriter},
+    server::{database::Database, random, rdb, ServerHandle},
+};
+use tokio::{
+    io::{BufReader, BufWriter},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpStream,
+    },
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+use self::backlog::Backlog;
+
+/// The replication ID reported as `master_replid2` when there's no previous history.
+static EMPTY_REPLID: &str = "0000000000000000000000000000000000000000";
+
+/// Delay before the first reconnection attempt after the master link drops.
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_millis(100);
+
+/// Upper bound for the exponential backoff between reconnection attempts.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+type MasterReader = ValueReader<BufReader<OwnedReadHalf>>;
+type MasterWriter = ValueWriter<BufWriter<OwnedWriteHalf>>;
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct ReplicationError(String);
+
+/// The outcome of a successful PSYNC handshake with the master.
+pub enum Resync {
+    /// The master sent a full snapshot, taken at `offset`
+    Full {
+        replid: String,
+        offset: u64,
+        database: Database,
+    },
+
+    /// The master will stream the commands this replica missed
+    Partial { replid: String },
+}
+
+/// Keeps this replica connected to `master` for as long as the server runs, reconnecting with
+/// exponential backoff whenever the link drops. Everything it reports to the server is tagged
+/// with the link `generation`, so the server can tell it from what earlier links reported.
+async fn replica_loop(master: String, port: u16, server: ServerHandle, generation: u64) {
+    let mut replid: Option<String> = None;
+    let mut offset = 0;
+    let mut delay = INITIAL_RECONNECT_DELAY;
+
+    while !server.is_closed() {
+        let known = replid.as_deref().map(|replid| (replid, offset));
+
+        match init(&master, port, known).await {
+            Ok((sync, reader, writer)) => {
+                delay = INITIAL_RECONNECT_DELAY;
+
+                match &sync {
+                    Resync::Full {
+                        replid: new_replid,
+                        offset: new_offset,
+                        ..
+                    } => {
+                        replid = Some(new_replid.clone());
+                        offset = *new_offset;
+                    }
+                    Resync::Partial { replid: new_replid } => replid = Some(new_replid.clone()),
+                }
+
+                offset += replication_loop(&server, generation, sync, reader, writer).await as u64;
+                let _ = server.master_link_down(generation);
+            }
+            Err(err) => println!("failed to sync with master {}: {}", master, err),
+        }
+
+        println!("reconnecting to master in {:?}", delay);
+        tokio::time::sleep(delay).await;
+        delay = next_reconnect_delay(delay);
+    }
+}
+
+/// Doubles the delay between reconnection attempts, up to [`MAX_RECONNECT_DELAY`].
+fn next_reconnect_delay(delay: Duration) -> Duration {
+    (delay * 2).min(MAX_RECONNECT_DELAY)
+}
+
+/// Connects to the master and performs the replication handshake.
+///
+/// When `known` holds the replication ID and offset this replica last saw, the master is asked
+/// to continue from there; otherwise a full resynchronization is requested.
+async fn init(
+    master: &str,
+    port: u16,
+    known: Option<(&str, u64)>,
+) -> Result<(Resync, MasterReader, MasterWriter), ReplicationError> {
+    let stream = TcpStream::connect(master)
+        .await
+        .map_err(|err| ReplicationError(format!("failed to connect to master: {}", err)))?;
+
+    let (reader, writer) = stream.into_split();
+    let mut reader = ValueReader::new(BufReader::new(reader));
+    let mut writer = ValueWriter::new(BufWriter::new(writer));
+
+    let port = port.to_string();
+    let commands = vec![
+        Value::command_str("PING", &[]),
+        Value::command_str("REPLCONF", &["listening-port", &port]),
+        Value::command_str("REPLCONF", &["capa", "psync2"]),
+    ];
+
+    for command in commands {
+        send_to_master(&mut writer, &command).await?;
+        read_from_master(&mut reader).await?;
+    }
+
+    let psync = match known {
+        Some((replid, offset)) => Value::command_str("PSYNC", &[replid, &(offset + 1).to_string()]),
+        None => Value::command_str("PSYNC", &["?", "-1"]),
+    };
+
+    send_to_master(&mut writer, &psync).await?;
+
+    let reply = match read_from_master(&mut reader).await? {
+        Value::SimpleString((_, reply)) => reply,
+        value => {
+            let message = format!("unexpected PSYNC reply: {:?}", value);
+            return Err(ReplicationError(message));
+        }
+    };
+
+    let parts: Vec<_> = reply.split(' ').collect();
+
+    let sync = match parts.as_slice() {
+        ["FULLRESYNC", replid, offset] => {
+            let offset = offset
+                .parse()
+                .map_err(|_| ReplicationError(format!("invalid FULLRESYNC offset: {}", offset)))?;
+
+            let (_, rdb) = reader.read_bytes().await.map_err(|err| {
+                ReplicationError(format!("error reading RDB from master: {}", err))
+            })?;
+
+            let database = rdb::read(&rdb).map_err(|err| {
+                ReplicationError(format!("error parsing RDB from master: {}", err))
+            })?;
+
+            println!("received RDB transfer of {} bytes", rdb.len());
+
+            Resync::Full {
+                replid: replid.to_string(),
+                offset,
+                database,
+            }
+        }
+        ["CONTINUE", replid] => Resync::Partial {
+            replid: replid.to_string(),
+        },
+        ["CONTINUE"] => Resync::Partial {
+            replid: known
+                .map(|(replid, _)| replid.to_string())
+                .unwrap_or_default(),
+        },
+        _ => {
+            let message = format!("unexpected PSYNC reply: {}", reply);
+            return Err(ReplicationError(message));
+        }
+    };
+
+    Ok((sync, reader, writer))
+}
+
+async fn send_to_master(writer: &mut MasterWriter, value: &Value) -> Result<(), ReplicationError> {
+    writer
+        .write(value)
+        .await
+        .map_err(|_| ReplicationError("failed to write command".to_string()))
+}
+
+async fn read_from_master(reader: &mut MasterReader) -> Result<Value, ReplicationError> {
+    reader
+        .read()
+        .await
+        .map_err(|_| ReplicationError("failed to read response".to_string()))
+}
+
+/// Reports the link to the master as up, then applies the command stream sent by the master
+/// until the connection drops.
+///
+/// Returns the number of bytes read from the stream.
+async fn replication_loop(
+    server: &ServerHandle,
+    generation: u64,
+    sync: Resync,
+    reader: MasterReader,
+    mut writer: MasterWriter, // we need to keep this alive
+) -> usize {
+    let mut reader = CommandReader::new(reader);
+
+    // a channel that will ignore all received values
+    let (black_hole_sender, mut black_hole_receiver) = mpsc::unbounded_channel();
+
+    // a channel to write values to the replication connection
+    let (values_sender, mut values_receiver) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while black_hole_receiver.recv().await.is_some() {
+            // do nothing — we don't send replies to replica commands
+        }
+    });
+
+    tokio::spawn(async move {
+        while let Some(values) = values_receiver.recv().await {
+            for value in values {
+                if let Err(err) = writer.write(&value).await {
+                    println!("failed to write reply to master: {}", err);
+                    return;
+                }
+            }
+        }
+    });
+
+    if let Err(err) = server.master_link_up(generation, sync, values_sender.clone()) {
+        println!("failed to report master link up: {:?}", err);
+        return 0;
+    }
+
+    loop {
+        let (result, bytes) = reader.read_with_bytes().await;
+
+        match result {
+            Ok(command) => {
+                let reply_to = match &command {
+                    Command::Replconf { key, .. }
+                        if String::from_utf8_lossy(key).to_uppercase() == "GETACK" =>
+                    {
+                        println!("using real response channel for command {:?}", command);
+                        values_sender.clone()
+                    }
+                    _ => black_hole_sender.clone(),
+                };
+
+                if let Err(err) = server.send_from_master(generation, command, bytes, reply_to) {
+                    println!("failed to send command to server: {:?}", err);
+                    break;
+                }
+            }
+            Err(CommandReadError::Invalid(values)) => {
+                // it's still part of the stream, so it counts toward the offset and is passed on
+                let message = match values.first() {
+                    Some(Value::SimpleError((_, message))) => message.as_str(),
+                    _ => "invalid command",
+                };
+
+                println!(
+                    "skipping {} bytes of invalid command from master: {}",
+                    bytes.len(),
+                    message
+                );
+
+                if let Err(err) = server.forward_from_master(generation, bytes) {
+                    println!("failed to send command to server: {:?}", err);
+                    break;
+                }
+            }
+            Err(CommandReadError::Stop(cause)) => {
+                if let Some(cause) = cause {
+                    println!("error reading command from master: {}", cause);
+                }
+                break;
+            }
+        }
+    }
+
+    println!("master link lost; exiting replication loop");
+    reader.bytes_read()
+}
+
+pub struct ReplicationManager {
+    replid: String,
+    replid2: String,
+    second_repl_offset: Option<u64>,
+    backlog: Backlog,
+    replicas: Vec<Replica>,
+    waiters: Vec<Waiter>,
+    master_link: Option<JoinHandle<()>>,
+    /// Counts the links to a master that were closed, to identify the current one.
+    link_generation: u64,
+    master_sender: Option<mpsc::UnboundedSender<Vec<Value>>>,
+}
+
+pub struct Replica {
+    address: SocketAddr,
+    listening_port: u16,
+    values_sender: mpsc::UnboundedSender<Vec<Value>>,
+    ack_offset: u64,
+    ack_time: Instant,
+}
+
+impl Replica {
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// The port the replica accepts connections on, as announced with REPLCONF listening-port.
+    pub fn listening_port(&self) -> u16 {
+        self.listening_port
+    }
+
+    /// The last offset this replica acknowledged.
+    pub fn ack_offset(&self) -> u64 {
+        self.ack_offset
+    }
+
+    /// Time since this replica last acknowledged its offset.
+    pub fn lag(&self) -> Duration {
+        self.ack_time.elapsed()
+    }
+}
+
+/// A client blocked in WAIT until enough replicas acknowledge `offset`.
+struct Waiter {
+    reply_to: mpsc::UnboundedSender<Vec<Value>>,
+    replicas: usize,
+    offset: u64,
+    deadline: Option<Instant>,
+}
+
+impl ReplicationManager {
+    pub fn new(backlog_size: usize) -> Self {
+        Self {
+            replid: random::hex_string(40),
+            replid2: EMPTY_REPLID.to_string(),
+            second_repl_offset: None,
+            backlog: Backlog::new(backlog_size),
+            replicas: vec![],
+            waiters: vec![],
+            master_link: None,
+            link_generation: 0,
+            master_sender: None,
+        }
+    }
+
+    pub fn replid(&self) -> &str {
+        &self.replid
+    }
+
+    /// The replication ID this node's history was known by before the current one.
+    pub fn replid2(&self) -> &str {
+        &self.replid2
+    }
+
+    /// The first offset that is no longer part of the `replid2` history.
+    pub fn second_repl_offset(&self) -> Option<u64> {
+        self.second_repl_offset
+    }
+
+    /// The master replication offset: the total number of bytes sent down the stream.
+    pub fn offset(&self) -> u64 {
+        self.backlog.offset()
+    }
+
+    pub fn backlog(&self) -> &Backlog {
+        &self.backlog
+    }
+
+    /// Adopts the history of a master after a full resynchronization at `offset`.
+    pub fn reset(&mut self, replid: String, offset: u64) {
+        self.replid = replid;
+        self.replid2 = EMPTY_REPLID.to_string();
+        self.second_repl_offset = None;
+        self.backlog.reset(offset);
+    }
+
+    /// Starts a new history under `replid`, keeping the current one as the secondary ID so
+    /// replicas that followed it can still continue from the backlog.
+    pub fn shift_replid(&mut self, replid: String) {
+        self.replid2 = std::mem::replace(&mut self.replid, replid);
+        self.second_repl_offset = Some(self.offset() + 1);
+        println!(
+            "replication ID set to {}, previous ID {} valid up to offset {}",
+            self.replid,
+            self.replid2,
+            self.offset()
+        );
+    }
+
+    pub fn add(
+        &mut self,
+        address: SocketAddr,
+        listening_port: u16,
+        values_sender: mpsc::UnboundedSender<Vec<Value>>,
+    ) {
+        self.replicas.push(Replica {
+            address,
+            listening_port,
+            values_sender,
+            ack_offset: 0,
+            ack_time: Instant::now(),
+        });
+        println!("added replica: {}", address);
+    }
+
+    /// The replicas whose connection is still open.
+    pub fn replicas(&self) -> impl Iterator<Item = &Replica> {
+        self.replicas
+            .iter()
+            .filter(|r| !r.values_sender.is_closed())
+    }
+
+    /// Returns what a replica that last saw `replid` needs to resume streaming from `offset`,
+    /// or `None` if it must go through a full resynchronization instead.
+    pub fn continue_from(&self, replid: &[u8], offset: u64) -> Option<Vec<u8>> {
+        let current = replid == self.replid.as_bytes();
+        let previous = replid == self.replid2.as_bytes()
+            && self
+                .second_repl_offset
+                .is_some_and(|second| offset <= second);
+
+        if !current && !previous {
+            return None;
+        }
+
+        self.backlog.since(offset)
+    }
+
+    pub fn replicate(&mut self, command: &Command) {
+        if command.is_write() {
+            self.feed(&command.to_value().to_bytes());
+        }
+    }
+
+    /// Asks every replica to report its offset with a REPLCONF ACK.
+    pub fn request_acks(&mut self) {
+        let getack = Value::command_str("REPLCONF", &["GETACK", "*"]);
+        self.feed(&getack.to_bytes());
+    }
+
+    /// Appends encoded commands to the replication stream. Replicas call this with their
+    /// master's stream, which they pass on verbatim.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.backlog.append(bytes);
+        self.replicas.retain(|r| send_to_replica(r, bytes));
+    }
+
+    /// Records that the replica at `address` has processed the stream up to `offset`, and
+    /// replies to every WAIT that is now satisfied.
+    pub fn ack(&mut self, address: SocketAddr, offset: u64) {
+        if let Some(replica) = self.replicas.iter_mut().find(|r| r.address == address) {
+            replica.ack_offset = offset;
+            replica.ack_time = Instant::now();
+        }
+
+        self.wake_waiters(Instant::now());
+    }
+
+    /// Number of replicas that acknowledged at least `offset`.
+    pub fn acked_replicas(&self, offset: u64) -> usize {
+        self.replicas().filter(|r| r.ack_offset >= offset).count()
+    }
+
+    /// Blocks a WAIT until `replicas` replicas acknowledge `offset` or `deadline` passes.
+    pub fn block(
+        &mut self,
+        reply_to: mpsc::UnboundedSender<Vec<Value>>,
+        replicas: usize,
+        offset: u64,
+        deadline: Option<Instant>,
+    ) {
+        self.waiters.push(Waiter {
+            reply_to,
+            replicas,
+            offset,
+            deadline,
+        });
+    }
+
+    /// The earliest moment a blocked WAIT times out.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.waiters.iter().filter_map(|w| w.deadline).min()
+    }
+
+    /// Replies to every WAIT that is satisfied or timed out by `now`, with the number of
+    /// replicas that acknowledged its offset.
+    pub fn wake_waiters(&mut self, now: Instant) {
+        let waiters = std::mem::take(&mut self.waiters);
+
+        for waiter in waiters {
+            let acked = self.acked_replicas(waiter.offset);
+            let timed_out = waiter.deadline.is_some_and(|deadline| deadline <= now);
+
+            if acked >= waiter.replicas || timed_out {
+                let _ = waiter.reply_to.send(vec![Value::integer(acked as i64)]);
+            } else if !waiter.reply_to.is_closed() {
+                self.waiters.push(waiter);
+            }
+        }
+    }
+
+    /// Starts replicating from `master` in the background, replacing any previous master link.
+    /// `port` is the port this server listens on, announced to the master.
+    pub fn follow(&mut self, master: String, port: u16, server: ServerHandle) {
+        self.unfollow();
+        let generation = self.link_generation;
+        let task =
+            tokio::spawn(async move { replica_loop(master, port, server, generation).await });
+        self.master_link = Some(task);
+    }
+
+    /// Closes the link to the master, if any. Whatever it already reported and the server
+    /// hasn't processed yet is stale from now on.
+    pub fn unfollow(&mut self) {
+        if let Some(task) = self.master_link.take() {
+            task.abort();
+        }
+
+        self.link_generation += 1;
+        self.master_sender = None;
+    }
+
+    /// Identifies the current link to the master, see [`follow`](Self::follow).
+    pub fn link_generation(&self) -> u64 {
+        self.link_generation
+    }
+
+    /// Disconnects every replica, so they have to sync again.
+    pub fn disconnect_replicas(&mut self) {
+        self.replicas.clear();
+    }
+
+    pub fn master_link_up(&self) -> bool {
+        self.master_sender.is_some()
+    }
+
+    /// Records the link to the master as up, writing to it through `sender`, or as down.
+    pub fn set_master_link(&mut self, sender: Option<mpsc::UnboundedSender<Vec<Value>>>) {
+        self.master_sender = sender;
+    }
+
+    /// Reports this replica's offset to its master, which uses it to track lag.
+    pub fn send_ack(&self) {
+        if let Some(master) = &self.master_sender {
+            let offset = self.offset().to_string();
+            let _ = master.send(vec![Value::command_str("REPLCONF", &["ACK", &offset])]);
+        }
+    }
+
+    /// Number of replicas that acknowledged their offset within the last `max_lag`.
+    pub fn good_replicas(&self, max_lag: Duration) -> usize {
+        self.replicas()
+            .filter(|r| r.lag().as_secs() <= max_lag.as_secs())
+            .count()
+    }
+}
+
+fn send_to_replica(replica: &Replica, bytes: &[u8]) -> bool {
+    let channel = &replica.values_sender;
+    let address = &replica.address;
+    let value = Value::raw(bytes.to_vec());
+
+    match channel.send(vec![value]) {
+        Ok(_) => true,
+        Err(err) => {
+            println!("failed to replicate command to {}: {}", address, err);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn wait_satisfied_by_acks() {
+        let mut manager = ReplicationManager::new(1024);
+        let (replica_sender, _replica_receiver) = mpsc::unbounded_channel();
+        let address = "127.0.0.1:6380".parse().unwrap();
+        manager.add(address, 6380, replica_sender);
+
+        manager.replicate(&set_command());
+        let offset = manager.offset();
+        assert_eq!(manager.acked_replicas(offset), 0);
+
+        let (reply_to, mut reply_receiver) = mpsc::unbounded_channel();
+        manager.block(reply_to, 1, offset, None);
+        manager.request_acks();
+        assert!(reply_receiver.try_recv().is_err());
+
+        manager.ack(address, offset);
+        assert_eq!(reply_receiver.try_recv().unwrap(), vec![Value::integer(1)]);
+        assert_eq!(manager.next_deadline(), None);
+    }
+
+    #[test]
+    fn wait_times_out() {
+        let mut manager = ReplicationManager::new(1024);
+        let deadline = Instant::now();
+
+        let (reply_to, mut reply_receiver) = mpsc::unbounded_channel();
+        manager.block(reply_to, 1, 10, Some(deadline));
+        assert_eq!(manager.next_deadline(), Some(deadline));
+
+        manager.wake_waiters(deadline);
+        assert_eq!(reply_receiver.try_recv().unwrap(), vec![Value::integer(0)]);
+        assert_eq!(manager.next_deadline(), None);
+    }
+
+    #[test]
+    fn good_replicas_skips_disconnected() {
+        let mut manager = ReplicationManager::new(1024);
+        let (connected, _receiver) = mpsc::unbounded_channel();
+        let (disconnected, _) = mpsc::unbounded_channel();
+        manager.add("127.0.0.1:6380".parse().unwrap(), 6380, connected);
+        manager.add("127.0.0.1:6381".parse().unwrap(), 6381, disconnected);
+
+        assert_eq!(manager.good_replicas(Duration::from_secs(10)), 1);
+    }
+
+    #[test]
+    fn continue_from_previous_replid() {
+        let mut manager = ReplicationManager::new(1024);
+        let old_replid = manager.replid().to_string();
+        manager.replicate(&set_command());
+        let offset = manager.offset();
+
+        manager.shift_replid(random::hex_string(40));
+        assert_eq!(manager.replid2(), old_replid);
+        assert_eq!(manager.second_repl_offset(), Some(offset + 1));
+
+        manager.replicate(&set_command());
+        let backlog = manager.continue_from(old_replid.as_bytes(), offset + 1);
+        assert_eq!(backlog, Some(set_command().to_value().to_bytes()));
+        assert_eq!(
+            manager.continue_from(old_replid.as_bytes(), manager.offset() + 1),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn full_resync_loads_the_rdb() {
+        let deadline = UNIX_EPOCH + Duration::from_millis(4_000_000_000_000);
+        let mut database = Database::new();
+        database.set(key("a"), key("1"), Some(deadline));
+        database.set(key("b"), key("2"), None);
+
+        let rdb = rdb::write(&database);
+        let (master, _) = fake_master(vec![
+            Value::simple_string(&format!("FULLRESYNC {} 42", REPLID)),
+            Value::BulkBytes((rdb.len(), rdb)),
+        ])
+        .await;
+
+        match init(&master, 6380, None).await.unwrap().0 {
+            Resync::Full {
+                replid,
+                offset,
+                mut database,
+            } => {
+                assert_eq!(replid, REPLID);
+                assert_eq!(offset, 42);
+                assert_eq!(database.get(&key("a")).unwrap(), Some(key("1")));
+                assert_eq!(database.expires_at(&key("a")), Some(Some(deadline)));
+                assert_eq!(database.expires_at(&key("b")), Some(None));
+            }
+            Resync::Partial { .. } => panic!("expected a full resync"),
+        }
+    }
+
+    #[test]
+    fn reconnect_backoff() {
+        let delays: Vec<_> = std::iter::successors(Some(INITIAL_RECONNECT_DELAY), |delay| {
+            Some(next_reconnect_delay(*delay))
+        })
+        .take(9)
+        .map(|delay| delay.as_millis())
+        .collect();
+
+        assert_eq!(
+            delays,
+            vec![100, 200, 400, 800, 1600, 3200, 5000, 5000, 5000]
+        );
+    }
+
+    #[tokio::test]
+    async fn resume_from_known_offset() {
+        let (master, psync) = fake_master(vec![
+            Value::simple_string("CONTINUE"),
+            Value::command_str("SET", &["a", "1"]),
+        ])
+        .await;
+
+        let (sync, mut reader, _) = init(&master, 6380, Some((REPLID, 100))).await.unwrap();
+
+        let expected = Value::command_str("PSYNC", &[REPLID, "101"]);
+        assert_eq!(psync.await.unwrap().to_bytes(), expected.to_bytes());
+
+        match sync {
+            Resync::Partial { replid } => assert_eq!(replid, REPLID),
+            Resync::Full { .. } => panic!("expected a partial resync"),
+        }
+
+        // no RDB follows +CONTINUE: the stream goes straight on with commands
+        let command = reader.read().await.unwrap();
+        assert_eq!(
+            command.to_bytes(),
+            Value::command_str("SET", &["a", "1"]).to_bytes()
+        );
+    }
+
+    const REPLID: &str = "8371b4fb1155b71f4a04d3e1bc3e18c4a990aeeb";
+
+    /// Accepts a single replica and answers its handshake like a master, sending `replies`
+    /// after PSYNC. The task's output is the PSYNC command the replica sent.
+    async fn fake_master(replies: Vec<Value>) -> (String, JoinHandle<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let task = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, writer) = stream.into_split();
+            let mut reader = ValueReader::new(BufReader::new(reader));
+            let mut writer = ValueWriter::new(BufWriter::new(writer));
+
+            for reply in ["PONG", "OK", "OK"] {
+                reader.read().await.unwrap();
+                writer.write(&Value::simple_string(reply)).await.unwrap();
+            }
+
+            let psync = reader.read().await.unwrap();
+
+            for reply in replies {
+                writer.write(&reply).await.unwrap();
+            }
+
+            psync
+        });
+
+        (address, task)
+    }
+
+    fn key(key: &str) -> Vec<u8> {
+        key.as_bytes().to_vec()
+    }
+
+    fn set_command() -> Command {
+        Command::Set {
+            size: 0,
+            key: "key".as_bytes().to_vec(),
+            value: "value".as_bytes().to_vec(),
+            condition: None,
+            expiry: None,
+            get: false,
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use super::{
+    database::{Data, Set, WrongType},
+    random, CommandError, CommandResult, Server,
+};
+use crate::protocol::{Command, Value};
+
+/// How SINTER, SUNION and SDIFF combine their sets.
+#[derive(Clone, Copy)]
+pub(super) enum SetOperation {
+    Intersection,
+    Union,
+    Difference,
+}
+
+impl Server {
+    pub(super) fn sadd(&mut self, key: Vec<u8>, members: Vec<Vec<u8>>) -> CommandResult {
+        let set = self.database.members_or_insert(&key)?;
+        let added = members
+            .into_iter()
+            .filter(|member| set.insert(member.clone()))
+            .count();
+
+        Ok(vec![Value::integer(added as i64)])
+    }
+
+    pub(super) fn srem(&mut self, key: Vec<u8>, members: Vec<Vec<u8>>) -> CommandResult {
+        let Some(set) = self.database.members(&key)? else {
+            return Ok(vec![Value::integer(0)]);
+        };
+
+        let removed = members.iter().filter(|member| set.remove(*member)).count();
+
+        self.database.remove_if_empty(&key);
+        Ok(vec![Value::integer(removed as i64)])
+    }
+
+    pub(super) fn smembers(&mut self, key: Vec<u8>) -> CommandResult {
+        let members = match self.database.members(&key)? {
+            Some(set) => set.iter().cloned().collect(),
+            None => vec![],
+        };
+
+        Ok(vec![members_array(members)])
+    }
+
+    pub(super) fn sismember(&mut self, key: Vec<u8>, member: Vec<u8>) -> CommandResult {
+        let exists = self
+            .database
+            .members(&key)?
+            .is_some_and(|set| set.contains(&member));
+
+        Ok(vec![Value::integer(exists as i64)])
+    }
+
+    pub(super) fn smismember(&mut self, key: Vec<u8>, members: Vec<Vec<u8>>) -> CommandResult {
+        let set = self.database.members(&key)?;
+
+        let exists = members
+            .iter()
+            .map(|member| set.as_ref().is_some_and(|set| set.contains(member)))
+            .map(|exists| Value::integer(exists as i64))
+            .collect();
+
+        Ok(vec![Value::array(exists)])
+    }
+
+    pub(super) fn scard(&mut self, key: Vec<u8>) -> CommandResult {
+        let len = self.database.members(&key)?.map_or(0, |set| set.len());
+        Ok(vec![Value::integer(len as i64)])
+    }
+
+    /// Removes random members, propagated as an SREM of the members that were picked so
+    /// replicas remove the same ones.
+    pub(super) fn spop(&mut self, key: Vec<u8>, count: Option<usize>) -> CommandResult {
+        let Some(set) = self.database.members(&key)? else {
+            self.propagate_as = Some(vec![]);
+
+            return Ok(vec![match count {
+                Some(_) => Value::array(vec![]),
+                None => Value::NullBulkString,
+            }]);
+        };
+
+        let popped = pick_distinct(set, count.unwrap_or(1));
+        popped.iter().for_each(|member| {
+            set.remove(member);
+        });
+
+        self.database.remove_if_empty(&key);
+
+        let srem = Command::Srem {
+            size: 0,
+            key,
+            members: popped.clone(),
+        };
+        self.propagate_as = Some(if popped.is_empty() {
+            vec![]
+        } else {
+            vec![srem]
+        });
+
+        Ok(vec![match count {
+            Some(_) => members_array(popped),
+            None => match popped.into_iter().next() {
+                Some(member) => Value::bulk_string_from_bytes(member),
+                None => Value::NullBulkString,
+            },
+        }])
+    }
+
+    /// Picks random members: up to `count` distinct ones, or exactly `-count` members that may
+    /// repeat when it's negative. Like Redis, this rejects negative counts below
+    /// `-(i64::MAX / 2)` rather than trying to build a reply that large.
+    pub(super) fn srandmember(&mut self, key: Vec<u8>, count: Option<i64>) -> CommandResult {
+        if count.is_some_and(|count| count < -(i64::MAX / 2)) {
+            return Err(CommandError::Other("ERR value is out of range".to_string()));
+        }
+
+        let set = self.database.members(&key)?;
+
+        let members = match (set, count) {
+            (Some(set), Some(count)) if count < 0 => pick_with_repeats(set, count.unsigned_abs()),
+            (Some(set), Some(count)) => pick_distinct(set, count as usize),
+            (Some(set), None) => pick_distinct(set, 1),
+            (None, _) => vec![],
+        };
+
+        Ok(vec![match count {
+            Some(_) => members_array(members),
+            None => match members.into_iter().next() {
+                Some(member) => Value::bulk_string_from_bytes(member),
+                None => Value::NullBulkString,
+            },
+        }])
+    }
+
+    pub(super) fn set_operation(
+        &mut self,
+        keys: Vec<Vec<u8>>,
+        operation: SetOperation,
+    ) -> CommandResult {
+        let result = self.combine_sets(&keys, operation)?;
+        Ok(vec![members_array(result.into_iter().collect())])
+    }
+
+    /// Stores the result of a set operation at `destination`, replacing whatever was there.
+    pub(super) fn set_operation_store(
+        &mut self,
+        destination: Vec<u8>,
+        keys: Vec<Vec<u8>>,
+        operation: SetOperation,
+    ) -> CommandResult {
+        let result = self.combine_sets(&keys, operation)?;
+        let len = result.len();
+
+        if result.is_empty() {
+            self.database.remove(&destination);
+        } else {
+            self.database.insert(destination, Data::Set(result), None);
+        }
+
+        Ok(vec![Value::integer(len as i64)])
+    }
+
+    /// Combines the sets at `keys`, where missing keys count as empty sets.
+    fn combine_sets(
+        &mut self,
+        keys: &[Vec<u8>],
+        operation: SetOperation,
+    ) -> Result<Set, WrongType> {
+        let mut sets = Vec::with_capacity(keys.len());
+
+        for key in keys {
+            sets.push(
+                self.database
+                    .members(key)?
+                    .map(|set| set.clone())
+                    .unwrap_or_default(),
+            );
+        }
+
+        let mut sets = sets.into_iter();
+        let first = sets.next().unwrap_or_default();
+
+        Ok(sets.fold(first, |result, set| match operation {
+            SetOperation::Intersection => result.intersection(&set).cloned().collect(),
+            SetOperation::Union => result.union(&set).cloned().collect(),
+            SetOperation::Difference => result.difference(&set).cloned().collect(),
+        }))
+    }
+}
+
+/// Picks up to `count` distinct members at random.
+fn pick_distinct(set: &Set, count: usize) -> Vec<Vec<u8>> {
+    let mut members: Vec<_> = set.iter().collect();
+    let count = count.min(members.len());
+
+    // a partial Fisher-Yates shuffle, moving the picks to the front
+    for index in 0..count {
+        let pick = index + random_index(members.len() - index);
+        members.swap(index, pick);
+    }
+
+    members.into_iter().take(count).cloned().collect()
+}
+
+/// Picks `count` random members of a non-empty set, with repeats.
+fn pick_with_repeats(set: &Set, count: u64) -> Vec<Vec<u8>> {
+    let members: Vec<_> = set.iter().collect();
+
+    // grown as members are picked, as `count` comes straight from the client
+    let mut picked = Vec::new();
+
+    for _ in 0..count {
+        picked.push(members[random_index(members.len())].clone());
+    }
+
+    picked
+}
+
+fn random_index(len: usize) -> usize {
+    (random::next_u64() % len as u64) as usize
+}
+
+fn members_array(members: Vec<Vec<u8>>) -> Value {
+    Value::array(
+        members
+            .into_iter()
+            .map(Value::bulk_string_from_bytes)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pick_distinct_members() {
+        let set: Set = (0..10).map(|i| i.to_string().into_bytes()).collect();
+
+        let picked = pick_distinct(&set, 4);
+        let unique: Set = picked.iter().cloned().collect();
+        assert_eq!(picked.len(), 4);
+        assert_eq!(unique.len(), 4);
+        assert!(picked.iter().all(|member| set.contains(member)));
+
+        assert_eq!(pick_distinct(&set, 20).len(), 10);
+        assert!(pick_distinct(&Set::new(), 1).is_empty());
+    }
+
+    #[test]
+    fn pick_members_with_repeats() {
+        let set: Set = (0..2).map(|i| i.to_string().into_bytes()).collect();
+
+        let picked = pick_with_repeats(&set, 5);
+        assert_eq!(picked.len(), 5);
+        assert!(picked.iter().all(|member| set.contains(member)));
+    }
+}
@@ -0,0 +1,439 @@
+use super::random;
+use std::collections::HashMap;
+
+/// The most levels a skiplist node can have, enough for 2^64 elements with P = 1/4.
+const MAX_LEVEL: usize = 32;
+
+/// The skiplist's head node, which holds no element.
+const HEAD: usize = 0;
+
+/// Members ordered by score, then by member for equal scores, like a Redis sorted set.
+///
+/// Scores are looked up by member in a hash map, while order and ranks come from a skiplist
+/// whose links also record how many elements they skip, so finding an element's rank or the
+/// element at a rank takes O(log n).
+#[derive(Clone, Debug, Default)]
+pub struct SortedSet {
+    scores: HashMap<Vec<u8>, f64>,
+    skiplist: SkipList,
+}
+
+impl SortedSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    /// Adds `member` or updates its score. Returns the previous score, if any.
+    pub fn insert(&mut self, member: Vec<u8>, score: f64) -> Option<f64> {
+        let previous = self.scores.insert(member.clone(), score);
+
+        if let Some(previous) = previous {
+            if previous == score {
+                return Some(previous);
+            }
+
+            self.skiplist.remove(previous, &member);
+        }
+
+        self.skiplist.insert(score, member);
+        previous
+    }
+
+    /// Removes `member`, returning its score if it was there.
+    pub fn remove(&mut self, member: &[u8]) -> Option<f64> {
+        let score = self.scores.remove(member)?;
+        self.skiplist.remove(score, member);
+        Some(score)
+    }
+
+    /// The 0-based position of `member` in score order.
+    pub fn rank(&self, member: &[u8]) -> Option<usize> {
+        let score = self.score(member)?;
+        self.skiplist.rank(score, member)
+    }
+
+    /// How many elements, from the lowest, satisfy `predicate`. The predicate must hold for
+    /// a prefix of the set and not after it, like "score is less than 5".
+    pub fn count_while(&self, predicate: impl Fn(f64, &[u8]) -> bool) -> usize {
+        self.skiplist.count_while(predicate)
+    }
+
+    /// The elements whose rank is in `start..end`, in score order.
+    pub fn range(&self, start: usize, end: usize) -> Vec<(Vec<u8>, f64)> {
+        self.skiplist
+            .iter_from(start)
+            .take(end.saturating_sub(start))
+            .map(|(member, score)| (member.to_vec(), score))
+            .collect()
+    }
+
+    /// Every element in score order.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], f64)> {
+        self.skiplist.iter_from(0)
+    }
+}
+
+impl PartialEq for SortedSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.scores == other.scores
+    }
+}
+
+impl FromIterator<(Vec<u8>, f64)> for SortedSet {
+    fn from_iter<T: IntoIterator<Item = (Vec<u8>, f64)>>(iter: T) -> Self {
+        let mut set = SortedSet::new();
+
+        for (member, score) in iter {
+            set.insert(member, score);
+        }
+
+        set
+    }
+}
+
+/// A skiplist kept in an arena, with links as indexes into `nodes`. Removed nodes are reused.
+#[derive(Clone, Debug)]
+struct SkipList {
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    level: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    member: Vec<u8>,
+    score: f64,
+    levels: Vec<Level>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Level {
+    forward: Option<usize>,
+    /// How many elements the link moves forward by, counting the one it lands on.
+    span: usize,
+}
+
+impl Default for SkipList {
+    fn default() -> Self {
+        let head = Node {
+            member: vec![],
+            score: 0.0,
+            levels: vec![Level::default(); MAX_LEVEL],
+        };
+
+        Self {
+            nodes: vec![head],
+            free: vec![],
+            level: 1,
+        }
+    }
+}
+
+impl SkipList {
+    /// Inserts an element that isn't in the list yet.
+    fn insert(&mut self, score: f64, member: Vec<u8>) {
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut rank = [0; MAX_LEVEL];
+        let mut x = HEAD;
+
+        for i in (0..self.level).rev() {
+            rank[i] = if i == self.level - 1 { 0 } else { rank[i + 1] };
+
+            while let Some(next) = self.nodes[x].levels[i].forward {
+                if !self.nodes[next].is_before(score, &member) {
+                    break;
+                }
+
+                rank[i] += self.nodes[x].levels[i].span;
+                x = next;
+            }
+
+            update[i] = x;
+        }
+
+        let level = random_level();
+
+        if level > self.level {
+            for i in self.level..level {
+                rank[i] = 0;
+                update[i] = HEAD;
+                self.nodes[HEAD].levels[i].span = self.len();
+            }
+
+            self.level = level;
+        }
+
+        let node = self.allocate(Node {
+            member,
+            score,
+            levels: vec![Level::default(); level],
+        });
+
+        for i in 0..level {
+            let previous = self.nodes[update[i]].levels[i];
+            let skipped = rank[0] - rank[i];
+
+            self.nodes[node].levels[i] = Level {
+                forward: previous.forward,
+                span: previous.span - skipped,
+            };
+
+            self.nodes[update[i]].levels[i] = Level {
+                forward: Some(node),
+                span: skipped + 1,
+            };
+        }
+
+        for (i, &x) in update.iter().enumerate().take(self.level).skip(level) {
+            self.nodes[x].levels[i].span += 1;
+        }
+    }
+
+    /// Removes an element, returning whether it was there.
+    fn remove(&mut self, score: f64, member: &[u8]) -> bool {
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut x = HEAD;
+
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.nodes[x].levels[i].forward {
+                if !self.nodes[next].is_before(score, member) {
+                    break;
+                }
+
+                x = next;
+            }
+
+            update[i] = x;
+        }
+
+        let node = match self.nodes[x].levels[0].forward {
+            Some(node) if self.nodes[node].is(score, member) => node,
+            _ => return false,
+        };
+
+        for (i, &x) in update.iter().enumerate().take(self.level) {
+            let removed = self.nodes[node].levels.get(i).copied();
+            let previous = &mut self.nodes[x].levels[i];
+
+            match removed {
+                Some(removed) if previous.forward == Some(node) => {
+                    previous.span += removed.span;
+                    previous.span -= 1;
+                    previous.forward = removed.forward;
+                }
+                _ => previous.span -= 1,
+            }
+        }
+
+        while self.level > 1 && self.nodes[HEAD].levels[self.level - 1].forward.is_none() {
+            self.level -= 1;
+        }
+
+        self.nodes[node].member = vec![];
+        self.free.push(node);
+        true
+    }
+
+    /// The 0-based rank of an element in the list.
+    fn rank(&self, score: f64, member: &[u8]) -> Option<usize> {
+        let mut rank = 0;
+        let mut x = HEAD;
+
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.nodes[x].levels[i].forward {
+                let node = &self.nodes[next];
+
+                if !node.is_before(score, member) && !node.is(score, member) {
+                    break;
+                }
+
+                rank += self.nodes[x].levels[i].span;
+                x = next;
+            }
+
+            if x != HEAD && self.nodes[x].is(score, member) {
+                return Some(rank - 1);
+            }
+        }
+
+        None
+    }
+
+    fn count_while(&self, predicate: impl Fn(f64, &[u8]) -> bool) -> usize {
+        let mut count = 0;
+        let mut x = HEAD;
+
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.nodes[x].levels[i].forward {
+                let node = &self.nodes[next];
+
+                if !predicate(node.score, &node.member) {
+                    break;
+                }
+
+                count += self.nodes[x].levels[i].span;
+                x = next;
+            }
+        }
+
+        count
+    }
+
+    /// The node at a 0-based rank.
+    fn node_at(&self, rank: usize) -> Option<usize> {
+        let target = rank + 1;
+        let mut traversed = 0;
+        let mut x = HEAD;
+
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.nodes[x].levels[i].forward {
+                if traversed + self.nodes[x].levels[i].span > target {
+                    break;
+                }
+
+                traversed += self.nodes[x].levels[i].span;
+                x = next;
+            }
+
+            if traversed == target {
+                return Some(x);
+            }
+        }
+
+        None
+    }
+
+    fn iter_from(&self, rank: usize) -> impl Iterator<Item = (&[u8], f64)> {
+        let mut next = self.node_at(rank);
+
+        std::iter::from_fn(move || {
+            let node = &self.nodes[next?];
+            next = node.levels[0].forward;
+            Some((node.member.as_slice(), node.score))
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.nodes.len() - 1 - self.free.len()
+    }
+
+    fn allocate(&mut self, node: Node) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+}
+
+impl Node {
+    /// Whether this node comes before the element with `score` and `member`.
+    fn is_before(&self, score: f64, member: &[u8]) -> bool {
+        self.score < score || (self.score == score && self.member.as_slice() < member)
+    }
+
+    fn is(&self, score: f64, member: &[u8]) -> bool {
+        self.score == score && self.member == member
+    }
+}
+
+/// A random level where each level is 4 times less likely than the one below.
+fn random_level() -> usize {
+    let bits = random::next_u64();
+    let level = 1 + (bits.trailing_zeros() / 2) as usize;
+    level.min(MAX_LEVEL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ordered_by_score_then_member() {
+        let set = sorted_set(&[("b", 2.0), ("a", 2.0), ("c", 1.0), ("d", -1.5)]);
+        assert_eq!(members(&set), vec!["d", "c", "a", "b"]);
+    }
+
+    #[test]
+    fn update_score() {
+        let mut set = sorted_set(&[("a", 1.0), ("b", 2.0), ("c", 3.0)]);
+
+        assert_eq!(set.insert(key("a"), 5.0), Some(1.0));
+        assert_eq!(members(&set), vec!["b", "c", "a"]);
+        assert_eq!(set.rank(b"a"), Some(2));
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn remove_members() {
+        let mut set = sorted_set(&[("a", 1.0), ("b", 2.0), ("c", 3.0)]);
+
+        assert_eq!(set.remove(b"b"), Some(2.0));
+        assert_eq!(set.remove(b"b"), None);
+        assert_eq!(members(&set), vec!["a", "c"]);
+        assert_eq!(set.rank(b"c"), Some(1));
+
+        set.insert(key("d"), 0.0);
+        assert_eq!(members(&set), vec!["d", "a", "c"]);
+    }
+
+    #[test]
+    fn ranks_and_ranges_of_a_large_set() {
+        let mut set = SortedSet::new();
+
+        for i in (0..1000).rev() {
+            set.insert(format!("m{:04}", i).into_bytes(), i as f64);
+        }
+
+        for i in (0..1000).step_by(2) {
+            set.remove(format!("m{:04}", i).as_bytes());
+        }
+
+        assert_eq!(set.len(), 500);
+        assert_eq!(set.rank(b"m0001"), Some(0));
+        assert_eq!(set.rank(b"m0999"), Some(499));
+        assert_eq!(set.rank(b"m0501"), Some(250));
+        assert_eq!(set.rank(b"m0500"), None);
+
+        let range = set.range(250, 252);
+        assert_eq!(range, vec![(key("m0501"), 501.0), (key("m0503"), 503.0)]);
+        assert!(set.range(500, 510).is_empty());
+
+        assert_eq!(set.count_while(|score, _| score < 101.0), 50);
+        assert_eq!(set.count_while(|score, _| score <= 101.0), 51);
+    }
+
+    fn sorted_set(elements: &[(&str, f64)]) -> SortedSet {
+        elements
+            .iter()
+            .map(|(member, score)| (key(member), *score))
+            .collect()
+    }
+
+    fn members(set: &SortedSet) -> Vec<String> {
+        set.iter()
+            .map(|(member, _)| String::from_utf8_lossy(member).to_string())
+            .collect()
+    }
+
+    fn key(key: &str) -> Vec<u8> {
+        key.as_bytes().to_vec()
+    }
+}
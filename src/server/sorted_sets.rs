@@ -0,0 +1,428 @@
+use super::{
+    database::Data, lists, number, sets::SetOperation, sorted_set::SortedSet, CommandError,
+    CommandResult, Server,
+};
+use crate::protocol::{Aggregate, LexBound, ScoreBound, Value, ZaddFlags, ZrangeBy};
+use std::collections::HashMap;
+
+impl Server {
+    pub(super) fn zadd(
+        &mut self,
+        key: Vec<u8>,
+        flags: ZaddFlags,
+        elements: Vec<(f64, Vec<u8>)>,
+    ) -> CommandResult {
+        // XX only updates, so it never creates the key
+        if flags.xx && self.database.sorted_set(&key)?.is_none() {
+            return Ok(vec![match flags.incr {
+                true => Value::NullBulkString,
+                false => Value::integer(0),
+            }]);
+        }
+
+        let set = self.database.sorted_set_or_insert(&key)?;
+        let mut added = 0;
+        let mut updated = 0;
+        let mut last_score = None;
+        let mut result = Ok(());
+
+        for (score, member) in elements {
+            let current = set.score(&member);
+            let score = match (flags.incr, current) {
+                (true, Some(current)) => current + score,
+                _ => score,
+            };
+
+            if score.is_nan() {
+                result = Err(not_a_number());
+                break;
+            }
+
+            match current {
+                Some(_) if flags.nx => continue,
+                None if flags.xx => continue,
+                // GT and LT only restrict updates; new members are always added
+                Some(current) if flags.gt && score <= current => continue,
+                Some(current) if flags.lt && score >= current => continue,
+                Some(current) => {
+                    if score != current {
+                        set.insert(member, score);
+                        updated += 1;
+                    }
+                }
+                None => {
+                    set.insert(member, score);
+                    added += 1;
+                }
+            }
+
+            last_score = Some(score);
+        }
+
+        self.database.remove_if_empty(&key);
+        result?;
+
+        Ok(vec![if flags.incr {
+            score_or_null(last_score)
+        } else if flags.ch {
+            Value::integer(added + updated)
+        } else {
+            Value::integer(added)
+        }])
+    }
+
+    pub(super) fn zrange(
+        &mut self,
+        key: Vec<u8>,
+        by: ZrangeBy,
+        rev: bool,
+        limit: Option<(i64, i64)>,
+        withscores: bool,
+    ) -> CommandResult {
+        let Some(set) = self.database.sorted_set(&key)? else {
+            return Ok(vec![Value::array(vec![])]);
+        };
+
+        let len = set.len();
+        let (start, end) = match by {
+            ZrangeBy::Rank { start, stop } => match lists::range(len, start, stop) {
+                // ranks given with REV count from the highest score
+                Some((start, stop)) if rev => (len - 1 - stop, len - start),
+                Some((start, stop)) => (start, stop + 1),
+                None => (0, 0),
+            },
+            ZrangeBy::Score { min, max } => score_range(set, &min, &max),
+            ZrangeBy::Lex { min, max } => lex_range(set, &min, &max),
+        };
+
+        let (start, end) = match limit {
+            Some((offset, count)) => limit_range(start, end, offset, count, rev),
+            None => (start, end),
+        };
+
+        let mut elements = set.range(start, end);
+
+        if rev {
+            elements.reverse();
+        }
+
+        let mut values = Vec::with_capacity(elements.len() * 2);
+
+        for (member, score) in elements {
+            values.push(Value::bulk_string_from_bytes(member));
+
+            if withscores {
+                values.push(score_value(score));
+            }
+        }
+
+        Ok(vec![Value::array(values)])
+    }
+
+    pub(super) fn zrank(&mut self, key: Vec<u8>, member: Vec<u8>) -> CommandResult {
+        let rank = self
+            .database
+            .sorted_set(&key)?
+            .and_then(|set| set.rank(&member));
+
+        Ok(vec![match rank {
+            Some(rank) => Value::integer(rank as i64),
+            None => Value::NullBulkString,
+        }])
+    }
+
+    pub(super) fn zscore(&mut self, key: Vec<u8>, member: Vec<u8>) -> CommandResult {
+        let score = self
+            .database
+            .sorted_set(&key)?
+            .and_then(|set| set.score(&member));
+
+        Ok(vec![score_or_null(score)])
+    }
+
+    pub(super) fn zcard(&mut self, key: Vec<u8>) -> CommandResult {
+        let len = self.database.sorted_set(&key)?.map_or(0, |set| set.len());
+        Ok(vec![Value::integer(len as i64)])
+    }
+
+    pub(super) fn zincrby(
+        &mut self,
+        key: Vec<u8>,
+        increment: f64,
+        member: Vec<u8>,
+    ) -> CommandResult {
+        let set = self.database.sorted_set_or_insert(&key)?;
+        let score = set.score(&member).unwrap_or(0.0) + increment;
+
+        if score.is_nan() {
+            self.database.remove_if_empty(&key);
+            return Err(not_a_number());
+        }
+
+        set.insert(member, score);
+        Ok(vec![score_value(score)])
+    }
+
+    pub(super) fn zrem(&mut self, key: Vec<u8>, members: Vec<Vec<u8>>) -> CommandResult {
+        let Some(set) = self.database.sorted_set(&key)? else {
+            return Ok(vec![Value::integer(0)]);
+        };
+
+        let removed = members
+            .iter()
+            .filter(|member| set.remove(member).is_some())
+            .count();
+
+        self.database.remove_if_empty(&key);
+        Ok(vec![Value::integer(removed as i64)])
+    }
+
+    pub(super) fn zcount(
+        &mut self,
+        key: Vec<u8>,
+        min: ScoreBound,
+        max: ScoreBound,
+    ) -> CommandResult {
+        let count = match self.database.sorted_set(&key)? {
+            Some(set) => {
+                let (start, end) = score_range(set, &min, &max);
+                end - start
+            }
+            None => 0,
+        };
+
+        Ok(vec![Value::integer(count as i64)])
+    }
+
+    pub(super) fn zpopmin(&mut self, key: Vec<u8>, count: Option<usize>) -> CommandResult {
+        let Some(set) = self.database.sorted_set(&key)? else {
+            return Ok(vec![Value::array(vec![])]);
+        };
+
+        let popped = set.range(0, count.unwrap_or(1));
+        let mut values = Vec::with_capacity(popped.len() * 2);
+
+        for (member, score) in popped {
+            set.remove(&member);
+            values.push(Value::bulk_string_from_bytes(member));
+            values.push(score_value(score));
+        }
+
+        self.database.remove_if_empty(&key);
+        Ok(vec![Value::array(values)])
+    }
+
+    /// Stores the union or intersection of the sorted sets, or plain sets, at `keys` in
+    /// `destination`, replacing whatever was there.
+    pub(super) fn zstore(
+        &mut self,
+        destination: Vec<u8>,
+        keys: Vec<Vec<u8>>,
+        weights: Option<Vec<f64>>,
+        aggregate: Aggregate,
+        operation: SetOperation,
+    ) -> CommandResult {
+        let mut inputs = Vec::with_capacity(keys.len());
+
+        for key in &keys {
+            inputs.push(self.database.scores(key)?.unwrap_or_default());
+        }
+
+        let weights = weights.unwrap_or_else(|| vec![1.0; keys.len()]);
+        let mut result: Option<HashMap<Vec<u8>, f64>> = None;
+
+        for (input, weight) in inputs.into_iter().zip(weights) {
+            let input = input
+                .into_iter()
+                .map(|(member, score)| (member, weighted(score, weight)));
+
+            result = Some(match (result, operation) {
+                (None, _) => input.collect(),
+                (Some(mut result), SetOperation::Intersection) => input
+                    .filter_map(|(member, score)| {
+                        let current = result.remove(&member)?;
+                        Some((member, combine(current, score, aggregate)))
+                    })
+                    .collect(),
+                (Some(mut result), _) => {
+                    for (member, score) in input {
+                        let score = match result.get(&member) {
+                            Some(current) => combine(*current, score, aggregate),
+                            None => score,
+                        };
+                        result.insert(member, score);
+                    }
+
+                    result
+                }
+            });
+        }
+
+        let result: SortedSet = result.unwrap_or_default().into_iter().collect();
+        let len = result.len();
+
+        if result.is_empty() {
+            self.database.remove(&destination);
+        } else {
+            self.database
+                .insert(destination, Data::SortedSet(result), None);
+        }
+
+        Ok(vec![Value::integer(len as i64)])
+    }
+}
+
+/// The ranks of the elements with scores between `min` and `max`, as a `start..end` range.
+fn score_range(set: &SortedSet, min: &ScoreBound, max: &ScoreBound) -> (usize, usize) {
+    let start = set.count_while(|score, _| match min.exclusive {
+        true => score <= min.value,
+        false => score < min.value,
+    });
+    let end = set.count_while(|score, _| match max.exclusive {
+        true => score < max.value,
+        false => score <= max.value,
+    });
+
+    (start, end.max(start))
+}
+
+/// The ranks of the members between `min` and `max`, as a `start..end` range. Like in Redis,
+/// this only makes sense when every member has the same score.
+fn lex_range(set: &SortedSet, min: &LexBound, max: &LexBound) -> (usize, usize) {
+    let start = set.count_while(|_, member| match min {
+        LexBound::Lowest => false,
+        LexBound::Highest => true,
+        LexBound::Inclusive(min) => member < min.as_slice(),
+        LexBound::Exclusive(min) => member <= min.as_slice(),
+    });
+    let end = set.count_while(|_, member| match max {
+        LexBound::Lowest => false,
+        LexBound::Highest => true,
+        LexBound::Inclusive(max) => member <= max.as_slice(),
+        LexBound::Exclusive(max) => member < max.as_slice(),
+    });
+
+    (start, end.max(start))
+}
+
+/// Narrows a `start..end` range of ranks to a LIMIT, where the offset counts from the end of
+/// the range with REV, and a negative count means no limit.
+fn limit_range(start: usize, end: usize, offset: i64, count: i64, rev: bool) -> (usize, usize) {
+    if offset < 0 {
+        return (start, start);
+    }
+
+    let len = end - start;
+    let offset = (offset as usize).min(len);
+    let count = match count {
+        count if count < 0 => len - offset,
+        count => (count as usize).min(len - offset),
+    };
+
+    match rev {
+        true => (end - offset - count, end - offset),
+        false => (start + offset, start + offset + count),
+    }
+}
+
+/// A score multiplied by a ZUNIONSTORE or ZINTERSTORE weight, where 0 times infinity is 0.
+fn weighted(score: f64, weight: f64) -> f64 {
+    match score * weight {
+        score if score.is_nan() => 0.0,
+        score => score,
+    }
+}
+
+/// Combines the scores of a member found in several sets. Adding opposite infinities gives 0.
+fn combine(current: f64, score: f64, aggregate: Aggregate) -> f64 {
+    match aggregate {
+        Aggregate::Sum => match current + score {
+            sum if sum.is_nan() => 0.0,
+            sum => sum,
+        },
+        Aggregate::Min => current.min(score),
+        Aggregate::Max => current.max(score),
+    }
+}
+
+fn score_value(score: f64) -> Value {
+    Value::bulk_string(&number::format_float(score))
+}
+
+fn score_or_null(score: Option<f64>) -> Value {
+    match score {
+        Some(score) => score_value(score),
+        None => Value::NullBulkString,
+    }
+}
+
+fn not_a_number() -> CommandError {
+    CommandError::Other("ERR resulting score is not a number (NaN)".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_ranges() {
+        let set: SortedSet = [("a", 1.0), ("b", 2.0), ("c", 2.0), ("d", 3.0)]
+            .into_iter()
+            .map(|(member, score)| (member.as_bytes().to_vec(), score))
+            .collect();
+
+        let bound = |value, exclusive| ScoreBound { value, exclusive };
+
+        assert_eq!(
+            score_range(&set, &bound(2.0, false), &bound(2.0, false)),
+            (1, 3)
+        );
+        assert_eq!(
+            score_range(&set, &bound(1.0, true), &bound(3.0, true)),
+            (1, 3)
+        );
+        assert_eq!(
+            score_range(
+                &set,
+                &bound(f64::NEG_INFINITY, false),
+                &bound(f64::INFINITY, false)
+            ),
+            (0, 4)
+        );
+        assert_eq!(
+            score_range(&set, &bound(3.0, false), &bound(1.0, false)),
+            (3, 3)
+        );
+    }
+
+    #[test]
+    fn lex_ranges() {
+        let set: SortedSet = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|member| (member.as_bytes().to_vec(), 0.0))
+            .collect();
+
+        let inclusive = |member: &str| LexBound::Inclusive(member.as_bytes().to_vec());
+        let exclusive = |member: &str| LexBound::Exclusive(member.as_bytes().to_vec());
+
+        assert_eq!(
+            lex_range(&set, &LexBound::Lowest, &LexBound::Highest),
+            (0, 4)
+        );
+        assert_eq!(lex_range(&set, &inclusive("b"), &exclusive("d")), (1, 3));
+        assert_eq!(lex_range(&set, &exclusive("b"), &LexBound::Highest), (2, 4));
+        assert_eq!(
+            lex_range(&set, &LexBound::Highest, &LexBound::Lowest),
+            (4, 4)
+        );
+    }
+
+    #[test]
+    fn limits() {
+        assert_eq!(limit_range(2, 10, 1, 3, false), (3, 6));
+        assert_eq!(limit_range(2, 10, 1, 3, true), (6, 9));
+        assert_eq!(limit_range(2, 10, 5, -1, false), (7, 10));
+        assert_eq!(limit_range(2, 10, 20, 5, false), (10, 10));
+        assert_eq!(limit_range(2, 10, -1, 5, false), (2, 2));
+    }
+}
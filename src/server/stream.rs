@@ -0,0 +1,392 @@
+use super::consumer_group::ConsumerGroup;
+use crate::protocol::{StreamId, StreamTrim, XaddId};
+use std::collections::BTreeMap;
+
+/// The field-value pairs of a stream entry, in the order they were given.
+pub type Fields = Vec<(Vec<u8>, Vec<u8>)>;
+
+/// Why a new entry can't get the ID it asked for.
+#[derive(Debug, thiserror::Error)]
+pub enum StreamIdError {
+    #[error("ERR The ID specified in XADD must be greater than 0-0")]
+    Zero,
+
+    #[error("ERR The ID specified in XADD is equal or smaller than the target stream top item")]
+    NotIncreasing,
+
+    #[error("ERR The stream has exhausted the last possible ID, unable to add more items")]
+    Exhausted,
+}
+
+/// An append-only log of entries ordered by ID, like a Redis stream.
+///
+/// Besides its entries, a stream remembers the last ID it handed out, so IDs keep increasing
+/// after the newest entries are deleted, a few counters reported by XINFO, and its consumer
+/// groups by name.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stream {
+    entries: BTreeMap<StreamId, Fields>,
+    last_id: StreamId,
+    max_deleted_id: StreamId,
+    entries_added: u64,
+    groups: BTreeMap<Vec<u8>, ConsumerGroup>,
+}
+
+impl Stream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    /// The largest ID of an entry removed by XDEL.
+    pub fn max_deleted_id(&self) -> StreamId {
+        self.max_deleted_id
+    }
+
+    /// How many entries were ever added, including the ones removed since.
+    pub fn entries_added(&self) -> u64 {
+        self.entries_added
+    }
+
+    /// The ID of the newest entry still in the stream.
+    pub fn top_id(&self) -> Option<StreamId> {
+        self.entries.keys().next_back().copied()
+    }
+
+    /// The ID of the oldest entry, or 0-0 if the stream is empty.
+    pub fn first_id(&self) -> StreamId {
+        self.entries.keys().next().copied().unwrap_or(StreamId::MIN)
+    }
+
+    pub fn entry(&self, id: StreamId) -> Option<&Fields> {
+        self.entries.get(&id)
+    }
+
+    /// Resolves the ID for a new entry, which must be greater than every ID handed out so far.
+    /// `*` takes the current time, or stays on the last ID's millisecond if the clock is behind.
+    pub fn next_id(&self, requested: XaddId, now_ms: u64) -> Result<StreamId, StreamIdError> {
+        if self.last_id == StreamId::MAX {
+            return Err(StreamIdError::Exhausted);
+        }
+
+        let id = match requested {
+            XaddId::Auto if now_ms > self.last_id.ms => StreamId { ms: now_ms, seq: 0 },
+            XaddId::Auto => self.last_id.next().ok_or(StreamIdError::Exhausted)?,
+            XaddId::AutoSequence(ms) if ms == self.last_id.ms => {
+                let seq = self.last_id.seq.checked_add(1);
+                StreamId {
+                    ms,
+                    seq: seq.ok_or(StreamIdError::NotIncreasing)?,
+                }
+            }
+            XaddId::AutoSequence(ms) => StreamId { ms, seq: 0 },
+            XaddId::Explicit(id) => id,
+        };
+
+        if id == StreamId::MIN {
+            return Err(StreamIdError::Zero);
+        }
+
+        if id <= self.last_id {
+            return Err(StreamIdError::NotIncreasing);
+        }
+
+        Ok(id)
+    }
+
+    /// Appends an entry with an ID from [`next_id`](Self::next_id).
+    pub fn add(&mut self, id: StreamId, fields: Fields) {
+        debug_assert!(id > self.last_id, "stream IDs must increase");
+
+        self.entries.insert(id, fields);
+        self.last_id = id;
+        self.entries_added += 1;
+    }
+
+    /// Removes an entry, returning whether it was there.
+    pub fn remove(&mut self, id: StreamId) -> bool {
+        if self.entries.remove(&id).is_none() {
+            return false;
+        }
+
+        self.max_deleted_id = self.max_deleted_id.max(id);
+        true
+    }
+
+    /// Removes the oldest entries until the stream fits `trim`. Returns how many were removed.
+    pub fn trim(&mut self, trim: StreamTrim) -> usize {
+        let len = self.entries.len();
+
+        match trim {
+            StreamTrim::MaxLen(max_len) => {
+                for _ in max_len..len {
+                    self.entries.pop_first();
+                }
+            }
+            StreamTrim::MinId(min_id) => self.entries = self.entries.split_off(&min_id),
+        }
+
+        len - self.entries.len()
+    }
+
+    /// Overwrites the last ID and counters, as XSETID does.
+    pub fn set_id(&mut self, last_id: StreamId, entries_added: u64, max_deleted_id: StreamId) {
+        self.last_id = last_id;
+        self.entries_added = entries_added;
+        self.max_deleted_id = max_deleted_id;
+    }
+
+    /// The entries with IDs in `start..=end`, from the newest down if `rev`, up to `count` of
+    /// them.
+    pub fn range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+        rev: bool,
+        count: Option<usize>,
+    ) -> Vec<(StreamId, Fields)> {
+        if start > end {
+            return vec![];
+        }
+
+        let range = self.entries.range(start..=end);
+        let count = count.unwrap_or(usize::MAX);
+        let entries: Box<dyn Iterator<Item = _>> = match rev {
+            true => Box::new(range.rev()),
+            false => Box::new(range),
+        };
+
+        entries
+            .take(count)
+            .map(|(id, fields)| (*id, fields.clone()))
+            .collect()
+    }
+
+    /// Every entry, from the oldest.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&StreamId, &Fields)> {
+        self.entries.iter()
+    }
+
+    pub fn group(&self, name: &[u8]) -> Option<&ConsumerGroup> {
+        self.groups.get(name)
+    }
+
+    pub fn group_mut(&mut self, name: &[u8]) -> Option<&mut ConsumerGroup> {
+        self.groups.get_mut(name)
+    }
+
+    /// Every consumer group, ordered by name.
+    pub fn groups(&self) -> impl Iterator<Item = (&Vec<u8>, &ConsumerGroup)> {
+        self.groups.iter()
+    }
+
+    /// Adds a consumer group unless there's one with the same name. Returns whether it was
+    /// added.
+    pub fn create_group(&mut self, name: Vec<u8>, group: ConsumerGroup) -> bool {
+        if self.groups.contains_key(&name) {
+            return false;
+        }
+
+        self.groups.insert(name, group);
+        true
+    }
+
+    pub fn remove_group(&mut self, name: &[u8]) -> bool {
+        self.groups.remove(name).is_some()
+    }
+
+    /// Moves a group past `id`, an entry being delivered to it, counting the entry as read.
+    pub fn advance_group(&mut self, name: &[u8], id: StreamId) {
+        let count_one_more = !self.has_tombstones_from(id);
+        let estimate = self.entries_read_until(id);
+
+        let Some(group) = self.groups.get_mut(name) else {
+            return;
+        };
+
+        if id <= group.last_id {
+            return;
+        }
+
+        group.entries_read = match group.entries_read {
+            Some(read) if count_one_more => Some(read + 1),
+            _ => estimate,
+        };
+        group.last_id = id;
+    }
+
+    /// How many entries were added after the group's last ID, if that can be known.
+    pub fn lag(&self, group: &ConsumerGroup) -> Option<u64> {
+        if self.entries_added == 0 {
+            return Some(0);
+        }
+
+        let entries_read = match group.entries_read {
+            Some(read) if !self.has_tombstones_from(group.last_id) => Some(read),
+            _ => self.entries_read_until(group.last_id),
+        };
+
+        entries_read.map(|read| self.entries_added.saturating_sub(read))
+    }
+
+    /// Whether entries were deleted from `start` on, which makes counting entries by ID
+    /// impossible.
+    fn has_tombstones_from(&self, start: StreamId) -> bool {
+        !self.entries.is_empty()
+            && self.max_deleted_id != StreamId::MIN
+            && start <= self.max_deleted_id
+    }
+
+    /// How many entries were ever added up to and including `id`, when it can be worked out
+    /// from the counters, like Redis does.
+    fn entries_read_until(&self, id: StreamId) -> Option<u64> {
+        if self.entries_added == 0 {
+            return Some(0);
+        }
+
+        if id >= self.last_id || (self.entries.is_empty() && id <= self.last_id) {
+            return (id <= self.last_id).then_some(self.entries_added);
+        }
+
+        let first_id = self.first_id();
+        let len = self.entries.len() as u64;
+
+        if self.max_deleted_id == StreamId::MIN || self.max_deleted_id < first_id {
+            if id < first_id {
+                return Some(self.entries_added - len);
+            }
+
+            if id == first_id {
+                return Some(self.entries_added - len + 1);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_ids() {
+        let mut stream = Stream::new();
+
+        assert_eq!(stream.next_id(XaddId::Auto, 100).unwrap(), id(100, 0));
+        stream.add(id(100, 0), vec![]);
+
+        // a clock that went backwards keeps the last millisecond
+        assert_eq!(stream.next_id(XaddId::Auto, 90).unwrap(), id(100, 1));
+        assert_eq!(
+            stream.next_id(XaddId::AutoSequence(100), 0).unwrap(),
+            id(100, 1)
+        );
+        assert_eq!(
+            stream.next_id(XaddId::AutoSequence(150), 0).unwrap(),
+            id(150, 0)
+        );
+
+        assert!(matches!(
+            stream.next_id(XaddId::AutoSequence(99), 0),
+            Err(StreamIdError::NotIncreasing)
+        ));
+    }
+
+    #[test]
+    fn explicit_ids() {
+        let mut stream = Stream::new();
+
+        assert!(matches!(
+            stream.next_id(XaddId::Explicit(id(0, 0)), 0),
+            Err(StreamIdError::Zero)
+        ));
+        assert_eq!(
+            stream.next_id(XaddId::AutoSequence(0), 0).unwrap(),
+            id(0, 1)
+        );
+
+        stream.add(id(5, 5), vec![]);
+        assert!(matches!(
+            stream.next_id(XaddId::Explicit(id(5, 5)), 0),
+            Err(StreamIdError::NotIncreasing)
+        ));
+
+        stream.set_id(StreamId::MAX, 1, StreamId::MIN);
+        assert!(matches!(
+            stream.next_id(XaddId::Auto, 0),
+            Err(StreamIdError::Exhausted)
+        ));
+    }
+
+    #[test]
+    fn ranges_and_trimming() {
+        let mut stream = Stream::new();
+
+        for ms in 1..=5 {
+            stream.add(id(ms, 0), vec![]);
+        }
+
+        let ids = |entries: Vec<(StreamId, Fields)>| -> Vec<u64> {
+            entries.into_iter().map(|(id, _)| id.ms).collect()
+        };
+
+        assert_eq!(
+            ids(stream.range(id(2, 0), id(4, 0), false, None)),
+            [2, 3, 4]
+        );
+        assert_eq!(
+            ids(stream.range(id(2, 0), StreamId::MAX, true, Some(2))),
+            [5, 4]
+        );
+        assert!(stream.range(id(4, 0), id(2, 0), false, None).is_empty());
+
+        assert!(stream.remove(id(5, 0)));
+        assert!(!stream.remove(id(5, 0)));
+        assert_eq!(stream.max_deleted_id(), id(5, 0));
+        assert_eq!(stream.last_id(), id(5, 0));
+
+        assert_eq!(stream.trim(StreamTrim::MinId(id(2, 0))), 1);
+        assert_eq!(stream.trim(StreamTrim::MaxLen(2)), 1);
+        assert_eq!(
+            ids(stream.range(StreamId::MIN, StreamId::MAX, false, None)),
+            [3, 4]
+        );
+        assert_eq!(stream.entries_added(), 5);
+    }
+
+    #[test]
+    fn group_lag() {
+        let mut stream = Stream::new();
+
+        for ms in 1..=5 {
+            stream.add(id(ms, 0), vec![]);
+        }
+
+        stream.create_group(b"new".to_vec(), ConsumerGroup::new(StreamId::MIN, None));
+        stream.create_group(b"done".to_vec(), ConsumerGroup::new(id(5, 0), None));
+        assert_eq!(stream.lag(stream.group(b"new").unwrap()), Some(5));
+        assert_eq!(stream.lag(stream.group(b"done").unwrap()), Some(0));
+
+        stream.advance_group(b"new", id(1, 0));
+        stream.advance_group(b"new", id(2, 0));
+        assert_eq!(stream.group(b"new").unwrap().entries_read, Some(2));
+        assert_eq!(stream.lag(stream.group(b"new").unwrap()), Some(3));
+
+        // a deleted entry ahead of the group makes its lag unknown
+        stream.remove(id(4, 0));
+        assert_eq!(stream.lag(stream.group(b"new").unwrap()), None);
+        assert_eq!(stream.lag(stream.group(b"done").unwrap()), Some(0));
+    }
+
+    fn id(ms: u64, seq: u64) -> StreamId {
+        StreamId { ms, seq }
+    }
+}
@@ -0,0 +1,186 @@
+use super::{
+    database::WrongType,
+    stream::{Fields, Stream},
+    CommandError, CommandResult, Server,
+};
+use crate::protocol::{Command, StreamId, StreamTrim, Value, XaddId};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+impl Server {
+    /// Appends an entry, propagated with the ID it got so replicas store the same one.
+    pub(super) fn xadd(
+        &mut self,
+        key: Vec<u8>,
+        nomkstream: bool,
+        trim: Option<StreamTrim>,
+        id: XaddId,
+        fields: Fields,
+    ) -> CommandResult {
+        // resolve the ID before creating the stream, so a rejected one leaves nothing behind
+        let id = match self.database.stream(&key)? {
+            Some(stream) => stream.next_id(id, now_millis())?,
+            None if nomkstream => {
+                self.propagate_as = Some(vec![]);
+                return Ok(vec![Value::NullBulkString]);
+            }
+            None => Stream::new().next_id(id, now_millis())?,
+        };
+
+        let stream = self.database.stream_or_insert(&key)?;
+        stream.add(id, fields.clone());
+
+        if let Some(trim) = trim {
+            stream.trim(trim);
+        }
+
+        self.propagate_as = Some(vec![Command::Xadd {
+            size: 0,
+            key,
+            nomkstream,
+            trim,
+            id: XaddId::Explicit(id),
+            fields,
+        }]);
+
+        Ok(vec![Value::bulk_string(&id.to_string())])
+    }
+
+    pub(super) fn xrange(
+        &mut self,
+        key: Vec<u8>,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+        rev: bool,
+    ) -> CommandResult {
+        let entries = match self.database.stream(&key)? {
+            Some(stream) => stream.range(start, end, rev, count),
+            None => vec![],
+        };
+
+        Ok(vec![entries_value(entries)])
+    }
+
+    pub(super) fn xlen(&mut self, key: Vec<u8>) -> CommandResult {
+        let len = self.database.stream(&key)?.map_or(0, |stream| stream.len());
+        Ok(vec![Value::integer(len as i64)])
+    }
+
+    pub(super) fn xdel(&mut self, key: Vec<u8>, ids: Vec<StreamId>) -> CommandResult {
+        let removed = match self.database.stream(&key)? {
+            Some(stream) => ids.into_iter().filter(|id| stream.remove(*id)).count(),
+            None => 0,
+        };
+
+        Ok(vec![Value::integer(removed as i64)])
+    }
+
+    pub(super) fn xtrim(&mut self, key: Vec<u8>, trim: StreamTrim) -> CommandResult {
+        let removed = match self.database.stream(&key)? {
+            Some(stream) => stream.trim(trim),
+            None => 0,
+        };
+
+        Ok(vec![Value::integer(removed as i64)])
+    }
+
+    pub(super) fn xsetid(
+        &mut self,
+        key: Vec<u8>,
+        last_id: StreamId,
+        entries_added: Option<u64>,
+        max_deleted_id: Option<StreamId>,
+    ) -> CommandResult {
+        let stream = self
+            .database
+            .stream(&key)?
+            .ok_or_else(|| CommandError::Other("ERR no such key".to_string()))?;
+
+        let error = |message: &str| Err(CommandError::Other(message.to_string()));
+
+        if max_deleted_id.is_some_and(|max_deleted_id| max_deleted_id > last_id) {
+            return error(
+                "ERR The ID specified in XSETID is smaller than the provided max_deleted_entry_id",
+            );
+        }
+
+        if entries_added.is_some_and(|entries_added| entries_added < stream.len() as u64) {
+            return error(
+                "ERR The entries_added specified in XSETID is smaller than the target stream length",
+            );
+        }
+
+        if stream.top_id().is_some_and(|top_id| top_id > last_id) {
+            return error(
+                "ERR The ID specified in XSETID is smaller than the target stream top item",
+            );
+        }
+
+        stream.set_id(
+            last_id,
+            entries_added.unwrap_or(stream.entries_added()),
+            max_deleted_id.unwrap_or(stream.max_deleted_id()),
+        );
+
+        Ok(vec![Value::ok()])
+    }
+
+    /// Reads the entries after `ids[i]` from the stream at `keys[i]`, as `[key, entries]` pairs
+    /// for the streams that had any. Returns `None` if none did.
+    pub(super) fn read_streams(
+        &mut self,
+        keys: &[Vec<u8>],
+        ids: &[StreamId],
+        count: Option<usize>,
+    ) -> Result<Option<Value>, WrongType> {
+        let mut streams = vec![];
+
+        for (key, id) in keys.iter().zip(ids) {
+            let entries = match (self.database.stream(key)?, id.next()) {
+                (Some(stream), Some(start)) => stream.range(start, StreamId::MAX, false, count),
+                _ => continue,
+            };
+
+            if !entries.is_empty() {
+                let key = Value::bulk_string_from_bytes(key.clone());
+                streams.push(Value::array(vec![key, entries_value(entries)]));
+            }
+        }
+
+        Ok((!streams.is_empty()).then(|| Value::array(streams)))
+    }
+}
+
+/// Replies with entries as `[id, [field, value, ...]]` pairs.
+pub(super) fn entries_value(entries: Vec<(StreamId, Fields)>) -> Value {
+    Value::array(
+        entries
+            .into_iter()
+            .map(|(id, fields)| entry_value(id, Some(fields)))
+            .collect(),
+    )
+}
+
+/// Replies with an entry as an `[id, [field, value, ...]]` pair, or `[id, nil]` for an entry
+/// that was deleted.
+pub(super) fn entry_value(id: StreamId, fields: Option<Fields>) -> Value {
+    let fields = match fields {
+        Some(fields) => Value::array(
+            fields
+                .into_iter()
+                .flat_map(|(field, value)| [field, value])
+                .map(Value::bulk_string_from_bytes)
+                .collect(),
+        ),
+        None => Value::NullArray,
+    };
+
+    Value::array(vec![Value::bulk_string(&id.to_string()), fields])
+}
+
+pub(super) fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
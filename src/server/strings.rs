@@ -0,0 +1,313 @@
+use super::{number, CommandError, CommandResult, Server};
+use crate::protocol::{Command, ExpireFlags, GetexExpiry, SetCondition, SetExpiry, Value};
+
+impl Server {
+    pub(super) fn get(&mut self, key: Vec<u8>) -> CommandResult {
+        let value = self.database.get(&key)?;
+        Ok(vec![bulk_or_null(value)])
+    }
+
+    /// Sets a string unless the NX or XX condition fails, in which case nothing is written or
+    /// propagated. With `get`, replies with the previous string instead of OK.
+    pub(super) fn set(
+        &mut self,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        condition: Option<SetCondition>,
+        expiry: Option<SetExpiry>,
+        get: bool,
+    ) -> CommandResult {
+        let previous = if get { self.database.get(&key)? } else { None };
+        let exists = self.database.type_name(&key).is_some();
+
+        let reply = match get {
+            true => bulk_or_null(previous),
+            false => Value::ok(),
+        };
+
+        match (condition, exists) {
+            (Some(SetCondition::Nx), true) | (Some(SetCondition::Xx), false) => {
+                self.propagate_as = Some(vec![]);
+                return Ok(vec![if get { reply } else { Value::NullBulkString }]);
+            }
+            _ => {}
+        }
+
+        match expiry {
+            Some(SetExpiry::At(deadline)) => self.database.set(key, value, Some(deadline)),
+            Some(SetExpiry::KeepTtl) => self.database.set_keep_ttl(key, value),
+            None => self.database.set(key, value, None),
+        }
+
+        Ok(vec![reply])
+    }
+
+    /// Adds to the integer stored at `key`, as INCR, DECR, INCRBY and DECRBY all do. A missing
+    /// key counts as 0, and an existing one keeps its time to live.
+    pub(super) fn incr_by(&mut self, key: Vec<u8>, increment: i64) -> CommandResult {
+        let current = match self.database.get(&key)? {
+            Some(value) => number::parse_integer(&value).ok_or_else(|| {
+                CommandError::Other("ERR value is not an integer or out of range".to_string())
+            })?,
+            None => 0,
+        };
+
+        let value = current.checked_add(increment).ok_or_else(|| {
+            CommandError::Other("ERR increment or decrement would overflow".to_string())
+        })?;
+
+        self.database
+            .set_keep_ttl(key, value.to_string().into_bytes());
+        Ok(vec![Value::integer(value)])
+    }
+
+    pub(super) fn decr_by(&mut self, key: Vec<u8>, decrement: i64) -> CommandResult {
+        match decrement.checked_neg() {
+            Some(increment) => self.incr_by(key, increment),
+            None => Err(CommandError::Other(
+                "ERR decrement would overflow".to_string(),
+            )),
+        }
+    }
+
+    /// Increments a string by a float, propagated as a SET of the result so replicas don't
+    /// depend on their own float arithmetic.
+    pub(super) fn incrbyfloat(&mut self, key: Vec<u8>, increment: f64) -> CommandResult {
+        let current = match self.database.get(&key)? {
+            Some(value) => number::parse_float(&value)
+                .ok_or_else(|| CommandError::Other("ERR value is not a valid float".to_string()))?,
+            None => 0.0,
+        };
+
+        let value = Some(current + increment)
+            .filter(|value| value.is_finite())
+            .ok_or_else(|| {
+                CommandError::Other("ERR increment would produce NaN or Infinity".to_string())
+            })?;
+
+        let value = number::format_float(value).into_bytes();
+        self.database.set_keep_ttl(key.clone(), value.clone());
+
+        self.propagate_as = Some(vec![Command::Set {
+            size: 0,
+            key,
+            value: value.clone(),
+            condition: None,
+            expiry: Some(SetExpiry::KeepTtl),
+            get: false,
+        }]);
+
+        Ok(vec![Value::bulk_string_from_bytes(value)])
+    }
+
+    pub(super) fn append(&mut self, key: Vec<u8>, value: Vec<u8>) -> CommandResult {
+        let len = match self.database.string(&key)? {
+            Some(string) => {
+                string.extend_from_slice(&value);
+                string.len()
+            }
+            None => {
+                let len = value.len();
+                self.database.set(key, value, None);
+                len
+            }
+        };
+
+        Ok(vec![Value::integer(len as i64)])
+    }
+
+    pub(super) fn strlen(&mut self, key: Vec<u8>) -> CommandResult {
+        let len = self.database.string(&key)?.map_or(0, |string| string.len());
+        Ok(vec![Value::integer(len as i64)])
+    }
+
+    pub(super) fn getrange(&mut self, key: Vec<u8>, start: i64, end: i64) -> CommandResult {
+        let value = match self.database.string(&key)? {
+            Some(string) => substring(string, start, end).to_vec(),
+            None => vec![],
+        };
+
+        Ok(vec![Value::bulk_string_from_bytes(value)])
+    }
+
+    /// Overwrites part of a string, padding it with zero bytes when `offset` is past its end.
+    /// An empty `value` changes nothing, and doesn't create the key.
+    pub(super) fn setrange(
+        &mut self,
+        key: Vec<u8>,
+        offset: usize,
+        value: Vec<u8>,
+    ) -> CommandResult {
+        if value.is_empty() {
+            self.propagate_as = Some(vec![]);
+        }
+
+        let len = match self.database.string(&key)? {
+            Some(string) => {
+                overwrite(string, offset, &value);
+                string.len()
+            }
+            None if value.is_empty() => 0,
+            None => {
+                let mut string = vec![];
+                overwrite(&mut string, offset, &value);
+
+                let len = string.len();
+                self.database.set(key, string, None);
+                len
+            }
+        };
+
+        Ok(vec![Value::integer(len as i64)])
+    }
+
+    pub(super) fn getdel(&mut self, key: Vec<u8>) -> CommandResult {
+        let value = self.database.get(&key)?;
+
+        match value {
+            Some(_) => {
+                self.database.remove(&key);
+            }
+            None => self.propagate_as = Some(vec![]),
+        }
+
+        Ok(vec![bulk_or_null(value)])
+    }
+
+    /// Gets a string and changes its time to live, propagated as a PEXPIREAT or PERSIST.
+    /// Without an option it's only a read, so it isn't propagated.
+    pub(super) fn getex(&mut self, key: Vec<u8>, expiry: Option<GetexExpiry>) -> CommandResult {
+        let value = self.database.get(&key)?;
+
+        let propagate_as = match (&value, expiry) {
+            (Some(_), Some(GetexExpiry::At(deadline))) => {
+                self.database.set_expires_at(&key, Some(deadline));
+
+                vec![Command::Expire {
+                    size: 0,
+                    key,
+                    deadline,
+                    flags: ExpireFlags::default(),
+                }]
+            }
+            (Some(_), Some(GetexExpiry::Persist)) => {
+                self.database.set_expires_at(&key, None);
+                vec![Command::Persist { size: 0, key }]
+            }
+            _ => vec![],
+        };
+
+        self.propagate_as = Some(propagate_as);
+
+        Ok(vec![bulk_or_null(value)])
+    }
+
+    pub(super) fn getset(&mut self, key: Vec<u8>, value: Vec<u8>) -> CommandResult {
+        let previous = self.database.get(&key)?;
+        self.database.set(key, value, None);
+        Ok(vec![bulk_or_null(previous)])
+    }
+
+    /// Gets several strings, with a null for keys that are missing or hold another type.
+    pub(super) fn mget(&mut self, keys: Vec<Vec<u8>>) -> CommandResult {
+        let values = keys
+            .iter()
+            .map(|key| bulk_or_null(self.database.get(key).ok().flatten()))
+            .collect();
+
+        Ok(vec![Value::array(values)])
+    }
+
+    pub(super) fn mset(&mut self, pairs: Vec<(Vec<u8>, Vec<u8>)>) -> CommandResult {
+        for (key, value) in pairs {
+            self.database.set(key, value, None);
+        }
+
+        Ok(vec![Value::ok()])
+    }
+
+    pub(super) fn msetnx(&mut self, pairs: Vec<(Vec<u8>, Vec<u8>)>) -> CommandResult {
+        if pairs
+            .iter()
+            .any(|(key, _)| self.database.type_name(key).is_some())
+        {
+            self.propagate_as = Some(vec![]);
+            return Ok(vec![Value::integer(0)]);
+        }
+
+        self.mset(pairs)?;
+        Ok(vec![Value::integer(1)])
+    }
+
+    pub(super) fn setnx(&mut self, key: Vec<u8>, value: Vec<u8>) -> CommandResult {
+        self.msetnx(vec![(key, value)])
+    }
+}
+
+/// The bytes from `start` to `end` inclusive, where negative indexes count from the end, as
+/// GETRANGE takes them.
+fn substring(string: &[u8], start: i64, end: i64) -> &[u8] {
+    let len = string.len() as i64;
+
+    if len == 0 || (start < 0 && end < 0 && start > end) {
+        return &[];
+    }
+
+    let start = if start < 0 { len + start } else { start }.max(0);
+    let end = if end < 0 { len + end } else { end }.clamp(0, len - 1);
+
+    if start > end {
+        return &[];
+    }
+
+    &string[start as usize..=end as usize]
+}
+
+/// Writes `value` into `string` at `offset`, growing it with zero bytes as needed.
+fn overwrite(string: &mut Vec<u8>, offset: usize, value: &[u8]) {
+    let end = offset + value.len();
+
+    if string.len() < end {
+        string.resize(end, 0);
+    }
+
+    string[offset..end].copy_from_slice(value);
+}
+
+fn bulk_or_null(value: Option<Vec<u8>>) -> Value {
+    match value {
+        Some(value) => Value::bulk_string_from_bytes(value),
+        None => Value::NullBulkString,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substrings() {
+        let string = "This is a string".as_bytes();
+
+        assert_eq!(substring(string, 0, 3), "This".as_bytes());
+        assert_eq!(substring(string, -3, -1), "ing".as_bytes());
+        assert_eq!(substring(string, 0, -1), string);
+        assert_eq!(substring(string, 10, 100), "string".as_bytes());
+        assert_eq!(substring(string, -100, 3), "This".as_bytes());
+        assert!(substring(string, 5, 2).is_empty());
+        assert!(substring(string, -1, -3).is_empty());
+        assert!(substring(string, 100, 200).is_empty());
+        assert!(substring(&[], 0, -1).is_empty());
+    }
+
+    #[test]
+    fn overwrite_pads_with_zeros() {
+        let mut string = "Hello World".as_bytes().to_vec();
+        overwrite(&mut string, 6, "Redis".as_bytes());
+        assert_eq!(string, "Hello Redis".as_bytes());
+
+        let mut string = vec![];
+        overwrite(&mut string, 2, "ab".as_bytes());
+        assert_eq!(string, [0, 0, b'a', b'b']);
+    }
+}